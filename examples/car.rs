@@ -1,5 +1,4 @@
-use rand::Rng;
-use std::f32::consts::{FRAC_PI_2, PI};
+use std::f32::consts::FRAC_PI_2;
 
 use oge::{Angle, Oge, Script, Vector2};
 
@@ -12,8 +11,8 @@ impl Script for WindowHandler {
 
     fn update(&mut self, oge: &mut Oge) {
         if oge.window_has_resized() {
-            let top_right = oge.window_dimensions().as_vector2().scale(0.5);
-            let bottom_left = top_right.scale(-1.);
+            let top_right = oge.window_dimensions().as_vector2() * 0.5;
+            let bottom_left = -top_right;
             oge.set_window_bounds(oge::Bounds {
                 bottom_left,
                 top_right,
@@ -22,91 +21,9 @@ impl Script for WindowHandler {
     }
 }
 
-struct Particle {
-    position: Vector2,
-    velocity: Vector2,
-    opacity: f32,
-    size: f32,
-}
-
-impl Particle {
-    fn update(&mut self, delta_time: f32) {
-        let drag = (self.velocity.x.powi(2) + self.velocity.y.powi(2)) * 0.005;
-        let acceleration = &self.velocity.with_magnitude(-drag);
-        self.velocity.add_assign(&acceleration.scale(delta_time));
-        self.position.add_assign(&self.velocity.scale(delta_time));
-        self.opacity -= 0.5 * delta_time;
-    }
-}
-
-struct ParticleHandler {
-    sprite: oge::Sprite,
-    particles: Vec<Particle>,
-    max_count: usize,
-}
-
-impl ParticleHandler {
-    fn new(sprite: oge::Sprite, max_particles: usize) -> Self {
-        Self {
-            particles: Vec::with_capacity(max_particles),
-            sprite,
-            max_count: max_particles,
-        }
-    }
-
-    fn spawn(&mut self, rough_position: Vector2, rough_velocity: Vector2, count: u32) {
-        let mut rng = rand::thread_rng();
-
-        for _ in 0..count {
-            // 0.9 -> 1.1
-            let magnitude = rough_velocity.magnitude() * rng.gen::<f32>();
-            // ~-23deg -> ~23deg
-            let direction = rough_velocity
-                .direction()
-                .rotate(rng.gen::<f32>() * FRAC_PI_2)
-                .rotate(-FRAC_PI_2 * 0.5);
-            self.particles.push(Particle {
-                position: rough_position.add(&Vector2::new(
-                    60. * (rng.gen::<f32>() - 0.5),
-                    60. * (rng.gen::<f32>() - 0.5),
-                )),
-                velocity: Vector2::new_euclidean(direction, magnitude),
-                opacity: rng.gen::<f32>() * 0.5 + 0.5,
-                size: rng.gen::<f32>() * 3. + 0.5,
-            });
-            if self.particles.len() >= self.max_count {
-                break;
-            }
-        }
-    }
-
-    fn update(&mut self, delta_time: f32) {
-        let mut remove_indices = Vec::<usize>::with_capacity(self.particles.capacity());
-        for (i, particle) in self.particles.iter_mut().enumerate() {
-            particle.update(delta_time);
-            if particle.opacity <= 0. {
-                remove_indices.push(i);
-            }
-        }
-        for i in remove_indices.into_iter().rev() {
-            self.particles.swap_remove(i);
-        }
-    }
-
-    fn draw_particles(&mut self, oge: &mut Oge) {
-        for particle in self.particles.iter() {
-            self.sprite.set_position(particle.position);
-            self.sprite.set_opacity(particle.opacity);
-            self.sprite
-                .set_transformation(oge::Matrix2::scale(particle.size));
-            oge.draw_once(&self.sprite);
-        }
-    }
-}
-
 struct Car {
     car_sprite: oge::Sprite,
-    particle_handler: ParticleHandler,
+    dust_emitter: oge::ParticleEmitter,
     is_drifting: bool,
     position: Vector2,
     velocity: Vector2,
@@ -131,19 +48,25 @@ impl Script for Car {
             z_index: oge::ZIndex::Specific(1),
             opacity: 1.,
             texture_projection_method: oge::TextureProjectionMethod::ScaleToFit,
+            normal_map: None,
         })?;
 
-        let dust_particle_sprite = oge.create_sprite(oge::SpriteConfiguration {
-            label: Some("Dust Particle Sprite"),
-            mesh: oge::SpriteMesh::new_elipse(4., 4., 8),
-            default_texture: oge.create_texture(&oge::TextureConfiguration::color(
-                oge::Color::new(0.4, 0.4, 0.4, 1.),
-            ))?,
-            z_index: oge::ZIndex::Specific(0),
-            opacity: 1.,
-            texture_projection_method: oge::TextureProjectionMethod::SingleColor,
-        })?;
-        let particle_handler = ParticleHandler::new(dust_particle_sprite, 4096);
+        let dust_emitter = oge::ParticleEmitter::new(oge::ParticleEmitterConfiguration {
+            label: Some("Dust Particle Emitter"),
+            mesh: oge::SpriteMesh::new_elipse(1., 1., 8),
+            texture: oge.create_texture(&oge::TextureConfiguration::color(oge::Color::new(
+                0.4, 0.4, 0.4, 1.,
+            )))?,
+            max_count: 4096,
+            emission_rate: 0.,
+            lifetime: (1., 2.),
+            spread: FRAC_PI_2,
+            spawn_radius: 30.,
+            size: (2., 14.),
+            drag: 1.,
+            opacity_over_lifetime: oge::LifetimeCurve::linear(0.75, 0.),
+            ..oge::ParticleEmitterConfiguration::default(oge)?
+        });
 
         let velocity = Vector2::ZERO;
         let direction = 0.;
@@ -152,7 +75,7 @@ impl Script for Car {
 
         Ok(Self {
             car_sprite,
-            particle_handler,
+            dust_emitter,
             velocity,
             direction,
             torque,
@@ -194,47 +117,45 @@ impl Script for Car {
         }
 
         // oge.draw_debug_arrow(
-        //     vec![self.position, relative_y_velocity.add(&self.position)],
+        //     vec![self.position, self.position + relative_y_velocity],
         //     None,
         // );
         // oge.draw_debug_arrow(
-        //     vec![self.position, relative_x_velocity.add(&self.position)],
+        //     vec![self.position, self.position + relative_x_velocity],
         //     None,
         // );
         // oge.draw_debug_arrow(
         //     vec![
         //         self.position,
-        //         relative_y_velocity
-        //             .add(&relative_x_velocity)
-        //             .add(&self.position),
+        //         self.position + relative_x_velocity + relative_y_velocity,
         //     ],
         //     Some((Some(oge::Color::BLUE), None, None)),
         // );
 
         let mut acceleration = if oge.get_key_down(oge::KeyCode::W) {
-            direction_vector.scale(1200.)
+            direction_vector * 1200.
         } else {
             Vector2::ZERO
         };
-        let friction_x = relative_x_velocity
-            .scale(relative_x_velocity.magnitude() * 0.2)
-            .scale(if self.is_drifting { -0.005 } else { -0.1 });
-        let friction = relative_y_velocity.scale(-0.5).add(&friction_x);
-        acceleration.add_assign(&friction);
+        let drift = if self.is_drifting { -0.005 } else { -0.1 };
+        let friction_x = relative_x_velocity * (relative_x_velocity.magnitude() * 0.2 * drift);
+        let friction = relative_y_velocity * -0.5 + friction_x;
+        acceleration += friction;
 
         /* particles */
         {
             let count = (0.000001 * (oge.delta_time() * friction_x.magnitude()).powi(3)) as u32;
-            let position = self.position;
-            let rough_velocity = friction.scale(-0.08);
-            self.particle_handler.spawn(position, rough_velocity, count);
-            self.particle_handler.update(oge.delta_time());
+            let rough_velocity = friction * -0.08;
+            self.dust_emitter.set_position(self.position);
+            self.dust_emitter.set_direction(rough_velocity.direction());
+            self.dust_emitter
+                .set_speed((0., rough_velocity.magnitude()));
+            self.dust_emitter.emit(count);
+            self.dust_emitter.update(oge.delta_time());
         }
 
-        self.velocity
-            .add_assign(&acceleration.scale(oge.delta_time()));
-        self.position
-            .add_assign(&self.velocity.scale(oge.delta_time()));
+        self.velocity += acceleration * oge.delta_time();
+        self.position += self.velocity * oge.delta_time();
 
         {
             let window_bounds = oge.window_bounds();
@@ -261,7 +182,7 @@ impl Script for Car {
         self.car_sprite
             .set_transformation(oge::Matrix2::rotation(self.direction));
         oge.draw_once(&self.car_sprite);
-        self.particle_handler.draw_particles(oge);
+        oge.draw_once(&self.dust_emitter);
     }
 }
 
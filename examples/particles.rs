@@ -1,45 +1,29 @@
 use oge::{Oge, Script};
 use rand::Rng;
-use std::{
-    f32::consts::PI,
-    time::{Duration, Instant},
-};
-
-struct Particle {
-    position: oge::Vector2,
-    velocity: oge::Vector2,
-    opacity: f32,
-}
-
-impl Particle {
-    fn update(&mut self, delta_time: f32) {
-        let mut acceleration = oge::Vector2::new(0.0, -1000.);
-        let drag = (self.velocity.x.powi(2) + self.velocity.y.powi(2)) * 0.001;
-        acceleration.sub_assign(&self.velocity.with_magnitude(drag));
-
-        self.velocity.add_assign(&acceleration.scale(delta_time));
-        self.position.add_assign(&self.velocity.scale(delta_time));
-        self.opacity -= 0.2 * delta_time;
-    }
-}
+use std::f32::consts::PI;
 
 struct ParticleEffects {
-    particle_sprite: oge::Sprite,
-    particles: Vec<Particle>,
+    emitter: oge::ParticleEmitter,
 }
 
 impl Script for ParticleEffects {
     fn start(oge: &mut Oge) -> oge::Result<Self> {
         let script = Self {
-            particle_sprite: oge.create_sprite(oge::SpriteConfiguration {
+            emitter: oge::ParticleEmitter::new(oge::ParticleEmitterConfiguration {
                 label: Some("Particle"),
-                mesh: oge::SpriteMesh::new_elipse(4., 4., 8),
-                default_texture: oge.create_texture(&oge::TextureConfiguration::color(oge::Color::from_rgba8(
-                    220, 20, 20, 255,
-                )))?,
-                ..oge::SpriteConfiguration::default(oge)?
-            })?,
-            particles: vec![],
+                mesh: oge::SpriteMesh::new_elipse(1., 1., 8),
+                texture: oge.create_texture(&oge::TextureConfiguration::color(
+                    oge::Color::from_rgba8(220, 20, 20, 255),
+                ))?,
+                emission_rate: 0.,
+                lifetime: (5., 5.),
+                speed: (100., 300.),
+                spread: PI,
+                size: (4., 4.),
+                gravity: oge::Vector2::new(0., -1000.),
+                drag: 0.5,
+                ..oge::ParticleEmitterConfiguration::default(oge)?
+            }),
         };
         Ok(script)
     }
@@ -59,38 +43,16 @@ impl Script for ParticleEffects {
             > 0
         {
             let mut rng = rand::thread_rng();
-            for _ in 0..((rng.gen::<u8>() >> 3) + 4) {
-                self.particles.push(Particle {
-                    position: oge.get_real_position(&oge.cursor_position()),
-                    velocity: {
-                        oge::Vector2::new_euclidean(
-                            PI * (rng.gen::<f32>() - 0.5),
-                            rng.gen::<f32>() * 200. + 100.,
-                        )
-                    },
-                    opacity: 1.,
-                })
-            }
-        }
-
-        for particle in self.particles.iter_mut() {
-            particle.update(oge.delta_time());
+            self.emitter
+                .set_position(oge.get_real_position(&oge.cursor_position()));
+            self.emitter.emit(((rng.gen::<u8>() >> 3) + 4) as u32);
         }
 
-        for i in (0..self.particles.len()).rev() {
-            let ref particle = self.particles[i];
-            if particle.opacity < 0. {
-                self.particles.remove(i);
-            }
-        }
+        self.emitter.update(oge.delta_time());
     }
 
     fn render(&mut self, oge: &mut Oge) {
-        for particle in self.particles.iter() {
-            self.particle_sprite.set_position(particle.position);
-            self.particle_sprite.set_opacity(particle.opacity);
-            oge.draw_once(&self.particle_sprite);
-        }
+        oge.draw_once(&self.emitter);
     }
 }
 
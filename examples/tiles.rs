@@ -1,21 +1,83 @@
-// use oge::
+use oge::{Oge, Script};
 
-// struct Tile {
-//     placed: bool,
-// }
+struct WindowHandler;
 
-// struct Chunk {
-//     tiles: Box<[[Tile; 16]; 16]>,
-// }
+impl Script for WindowHandler {
+    fn start(_: &mut Oge) -> oge::Result<Self> {
+        Ok(Self)
+    }
 
-// struct TilesScript {
-//     chunk: Chunk,
-// }
+    fn update(&mut self, oge: &mut Oge) {
+        if oge.window_has_resized() {
+            let top_right = oge.window_dimensions().as_vector2().scale(0.5);
+            oge.set_window_bounds(oge::Bounds {
+                bottom_left: top_right.scale(-1.),
+                top_right,
+            });
+        }
+    }
+}
 
-// impl 
+struct Tiles {
+    tilemap: oge::Tilemap,
+    next_tile: oge::TileId,
+}
 
-// fn main() {
-    
-// }
+impl Script for Tiles {
+    fn start(oge: &mut Oge) -> oge::Result<Self> {
+        let tileset = oge::Tileset::new(oge::TilesetConfiguration {
+            texture: oge.create_texture(&oge::TextureConfiguration {
+                source: oge::TextureSource::Bytes(include_bytes!("./test-texture.png")),
+                filter_mode: oge::FilterMode::Point,
+                ..Default::default()
+            })?,
+            columns: 2,
+            rows: 2,
+        });
+        let mut tilemap = oge::Tilemap::new(oge::TilemapConfiguration {
+            label: Some("Tiles"),
+            tileset,
+            tile_size: oge::Vector2::new(32., 32.),
+            origin: oge::Vector2::ZERO,
+            z_index: oge::ZIndex::default(),
+            opacity: 1.,
+        });
 
-fn main() {}
\ No newline at end of file
+        for x in -20..20 {
+            for y in -12..-8 {
+                tilemap.set_tile(x, y, Some(((x + y).rem_euclid(4)) as oge::TileId));
+            }
+        }
+
+        Ok(Self {
+            tilemap,
+            next_tile: 0,
+        })
+    }
+
+    fn update(&mut self, oge: &mut Oge) {
+        let (x, y) = self
+            .tilemap
+            .world_to_tile(&oge.get_real_cursor_position());
+
+        if oge
+            .get_mouse_button_status(oge::MouseButtonCode::Left)
+            .just_pressed()
+        {
+            self.tilemap.set_tile(x, y, Some(self.next_tile));
+            self.next_tile = (self.next_tile + 1) % self.tilemap.tileset().tile_count();
+        }
+        if oge.get_mouse_button_down(oge::MouseButtonCode::Right) {
+            self.tilemap.set_tile(x, y, None);
+        }
+    }
+
+    fn render(&mut self, oge: &mut Oge) {
+        let render_bundles = self.tilemap.render_bundles(oge);
+        oge.draw(render_bundles);
+    }
+}
+
+fn main() -> oge::Result<()> {
+    oge::main_loop::start([WindowHandler::load_script(), Tiles::load_script()])
+}
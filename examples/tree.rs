@@ -18,6 +18,7 @@ impl Script for Tree {
                 z_index: oge::ZIndex::AboveAll,
                 opacity: 1.,
                 texture_projection_method: oge::TextureProjectionMethod::ScaleToFit,
+                normal_map: None,
             })?,
         };
         Ok(tree)
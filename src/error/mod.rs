@@ -13,6 +13,9 @@ macro_rules! impl_display {
 pub enum Error {
     Texture(TextureError),
     Render(RenderError),
+    Tiled(TiledError),
+    Profiler(ProfilerError),
+    Svg(SvgError),
 }
 
 pub struct TextureError {
@@ -75,4 +78,88 @@ impl RenderError {
     }
 }
 
+/// Returned if a Tiled map or tileset could not be loaded.
+pub struct TiledError {
+    description: String,
+}
+
+impl_display!(TiledError);
+
+impl fmt::Debug for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl TiledError {
+    pub(crate) fn open(path_buf: &PathBuf) -> Error {
+        Error::Tiled(TiledError {
+            description: format!("could not open tiled file {:?}", path_buf),
+        })
+    }
+
+    pub(crate) fn parse(path_buf: &PathBuf, reason: &str) -> Error {
+        Error::Tiled(TiledError {
+            description: format!("could not parse tiled file {:?}: {}", path_buf, reason),
+        })
+    }
+
+    pub(crate) fn unsupported(feature: &str) -> Error {
+        Error::Tiled(TiledError {
+            description: format!("tiled feature is not supported: {}", feature),
+        })
+    }
+}
+
+/// Returned if the profiler could not be configured.
+pub struct ProfilerError {
+    description: String,
+}
+
+impl_display!(ProfilerError);
+
+impl fmt::Debug for ProfilerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl ProfilerError {
+    pub(crate) fn create_trace(path_buf: &PathBuf) -> Error {
+        Error::Profiler(ProfilerError {
+            description: format!("could not create trace file {:?}", path_buf),
+        })
+    }
+}
+
+/// Returned if an SVG file could not be loaded.
+pub struct SvgError {
+    description: String,
+}
+
+impl_display!(SvgError);
+
+impl fmt::Debug for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl SvgError {
+    pub(crate) fn open(path_buf: &PathBuf) -> Error {
+        Error::Svg(SvgError {
+            description: format!("could not open svg file {:?}", path_buf),
+        })
+    }
+
+    pub(crate) fn parse(path_buf: Option<&PathBuf>, reason: &str) -> Error {
+        Error::Svg(SvgError {
+            description: match path_buf {
+                Some(path_buf) => format!("could not parse svg file {:?}: {}", path_buf, reason),
+                None => format!("could not parse svg: {}", reason),
+            },
+        })
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
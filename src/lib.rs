@@ -16,6 +16,9 @@ usemod![
     pub oge_controller,
     pub physics,
     pub sprite,
+    pub tilemap,
+    pub particles,
+    pub svg,
     pub error,
     pub(crate) render_state,
 ];
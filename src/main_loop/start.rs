@@ -1,5 +1,4 @@
 use crate::*;
-use wgpu::QuerySetDescriptor;
 use winit::{
     dpi::{PhysicalSize, Size},
     event::*,
@@ -42,7 +41,7 @@ pub fn start<I: IntoIterator<Item = LoadedScript>>(scripts: I) -> Result<()> {
 
     // Run start() on all scripts
     let mut scripts: Vec<Box<dyn DynScript>> = {
-        let mut render_pass_resources = render_state.create_render_pass_resources()?;
+        let mut render_pass_resources = render_state.create_render_pass_resources(false)?;
         let mut oge = Oge::new(
             &mut oge_handlers,
             &mut render_state,
@@ -115,10 +114,13 @@ pub fn start<I: IntoIterator<Item = LoadedScript>>(scripts: I) -> Result<()> {
             },
 
             Event::RedrawRequested(_) => {
-                let mut render_pass_resources = match render_state.create_render_pass_resources() {
-                    Ok(resources) => resources,
-                    Err(_) => break 'event_handler,
-                };
+                oge_handlers.profiler.begin_frame();
+                let gpu_timing = oge_handlers.profiler.gpu_timing();
+                let mut render_pass_resources =
+                    match render_state.create_render_pass_resources(gpu_timing) {
+                        Ok(resources) => resources,
+                        Err(_) => break 'event_handler,
+                    };
                 let mut oge = Oge::new(
                     &mut oge_handlers,
                     &mut render_state,
@@ -126,17 +128,31 @@ pub fn start<I: IntoIterator<Item = LoadedScript>>(scripts: I) -> Result<()> {
                 );
 
                 oge.handlers.meta_handler.update();
+                oge.handlers.light_handler.update();
+                oge.handlers.profiler.begin_phase();
                 for script in scripts.iter_mut() {
                     script.update(&mut oge);
                 }
+                oge.handlers.profiler.end_phase(FramePhase::Update);
+                oge.handlers.profiler.begin_phase();
                 for script in scripts.iter_mut() {
                     script.render(&mut oge);
                 }
+                oge.draw_debug_shapes();
+                oge.handlers.profiler.end_phase(FramePhase::Render);
                 oge.handlers.input_handler.update();
 
-                let (render_pass, queued_operations) = oge.finish();
+                let (mut render_pass, queued_operations) = oge.finish();
+                let render_bundle_count = render_pass.render_bundles.len();
+                oge_handlers.profiler.begin_phase();
+                render_pass.sort_render_bundles();
+                oge_handlers.profiler.end_phase(FramePhase::Sorting);
+                oge_handlers.profiler.begin_phase();
                 render_pass.draw_render_bundles(&mut render_state);
-                render_pass_resources.finish(&render_state);
+                render_pass_resources.finish(&mut render_state);
+                oge_handlers.profiler.end_phase(FramePhase::Submission);
+                let gpu_time = render_state.read_gpu_time();
+                oge_handlers.profiler.end_frame(gpu_time, render_bundle_count);
                 
                 for operation in queued_operations {
                     match operation {
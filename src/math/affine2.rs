@@ -1,4 +1,5 @@
 use crate::*;
+use std::ops::{Mul, MulAssign};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -8,6 +9,19 @@ pub struct Affine2 {
     pub translation: Vector2,
 }
 
+/// The parts of an `Affine2`, which are applied in order: `sheer`, then `stretch`, then
+/// `rotation`, then `translation`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AffineParts {
+    pub translation: Vector2,
+    /// The rotation in radians, the same as for `Matrix2::rotation`
+    pub rotation: f32,
+    /// The scale in the x and y directions, the same as for `Matrix2::stretch`
+    pub stretch: Vector2,
+    /// The sheer in the x direction, the same as for `Matrix2::sheer_x`
+    pub sheer: f32,
+}
+
 impl Default for Affine2 {
     fn default() -> Self {
         Affine2 {
@@ -26,6 +40,104 @@ impl Affine2 {
             translation: k,
         }
     }
+
+    /// Creates a matrix that moves points by `translation`
+    pub const fn translation(translation: Vector2) -> Self {
+        Self::new(Vector2::RIGHT, Vector2::UP, translation)
+    }
+
+    /// Creates a matrix that represents a rotation of `angle` radians around the origin
+    pub fn rotation(angle: f32) -> Self {
+        Matrix2::rotation(angle).into()
+    }
+
+    /// Creates a matrix that scales points away from the origin by `stretch_x` in the
+    /// x-direction and `stretch_y` in the y-direction
+    pub const fn stretch(stretch_x: f32, stretch_y: f32) -> Self {
+        Self::new(
+            Vector2::new(stretch_x, 0.0),
+            Vector2::new(0.0, stretch_y),
+            Vector2::ZERO,
+        )
+    }
+
+    /// Creates a matrix that scales points away from the origin by `factor`
+    pub const fn scale(factor: f32) -> Self {
+        Self::stretch(factor, factor)
+    }
+
+    /// Creates a matrix that sheers in the +- x-direction by `shift`
+    pub const fn sheer_x(shift: f32) -> Self {
+        Self::new(
+            Vector2::new(1.0, 0.0),
+            Vector2::new(shift, 1.0),
+            Vector2::ZERO,
+        )
+    }
+
+    /// Creates a matrix that sheers in the +- y-direction by `shift`
+    pub const fn sheer_y(shift: f32) -> Self {
+        Self::new(
+            Vector2::new(1.0, shift),
+            Vector2::new(0.0, 1.0),
+            Vector2::ZERO,
+        )
+    }
+
+    /// Creates a matrix from its parts
+    pub fn from_parts(parts: &AffineParts) -> Self {
+        Self::translation(parts.translation)
+            .compose(&Self::rotation(parts.rotation))
+            .compose(&Self::stretch(parts.stretch.x, parts.stretch.y))
+            .compose(&Self::sheer_x(parts.sheer))
+    }
+
+    /// Splits this matrix into the parts `Affine2::from_parts` would build it from. The
+    /// rotation is between `-PI` and `PI`, and the x stretch is never negative, so a
+    /// reflection is returned as a negative y stretch. If the matrix collapses the
+    /// x-direction to nothing, the rotation and sheer are `0.`
+    pub fn decompose(&self) -> AffineParts {
+        let Matrix2 { i, j } = self.matrix2;
+        let stretch_x = i.magnitude();
+        if stretch_x == 0. {
+            return AffineParts {
+                translation: self.translation,
+                rotation: 0.,
+                stretch: Vector2::new(0., j.y),
+                sheer: 0.,
+            };
+        }
+        // the rotation takes the x-direction to `i`
+        let rotation = (-i.y).atan2(i.x);
+        let unrotated_j = j.mul(&Matrix2::rotation(-rotation));
+        AffineParts {
+            translation: self.translation,
+            rotation,
+            stretch: Vector2::new(stretch_x, unrotated_j.y),
+            sheer: unrotated_j.x / stretch_x,
+        }
+    }
+
+    /// Returns the matrix that undoes this one, or `None` if this matrix collapses points
+    /// onto a line or a single point
+    pub fn inverse(&self) -> Option<Self> {
+        let matrix2 = self.matrix2.inverse()?;
+        Some(Self {
+            translation: self.translation.mul(&matrix2).rotate_180(),
+            matrix2,
+        })
+    }
+
+    /// Transforms `point` by this matrix, applying its 2x2 part and then its translation
+    pub fn transform_point(&self, point: &Vector2) -> Vector2 {
+        point.mul(&self.matrix2).add(&self.translation)
+    }
+
+    /// Transforms the direction or offset `vector` by this matrix, which only applies its
+    /// 2x2 part
+    pub fn transform_vector(&self, vector: &Vector2) -> Vector2 {
+        vector.mul(&self.matrix2)
+    }
 }
 
 impl Affine2 {
@@ -39,8 +151,8 @@ impl Affine2 {
 
     /// Sets this matrix to the result of the composition `self ◦ rhs`
     pub fn compose_assign(&mut self, rhs: &Self) {
+        self.translation = self.transform_point(&rhs.translation);
         self.matrix2.compose_assign(&rhs.matrix2);
-        self.translation.add_assign(&rhs.translation);
     }
 
     /// Composes matrices backwards (`lhs ◦ self`) see `Matrix2::compose(self, rhs: &Self)`
@@ -49,3 +161,170 @@ impl Affine2 {
         lhs.compose(&self)
     }
 }
+
+impl From<Matrix2> for Affine2 {
+    fn from(matrix2: Matrix2) -> Self {
+        Self {
+            matrix2,
+            translation: Vector2::ZERO,
+        }
+    }
+}
+
+impl Mul<Vector2> for Affine2 {
+    type Output = Vector2;
+
+    /// Transforms the point `rhs` by this matrix, the same as `self.transform_point(&rhs)`
+    fn mul(self, rhs: Vector2) -> Vector2 {
+        self.transform_point(&rhs)
+    }
+}
+
+impl Mul for Affine2 {
+    type Output = Self;
+
+    /// Composes this matrix with `rhs`, the same as `self.compose(&rhs)`
+    fn mul(self, rhs: Self) -> Self {
+        self.compose(&rhs)
+    }
+}
+
+impl MulAssign for Affine2 {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.compose_assign(&rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_close(a: Vector2, b: Vector2) {
+        assert!(a.distance_to(&b) < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    fn assert_affine_close(a: &Affine2, b: &Affine2) {
+        assert_close(a.matrix2.i, b.matrix2.i);
+        assert_close(a.matrix2.j, b.matrix2.j);
+        assert_close(a.translation, b.translation);
+    }
+
+    fn example() -> Affine2 {
+        Affine2::new(
+            Vector2::new(2., 0.5),
+            Vector2::new(-1., 3.),
+            Vector2::new(4., -2.),
+        )
+    }
+
+    #[test]
+    fn transform_point_applies_translation_after_matrix() {
+        let point = Vector2::new(1., 2.);
+        assert_close(example().transform_point(&point), Vector2::new(4., 4.5));
+        assert_close(example() * point, Vector2::new(4., 4.5));
+    }
+
+    #[test]
+    fn transform_vector_ignores_translation() {
+        let vector = Vector2::new(1., 2.);
+        assert_close(example().transform_vector(&vector), Vector2::new(0., 6.5));
+    }
+
+    #[test]
+    fn compose_applies_rhs_first() {
+        let (a, b) = (
+            example(),
+            Affine2::rotation(0.7).compose(&Affine2::translation(Vector2::new(3., 1.))),
+        );
+        let point = Vector2::new(-2., 5.);
+        let expected = a.transform_point(&b.transform_point(&point));
+        assert_close(a.compose(&b).transform_point(&point), expected);
+        assert_close((a * b) * point, expected);
+        assert_affine_close(&b.reverse_compose(&a), &a.compose(&b));
+
+        let mut composed = a;
+        composed *= b;
+        assert_affine_close(&composed, &a.compose(&b));
+    }
+
+    #[test]
+    fn compose_transforms_translation() {
+        let composed = Affine2::scale(2.).compose(&Affine2::translation(Vector2::new(1., 1.)));
+        assert_close(composed.translation, Vector2::new(2., 2.));
+        let composed = Affine2::translation(Vector2::new(1., 1.)).compose(&Affine2::scale(2.));
+        assert_close(composed.translation, Vector2::new(1., 1.));
+    }
+
+    #[test]
+    fn inverse_undoes_matrix() {
+        let affine2 = example();
+        let inverse = affine2.inverse().unwrap();
+        assert_affine_close(&affine2.compose(&inverse), &Affine2::default());
+        assert_affine_close(&inverse.compose(&affine2), &Affine2::default());
+        let point = Vector2::new(3., -7.);
+        assert_close(
+            inverse.transform_point(&affine2.transform_point(&point)),
+            point,
+        );
+    }
+
+    #[test]
+    fn inverse_of_singular_matrix_is_none() {
+        assert!(Affine2::scale(0.).inverse().is_none());
+        let collapsed = Affine2::new(Vector2::new(1., 2.), Vector2::new(2., 4.), Vector2::UP);
+        assert!(collapsed.inverse().is_none());
+    }
+
+    #[test]
+    fn constructors() {
+        let point = Vector2::new(2., 3.);
+        assert_close(
+            Affine2::translation(Vector2::new(1., -1.)) * point,
+            Vector2::new(3., 2.),
+        );
+        // positive angles turn clockwise, as with `Vector2::new_euclidean`
+        assert_close(Affine2::rotation(FRAC_PI_2) * Vector2::UP, Vector2::RIGHT);
+        assert_close(Affine2::stretch(2., -1.) * point, Vector2::new(4., -3.));
+        assert_close(Affine2::scale(3.) * point, Vector2::new(6., 9.));
+        assert_close(Affine2::sheer_x(1.) * point, Vector2::new(5., 3.));
+        assert_close(Affine2::sheer_y(1.) * point, Vector2::new(2., 5.));
+        assert_affine_close(&Matrix2::scale(2.).into(), &Affine2::scale(2.));
+    }
+
+    #[test]
+    fn decompose_returns_parts() {
+        let parts = AffineParts {
+            translation: Vector2::new(5., -3.),
+            rotation: -2.,
+            stretch: Vector2::new(1.5, 0.5),
+            sheer: 0.25,
+        };
+        let decomposed = Affine2::from_parts(&parts).decompose();
+        assert_close(decomposed.translation, parts.translation);
+        assert!((decomposed.rotation - parts.rotation).abs() < EPSILON);
+        assert_close(decomposed.stretch, parts.stretch);
+        assert!((decomposed.sheer - parts.sheer).abs() < EPSILON);
+    }
+
+    #[test]
+    fn decompose_then_from_parts_is_unchanged() {
+        for affine2 in [
+            example(),
+            Affine2::rotation(PI).compose(&Affine2::stretch(1., -2.)),
+            Affine2::sheer_y(0.5),
+            Affine2::default(),
+        ] {
+            assert_affine_close(&Affine2::from_parts(&affine2.decompose()), &affine2);
+        }
+    }
+
+    #[test]
+    fn decompose_collapsed_matrix() {
+        let parts = Affine2::stretch(0., 2.).decompose();
+        assert_eq!(parts.rotation, 0.);
+        assert_close(parts.stretch, Vector2::new(0., 2.));
+    }
+}
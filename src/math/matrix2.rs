@@ -1,4 +1,5 @@
 use crate::*;
+use std::ops::{Mul, MulAssign};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Matrix2 {
@@ -91,3 +92,234 @@ impl Matrix2 {
         lhs.compose(&self)
     }
 }
+
+impl Matrix2 {
+    /// Returns the factor this matrix scales areas by. It is negative if the matrix flips
+    /// shapes over, and `0.` if it collapses them onto a line or a single point
+    pub fn determinant(&self) -> f32 {
+        self.i.perp_dot(&self.j)
+    }
+
+    /// Returns the matrix that undoes this one, or `None` if this matrix collapses points
+    /// onto a line or a single point
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0. || !determinant.is_finite() {
+            return None;
+        }
+        Some(Self {
+            i: Vector2::new(self.j.y, -self.i.y).scale(1. / determinant),
+            j: Vector2::new(-self.j.x, self.i.x).scale(1. / determinant),
+        })
+    }
+
+    /// Returns this matrix with its rows and columns swapped
+    pub fn transpose(self) -> Self {
+        Self {
+            i: Vector2::new(self.i.x, self.j.x),
+            j: Vector2::new(self.i.y, self.j.y),
+        }
+    }
+
+    /// Returns the eigenvalues of this matrix, largest first, along with their eigenvectors,
+    /// which are normalized and at right angles to each other. The matrix should be
+    /// symmetric, so `i.y` should equal `j.x`, and their average is used if they don't
+    pub fn symmetric_eigen(&self) -> [(f32, Vector2); 2] {
+        let (a, b, d) = (self.i.x, (self.i.y + self.j.x) / 2., self.j.y);
+        let mean = (a + d) / 2.;
+        let radius = ((a - d) / 2.).hypot(b);
+        // the direction stretched the most
+        let angle = (2. * b).atan2(a - d) / 2.;
+        let eigenvector = Vector2::new(angle.cos(), angle.sin());
+        [
+            (mean + radius, eigenvector),
+            (mean - radius, eigenvector.rotate_90_ccw()),
+        ]
+    }
+
+    /// Splits this matrix into a rotation, in radians as for `Matrix2::rotation`, and a
+    /// symmetric matrix that scales along two directions at right angles to each other,
+    /// which is applied first. The rotation is the one closest to this matrix, and the
+    /// scale has a negative eigenvalue if the matrix flips shapes over. Use
+    /// `Matrix2::symmetric_eigen` to find the directions and amounts of the scale
+    pub fn polar_decomposition(&self) -> (f32, Self) {
+        let angle = (self.j.x - self.i.y).atan2(self.i.x + self.j.y);
+        let mut scale = Self::rotation(-angle).compose(self);
+        // the scale is symmetric, apart from rounding errors
+        let shear = (scale.i.y + scale.j.x) / 2.;
+        scale.i.y = shear;
+        scale.j.x = shear;
+        (angle, scale)
+    }
+
+    /// Returns whether every component of this matrix is within `epsilon` of the same
+    /// component of `other`
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.i.approx_eq(&other.i, epsilon) && self.j.approx_eq(&other.j, epsilon)
+    }
+}
+
+// std::ops, for writing arithmetic with operators
+impl Mul<Vector2> for Matrix2 {
+    type Output = Vector2;
+
+    /// Transforms `rhs` by this matrix
+    fn mul(self, rhs: Vector2) -> Vector2 {
+        rhs.mul(&self)
+    }
+}
+
+impl Mul for Matrix2 {
+    type Output = Self;
+
+    /// Composes this matrix with `rhs`, the same as `self.compose(&rhs)`
+    fn mul(self, rhs: Self) -> Self {
+        self.compose(&rhs)
+    }
+}
+
+impl MulAssign for Matrix2 {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.compose_assign(&rhs);
+    }
+}
+
+impl Mul<f32> for Matrix2 {
+    type Output = Self;
+
+    /// Scales every component of this matrix by `rhs`
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.i.scale(rhs), self.j.scale(rhs))
+    }
+}
+
+// conversions
+impl From<cgmath::Matrix2<f32>> for Matrix2 {
+    fn from(matrix: cgmath::Matrix2<f32>) -> Self {
+        Self::new(matrix.x.into(), matrix.y.into())
+    }
+}
+
+impl From<Matrix2> for cgmath::Matrix2<f32> {
+    fn from(matrix: Matrix2) -> Self {
+        cgmath::Matrix2::from_cols(matrix.i.into(), matrix.j.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn example() -> Matrix2 {
+        Matrix2::new(Vector2::new(2., 0.5), Vector2::new(-1., 3.))
+    }
+
+    #[test]
+    fn determinant_scales_area() {
+        assert!((example().determinant() - 6.5).abs() < EPSILON);
+        assert!((Matrix2::stretch(2., 3.).determinant() - 6.).abs() < EPSILON);
+        assert!((Matrix2::rotation(1.).determinant() - 1.).abs() < EPSILON);
+        assert!((Matrix2::stretch(-1., 1.).determinant() + 1.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn inverse_undoes_matrix() {
+        let inverse = example().inverse().unwrap();
+        assert!(example()
+            .compose(&inverse)
+            .approx_eq(&Matrix2::default(), EPSILON));
+        assert!(inverse
+            .compose(&example())
+            .approx_eq(&Matrix2::default(), EPSILON));
+        assert!(Matrix2::scale(0.).inverse().is_none());
+        assert!(Matrix2::new(Vector2::new(1., 2.), Vector2::new(2., 4.))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let transpose = example().transpose();
+        assert!(transpose.approx_eq(
+            &Matrix2::new(Vector2::new(2., -1.), Vector2::new(0.5, 3.)),
+            0.
+        ));
+        assert!(transpose.transpose().approx_eq(&example(), 0.));
+        // the transpose of a rotation is its inverse
+        let rotation = Matrix2::rotation(0.4);
+        assert!(rotation
+            .transpose()
+            .approx_eq(&rotation.inverse().unwrap(), EPSILON));
+    }
+
+    #[test]
+    fn symmetric_eigen() {
+        let matrix = Matrix2::new(Vector2::new(2., 1.), Vector2::new(1., 2.));
+        let [(largest, largest_vector), (smallest, smallest_vector)] = matrix.symmetric_eigen();
+        assert!((largest - 3.).abs() < EPSILON);
+        assert!((smallest - 1.).abs() < EPSILON);
+        for (value, vector) in [(largest, largest_vector), (smallest, smallest_vector)] {
+            assert!((vector.magnitude() - 1.).abs() < EPSILON);
+            assert!(vector.mul(&matrix).approx_eq(&vector.scale(value), EPSILON));
+        }
+        assert!(largest_vector.dot(&smallest_vector).abs() < EPSILON);
+
+        let [(largest, largest_vector), (smallest, _)] = Matrix2::stretch(1., 4.).symmetric_eigen();
+        assert!((largest - 4.).abs() < EPSILON && (smallest - 1.).abs() < EPSILON);
+        assert!(largest_vector.dot(&Vector2::UP).abs() > 1. - EPSILON);
+    }
+
+    #[test]
+    fn polar_decomposition_recovers_rotation_and_scale() {
+        let scale = Matrix2::stretch(2., 0.5);
+        let matrix = Matrix2::rotation(0.8).compose(&scale);
+        let (angle, recovered) = matrix.polar_decomposition();
+        assert!((angle - 0.8).abs() < EPSILON);
+        assert!(recovered.approx_eq(&scale, EPSILON));
+
+        let (angle, scale) = example().polar_decomposition();
+        assert!(Matrix2::rotation(angle)
+            .compose(&scale)
+            .approx_eq(&example(), EPSILON));
+        assert_eq!(scale.i.y, scale.j.x);
+        assert!(scale.symmetric_eigen().iter().all(|(value, _)| *value > 0.));
+    }
+
+    #[test]
+    fn polar_decomposition_of_reflection() {
+        let matrix = Matrix2::rotation(FRAC_PI_2).compose(&Matrix2::stretch(1., -3.));
+        let (angle, scale) = matrix.polar_decomposition();
+        assert!(Matrix2::rotation(angle)
+            .compose(&scale)
+            .approx_eq(&matrix, EPSILON));
+        let [(_, _), (smallest, _)] = scale.symmetric_eigen();
+        assert!(smallest < 0.);
+    }
+
+    #[test]
+    fn approx_eq() {
+        let nudged = Matrix2::new(Vector2::new(2.001, 0.5), Vector2::new(-1., 3.));
+        assert!(example().approx_eq(&nudged, 0.01));
+        assert!(!example().approx_eq(&nudged, 0.0001));
+    }
+
+    #[test]
+    fn operators_match_methods() {
+        let rotation = Matrix2::rotation(0.3);
+        let vector = Vector2::new(3., -2.);
+        assert_eq!(example() * vector, vector.mul(&example()));
+        assert!((example() * rotation).approx_eq(&example().compose(&rotation), 0.));
+
+        let mut composed = example();
+        composed *= rotation;
+        assert!(composed.approx_eq(&example().compose(&rotation), 0.));
+
+        let scaled = example() * 2.;
+        assert_eq!(scaled.i, example().i.scale(2.));
+        assert_eq!(scaled.j, example().j.scale(2.));
+        assert_eq!(scaled * vector, (example() * vector).scale(2.));
+    }
+}
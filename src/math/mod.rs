@@ -4,5 +4,8 @@ usemod![
     pub vector2,
     pub line,
     pub angle,
+    pub polyline,
     pub(crate) mesh,
+    pub(crate) triangulation,
+    pub(crate) spline,
 ];
@@ -0,0 +1,216 @@
+use crate::*;
+
+/// Ways of cleaning up lines made of many points, such as freehand input from
+/// `Oge::get_real_cursor_positions`
+pub trait Polyline {
+    /// Returns the points needed to keep the line within `tolerance` of every one of its
+    /// points, using the Ramer-Douglas-Peucker algorithm. The first and last points are always
+    /// kept
+    fn simplify(&self, tolerance: f32) -> Vec<Vector2>;
+
+    /// Returns the points that are at least `min_distance` from the previous point that was
+    /// kept. The first and last points are always kept
+    fn resample(&self, min_distance: f32) -> Vec<Vector2>;
+
+    /// Rounds off the corners of the line by cutting each one off `iterations` times, using
+    /// Chaikin's algorithm. Each iteration roughly doubles the number of points. The ends of
+    /// an open line stay where they are, and a `closed` line is also rounded off where its
+    /// last point meets its first
+    fn smooth(&self, iterations: u32, closed: bool) -> Vec<Vector2>;
+}
+
+impl Polyline for [Vector2] {
+    fn simplify(&self, tolerance: f32) -> Vec<Vector2> {
+        simplified_indices(self, tolerance)
+            .into_iter()
+            .map(|i| self[i])
+            .collect()
+    }
+
+    fn resample(&self, min_distance: f32) -> Vec<Vector2> {
+        let mut resampled: Vec<Vector2> = Vec::with_capacity(self.len());
+        for &point in self {
+            match resampled.last() {
+                Some(last) if point.distance_to(last) < min_distance => {}
+                _ => resampled.push(point),
+            }
+        }
+        // the last point takes the place of the point it was too close to
+        if let Some(&last) = self.last() {
+            if resampled.last() != Some(&last) {
+                if resampled.len() > 1 {
+                    resampled.pop();
+                }
+                resampled.push(last);
+            }
+        }
+        resampled
+    }
+
+    fn smooth(&self, iterations: u32, closed: bool) -> Vec<Vector2> {
+        let mut points = self.to_vec();
+        if points.len() < 3 {
+            return points;
+        }
+        for _ in 0..iterations {
+            let mut smoothed = Vec::with_capacity(points.len() * 2);
+            if !closed {
+                smoothed.push(points[0]);
+            }
+            let lines = if closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            for i in 0..lines {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                smoothed.extend([
+                    a.scale(0.75).add(&b.scale(0.25)),
+                    a.scale(0.25).add(&b.scale(0.75)),
+                ]);
+            }
+            if !closed {
+                smoothed.push(points[points.len() - 1]);
+            }
+            points = smoothed;
+        }
+        points
+    }
+}
+
+/// Returns the distance from `point` to the closest point on the line from `start` to `end`
+fn distance_to_line(point: &Vector2, start: &Vector2, end: &Vector2) -> f32 {
+    let direction = end.sub(start);
+    let length_squared = direction.dot(&direction);
+    if length_squared == 0. {
+        return point.distance_to(start);
+    }
+    let t = (point.sub(start).dot(&direction) / length_squared).clamp(0., 1.);
+    point.distance_to(&start.add(&direction.scale(t)))
+}
+
+/// Returns the indices of the points kept by `Polyline::simplify`, in order
+pub(crate) fn simplified_indices(points: &[Vector2], tolerance: f32) -> Vec<usize> {
+    if points.len() < 3 {
+        return (0..points.len()).collect();
+    }
+    let mut kept = vec![false; points.len()];
+    kept[0] = true;
+    kept[points.len() - 1] = true;
+    // a stack rather than recursion, as freehand lines can have thousands of points
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = ranges.pop() {
+        let (farthest, distance) = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    distance_to_line(&points[i], &points[start], &points[end]),
+                )
+            })
+            .fold((start, -1.), |farthest, candidate| {
+                if candidate.1 > farthest.1 {
+                    candidate
+                } else {
+                    farthest
+                }
+            });
+        if distance > tolerance {
+            kept[farthest] = true;
+            ranges.extend([(start, farthest), (farthest, end)]);
+        }
+    }
+    (0..points.len()).filter(|&i| kept[i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn wobbly_line(count: usize) -> Vec<Vector2> {
+        let mut rng = StdRng::seed_from_u64(46);
+        (0..count)
+            .map(|i| {
+                let x = i as f32 * 0.5;
+                Vector2::new(x, (x * 0.3).sin() * 10. + rng.gen_range(-0.5..0.5))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collinear_points_simplify_to_ends() {
+        let line: Vec<Vector2> = (0..20)
+            .map(|i| Vector2::new(i as f32, i as f32 * 2. - 3.))
+            .collect();
+        assert_eq!(line.simplify(1e-3), vec![line[0], line[19]]);
+        assert_eq!(line.simplify(0.1), vec![line[0], line[19]]);
+    }
+
+    #[test]
+    fn simplify_keeps_points_within_tolerance() {
+        let line = wobbly_line(200);
+        for tolerance in [0.1, 0.5, 2.] {
+            let kept = simplified_indices(&line, tolerance);
+            assert_eq!(kept.first(), Some(&0));
+            assert_eq!(kept.last(), Some(&(line.len() - 1)));
+            for pair in kept.windows(2) {
+                let (start, end) = (pair[0], pair[1]);
+                for point in line[start..end].iter() {
+                    let distance = distance_to_line(point, &line[start], &line[end]);
+                    assert!(distance <= tolerance, "{} > {}", distance, tolerance);
+                }
+            }
+            let simplified = line.simplify(tolerance);
+            assert_eq!(simplified.len(), kept.len());
+            assert!(simplified.len() < line.len());
+        }
+    }
+
+    #[test]
+    fn short_lines_are_unchanged() {
+        let line = vec![Vector2::ZERO, Vector2::new(1., 1.)];
+        assert_eq!(line.simplify(10.), line);
+        assert_eq!(line.resample(10.), line);
+        assert_eq!(line.smooth(3, false), line);
+        assert!(Vec::<Vector2>::new().simplify(1.).is_empty());
+    }
+
+    #[test]
+    fn resample_keeps_ends() {
+        let line = wobbly_line(100);
+        for min_distance in [0.1, 1., 5., 1000.] {
+            let resampled = line.resample(min_distance);
+            assert_eq!(resampled.first(), line.first());
+            assert_eq!(resampled.last(), line.last());
+            // only the last point may be closer than `min_distance` to the one before it
+            let inner = &resampled[..resampled.len() - 1];
+            for pair in inner.windows(2) {
+                assert!(pair[0].distance_to(&pair[1]) >= min_distance);
+            }
+        }
+        assert_eq!(line.resample(1000.).len(), 2);
+    }
+
+    #[test]
+    fn smooth_doubles_points() {
+        let line = wobbly_line(5);
+        for iterations in 0..4 {
+            let open = line.smooth(iterations, false);
+            assert_eq!(open.len(), line.len() << iterations);
+            assert_eq!(open.first(), line.first());
+            assert_eq!(open.last(), line.last());
+            let closed = line.smooth(iterations, true);
+            assert_eq!(closed.len(), line.len() << iterations);
+        }
+        // the first corner of a closed line is cut a quarter of the way along each side
+        let square = vec![
+            Vector2::ZERO,
+            Vector2::new(4., 0.),
+            Vector2::new(4., 4.),
+            Vector2::new(0., 4.),
+        ];
+        let smoothed = square.smooth(1, true);
+        assert_eq!(smoothed[0], Vector2::new(1., 0.));
+        assert_eq!(smoothed[7], Vector2::new(0., 1.));
+    }
+}
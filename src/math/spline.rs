@@ -0,0 +1,293 @@
+use crate::*;
+use std::ops::Range;
+
+/// The control points of a cubic Bezier segment: start, two handles, end
+pub(crate) type CubicSegment = [Vector2; 4];
+
+/// Returns cubic segments that pass through each of `points`, with handles along the line
+/// between each point's neighbours. `tension` scales the handles, from `0.` for straight
+/// lines to `1.` for a Catmull-Rom spline. If `closed`, the last point is joined back to the
+/// first, and otherwise the first and last points use their only neighbour. Only the
+/// segments in `segments` are returned, where segment `i` starts at `points[i]`
+pub(crate) fn cardinal_segments(
+    points: &[Vector2],
+    tension: f32,
+    segments: Range<usize>,
+    closed: bool,
+) -> Vec<CubicSegment> {
+    let count = points.len();
+    let last = count - 1;
+    let tangent = |i: usize| {
+        let (previous, next, scale) = if closed {
+            (points[(i + last) % count], points[(i + 1) % count], 0.5)
+        } else if i == 0 || i == last {
+            (points[i.saturating_sub(1)], points[(i + 1).min(last)], 1.)
+        } else {
+            (points[i - 1], points[i + 1], 0.5)
+        };
+        next.sub(&previous).scale(tension * scale)
+    };
+    segments
+        .map(|i| {
+            let (start, end) = (points[i], points[(i + 1) % count]);
+            [
+                start,
+                start.add(&tangent(i).scale(1. / 3.)),
+                end.sub(&tangent((i + 1) % count).scale(1. / 3.)),
+                end,
+            ]
+        })
+        .collect()
+}
+
+/// Returns cubic segments that follow a centripetal Catmull-Rom spline through `points`,
+/// which doesn't overshoot or loop between points that are close together. If `closed`, the
+/// last point is joined back to the first, and otherwise the ends are extended by mirroring
+/// their neighbours. Only the segments in `segments` are returned.
+pub(crate) fn catmull_rom_segments(
+    points: &[Vector2],
+    segments: Range<usize>,
+    closed: bool,
+) -> Vec<CubicSegment> {
+    let last = points.len() - 1;
+    let point = |i: isize| {
+        if closed {
+            points[i.rem_euclid(points.len() as isize) as usize]
+        } else if i < 0 {
+            points[0].scale(2.).sub(&points[1])
+        } else if i as usize > last {
+            points[last].scale(2.).sub(&points[last - 1])
+        } else {
+            points[i as usize]
+        }
+    };
+    // the square root of the distance between points, so that knots are centripetal
+    let knot = |a: Vector2, b: Vector2| a.distance_to(&b).sqrt().max(f32::EPSILON);
+
+    segments
+        .map(|i| {
+            let i = i as isize;
+            let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+            let (t01, t12, t23) = (knot(p0, p1), knot(p1, p2), knot(p2, p3));
+            let chord = p2.sub(&p1);
+            let m1 = chord.add(
+                &p1.sub(&p0)
+                    .scale(1. / t01)
+                    .sub(&p2.sub(&p0).scale(1. / (t01 + t12)))
+                    .scale(t12),
+            );
+            let m2 = chord.add(
+                &p3.sub(&p2)
+                    .scale(1. / t23)
+                    .sub(&p3.sub(&p1).scale(1. / (t12 + t23)))
+                    .scale(t12),
+            );
+            [
+                p1,
+                p1.add(&m1.scale(1. / 3.)),
+                p2.sub(&m2.scale(1. / 3.)),
+                p2,
+            ]
+        })
+        .collect()
+}
+
+/// Splits a cubic segment in half until each piece is within `tolerance` of a straight line,
+/// calling `push` with the end point of each piece and how far along the segment it is
+pub(crate) fn flatten_cubic(
+    segment: &CubicSegment,
+    tolerance: f32,
+    push: &mut impl FnMut(Vector2, f32),
+) {
+    const MAX_DEPTH: u32 = 10;
+
+    fn subdivide(
+        segment: &CubicSegment,
+        tolerance: f32,
+        (start, end): (f32, f32),
+        depth: u32,
+        push: &mut impl FnMut(Vector2, f32),
+    ) {
+        // the handles' distance from the chord bounds the segment's distance from it
+        let chord = Line::connect(&segment[0], &segment[3]);
+        let flat = segment[1..3].iter().all(|handle| {
+            let offset = handle.sub(&segment[0]);
+            let length = chord.direction.magnitude();
+            let distance = if length > 0. {
+                (offset.x * chord.direction.y - offset.y * chord.direction.x).abs() / length
+            } else {
+                offset.magnitude()
+            };
+            distance <= tolerance
+        });
+        if flat || depth >= MAX_DEPTH {
+            push(segment[3], end);
+            return;
+        }
+
+        // de Casteljau's algorithm
+        let [p0, p1, p2, p3] = *segment;
+        let p01 = p0.midpoint(&p1);
+        let p12 = p1.midpoint(&p2);
+        let p23 = p2.midpoint(&p3);
+        let p012 = p01.midpoint(&p12);
+        let p123 = p12.midpoint(&p23);
+        let middle = p012.midpoint(&p123);
+        let halfway = (start + end) / 2.;
+        subdivide(
+            &[p0, p01, p012, middle],
+            tolerance,
+            (start, halfway),
+            depth + 1,
+            push,
+        );
+        subdivide(
+            &[middle, p123, p23, p3],
+            tolerance,
+            (halfway, end),
+            depth + 1,
+            push,
+        );
+    }
+
+    subdivide(segment, tolerance, (0., 1.), 0, push);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn example() -> Vec<Vector2> {
+        [(0., 0.), (3., 1.), (4., 5.), (4.2, 5.1), (-2., 3.)]
+            .iter()
+            .map(|&(x, y)| Vector2::new(x, y))
+            .collect()
+    }
+
+    fn point_on(segment: &CubicSegment, t: f32) -> Vector2 {
+        let u = 1. - t;
+        segment[0]
+            .scale(u * u * u)
+            .add(&segment[1].scale(3. * u * u * t))
+            .add(&segment[2].scale(3. * u * t * t))
+            .add(&segment[3].scale(t * t * t))
+    }
+
+    fn distance_to_chord(point: Vector2, a: Vector2, b: Vector2) -> f32 {
+        let chord = b.sub(&a);
+        let length_squared = chord.dot(&chord);
+        if length_squared == 0. {
+            return point.distance_to(&a);
+        }
+        let t = (point.sub(&a).dot(&chord) / length_squared).clamp(0., 1.);
+        point.distance_to(&a.add(&chord.scale(t)))
+    }
+
+    /// Flattens every segment, checking that the pieces stay within `tolerance` of the curve,
+    /// and returns the flattened points
+    fn flatten_within(segments: &[CubicSegment], tolerance: f32) -> Vec<Vector2> {
+        let mut flattened = vec![segments[0][0]];
+        for segment in segments {
+            let mut pieces = vec![(segment[0], 0.)];
+            flatten_cubic(segment, tolerance, &mut |point, t| pieces.push((point, t)));
+            assert_eq!(pieces.last().unwrap().1, 1.);
+            for piece in pieces.windows(2) {
+                let ((a, start), (b, end)) = (piece[0], piece[1]);
+                assert!(start < end);
+                for k in 0..=16 {
+                    let t = start + (end - start) * k as f32 / 16.;
+                    let distance = distance_to_chord(point_on(segment, t), a, b);
+                    assert!(
+                        distance <= tolerance + EPSILON,
+                        "{} > {}",
+                        distance,
+                        tolerance
+                    );
+                }
+            }
+            flattened.extend(pieces[1..].iter().map(|(point, _)| *point));
+        }
+        flattened
+    }
+
+    fn assert_passes_through(flattened: &[Vector2], points: &[Vector2]) {
+        for point in points {
+            assert!(
+                flattened
+                    .iter()
+                    .any(|other| other.distance_to(point) < EPSILON),
+                "{:?} is not on the curve",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn cardinal_passes_through_points() {
+        let points = example();
+        for closed in [false, true] {
+            let count = if closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            for tension in [0., 0.5, 1.] {
+                let segments = cardinal_segments(&points, tension, 0..count, closed);
+                for (i, segment) in segments.iter().enumerate() {
+                    assert_eq!(segment[0], points[i]);
+                    assert_eq!(segment[3], points[(i + 1) % points.len()]);
+                }
+                assert_passes_through(&flatten_within(&segments, 0.01), &points);
+            }
+        }
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_points() {
+        let points = example();
+        for closed in [false, true] {
+            let count = if closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+            let segments = catmull_rom_segments(&points, 0..count, closed);
+            for tolerance in [0.001, 0.1, 1.] {
+                assert_passes_through(&flatten_within(&segments, tolerance), &points);
+            }
+        }
+    }
+
+    #[test]
+    fn segments_are_smooth() {
+        let points = example();
+        let segments = catmull_rom_segments(&points, 0..points.len(), true);
+        for i in 0..segments.len() {
+            let next = &segments[(i + 1) % segments.len()];
+            let incoming = segments[i][3].sub(&segments[i][2]).normalize();
+            let outgoing = next[1].sub(&next[0]).normalize();
+            assert!(incoming.distance_to(&outgoing) < EPSILON);
+        }
+    }
+
+    #[test]
+    fn some_segments() {
+        let points = example();
+        let all = catmull_rom_segments(&points, 0..4, false);
+        let some = catmull_rom_segments(&points, 1..3, false);
+        assert_eq!(&all[1..3], &some[..]);
+    }
+
+    #[test]
+    fn zero_tension_is_straight() {
+        let points = example();
+        let segments = cardinal_segments(&points, 0., 0..points.len() - 1, false);
+        let mut count = 0;
+        for segment in segments.iter() {
+            flatten_cubic(segment, 0.001, &mut |_, _| count += 1);
+        }
+        assert_eq!(count, segments.len());
+    }
+}
@@ -0,0 +1,446 @@
+use crate::*;
+
+/// Returns twice the signed area of the polygon outlined by `points`, which is positive if the
+/// outline is counter-clockwise
+pub(crate) fn signed_area(points: &[Vector2]) -> f32 {
+    (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum()
+}
+
+/// Returns a positive number if `a`, `b` and `c` turn counter-clockwise, a negative number if
+/// they turn clockwise and `0.` if they are collinear
+fn cross(a: Vector2, b: Vector2, c: Vector2) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)
+}
+
+/// Returns `true` if `point` is inside or on the edge of the counter-clockwise triangle `abc`
+fn in_triangle(point: Vector2, a: Vector2, b: Vector2, c: Vector2) -> bool {
+    cross(a, b, point) >= 0. && cross(b, c, point) >= 0. && cross(c, a, point) >= 0.
+}
+
+/// Triangulates a simple polygon with holes using ear clipping.
+///
+/// Returns the vertices of the polygon, the outline's first and then each hole's, and the
+/// indices of counter-clockwise triangles between them. The outline and holes may be given in
+/// either winding order. Holes must be inside the outline and must not overlap each other.
+pub(crate) fn triangulate(outline: &[Vector2], holes: &[Vec<Vector2>]) -> (Vec<Vector2>, Vec<u32>) {
+    let mut vertices = outline.to_vec();
+    if signed_area(&vertices) < 0. {
+        vertices.reverse();
+    }
+    let mut polygon: Vec<usize> = (0..vertices.len()).collect();
+
+    // holes are joined to the outline from their rightmost vertex, so the hole furthest to the
+    // right is joined first and later bridges can't cross it
+    let mut holes: Vec<Vec<Vector2>> = holes
+        .iter()
+        .filter(|hole| hole.len() >= 3)
+        .map(|hole| {
+            let mut hole = hole.clone();
+            if signed_area(&hole) > 0. {
+                hole.reverse();
+            }
+            hole
+        })
+        .collect();
+    holes.sort_by(|a, b| {
+        let max_x = |hole: &Vec<Vector2>| hole.iter().fold(f32::MIN, |max, point| max.max(point.x));
+        max_x(b)
+            .partial_cmp(&max_x(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for hole in holes {
+        let start = vertices.len();
+        vertices.extend(hole.iter());
+        bridge_hole(&vertices, &mut polygon, start..vertices.len());
+    }
+
+    let indices = clip_ears(&vertices, polygon);
+    (vertices, indices)
+}
+
+/// Joins a clockwise hole to the counter-clockwise `polygon` with a pair of coincident edges,
+/// so that the polygon and hole can be clipped as a single outline
+fn bridge_hole(vertices: &[Vector2], polygon: &mut Vec<usize>, hole: std::ops::Range<usize>) {
+    let hole_start = hole.start;
+    let hole_length = hole.len();
+    let rightmost = hole
+        .max_by(|a, b| {
+            vertices[*a]
+                .x
+                .partial_cmp(&vertices[*b].x)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap();
+    let m = vertices[rightmost];
+
+    // cast a ray to the right of `m` and find the closest edge of the polygon it hits
+    let mut closest: Option<(f32, usize)> = None;
+    for i in 0..polygon.len() {
+        let (a, b) = (
+            vertices[polygon[i]],
+            vertices[polygon[(i + 1) % polygon.len()]],
+        );
+        if (a.y > m.y) == (b.y > m.y) || a.y == b.y {
+            continue;
+        }
+        let x = a.x + (m.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x < m.x || closest.map_or(false, |(closest_x, _)| closest_x <= x) {
+            continue;
+        }
+        // the end of the edge furthest to the right is visible from `m`, unless another
+        // vertex is in the way
+        let j = if a.x > b.x {
+            i
+        } else {
+            (i + 1) % polygon.len()
+        };
+        closest = Some((x, j));
+    }
+    let (x, mut bridge) = match closest {
+        Some(closest) => closest,
+        // the hole is not inside the polygon
+        None => return,
+    };
+
+    // if any reflex vertices are inside the triangle between `m`, the hit point and the
+    // candidate, the one with the smallest angle to the ray is visible instead
+    let hit = Vector2::new(x, m.y);
+    let candidate = vertices[polygon[bridge]];
+    let (a, b, c) = if cross(m, hit, candidate) >= 0. {
+        (m, hit, candidate)
+    } else {
+        (m, candidate, hit)
+    };
+    let mut best_angle = f32::MAX;
+    for i in 0..polygon.len() {
+        let point = vertices[polygon[i]];
+        let previous = vertices[polygon[(i + polygon.len() - 1) % polygon.len()]];
+        let next = vertices[polygon[(i + 1) % polygon.len()]];
+        if i == bridge || cross(previous, point, next) > 0. || !in_triangle(point, a, b, c) {
+            continue;
+        }
+        let offset = point.sub(&m);
+        let angle = offset.y.abs().atan2(offset.x);
+        if angle < best_angle {
+            best_angle = angle;
+            bridge = i;
+        }
+    }
+
+    // a vertex that already joins another hole appears in the polygon more than once, and
+    // only one of its copies opens towards `m`
+    let target = vertices[polygon[bridge]];
+    if let Some(copy) = (0..polygon.len())
+        .find(|&i| vertices[polygon[i]] == target && locally_inside(vertices, polygon, i, m))
+    {
+        bridge = copy;
+    }
+
+    // walk around the hole from its rightmost vertex and back to the bridge vertex
+    let mut joined = Vec::with_capacity(hole_length + 2);
+    for k in 0..=hole_length {
+        joined.push(hole_start + (rightmost - hole_start + k) % hole_length);
+    }
+    joined.push(polygon[bridge]);
+    polygon.splice(bridge + 1..bridge + 1, joined);
+}
+
+/// Clips ears off the counter-clockwise `polygon` until it is a single triangle, returning the
+/// indices of each clipped triangle
+fn clip_ears(vertices: &[Vector2], polygon: Vec<usize>) -> Vec<u32> {
+    let mut indices = Vec::with_capacity(polygon.len().saturating_sub(2) * 3);
+    clip_ears_into(vertices, polygon, &mut indices);
+    indices
+}
+
+fn clip_ears_into(vertices: &[Vector2], mut polygon: Vec<usize>, indices: &mut Vec<u32>) {
+    let mut i = 0;
+    // the number of vertices that have been checked since the last ear was clipped
+    let mut checked = 0;
+    while polygon.len() > 3 {
+        let length = polygon.len();
+        if checked >= length {
+            // no ears are left, which happens when rounding or touching holes leave vertices
+            // on the edges of every ear, so the polygon is split in two and each half is
+            // clipped separately
+            if let Some((start, end)) = find_diagonal(vertices, &polygon) {
+                let mut second = polygon.split_off(end);
+                second.extend_from_slice(&polygon[..=start]);
+                polygon.drain(..start);
+                polygon.push(second[0]);
+                clip_ears_into(vertices, second, indices);
+                clip_ears_into(vertices, polygon, indices);
+                return;
+            }
+        }
+        let previous = polygon[(i + length - 1) % length];
+        let current = polygon[i % length];
+        let next = polygon[(i + 1) % length];
+        let (a, b, c) = (vertices[previous], vertices[current], vertices[next]);
+
+        let area = cross(a, b, c);
+        let is_ear = if checked >= length {
+            // there is no diagonal to split along either, so the current vertex is clipped to
+            // guarantee progress
+            true
+        } else {
+            area > 0.
+                && !polygon.iter().any(|&other| {
+                    let point = vertices[other];
+                    // vertices joining a hole are duplicated, so they are compared by position
+                    point != a && point != b && point != c && in_triangle(point, a, b, c)
+                })
+        };
+
+        if is_ear || area == 0. {
+            if area > 0. {
+                indices.extend([previous as u32, current as u32, next as u32]);
+            }
+            polygon.remove(i % length);
+            i %= polygon.len();
+            checked = 0;
+        } else {
+            i = (i + 1) % length;
+            checked += 1;
+        }
+    }
+    if polygon.len() == 3
+        && cross(
+            vertices[polygon[0]],
+            vertices[polygon[1]],
+            vertices[polygon[2]],
+        ) > 0.
+    {
+        indices.extend(polygon.iter().map(|&index| index as u32));
+    }
+}
+
+/// Returns `true` if a line from the vertex at position `i` in the counter-clockwise `polygon`
+/// towards `point` starts off inside the polygon
+fn locally_inside(vertices: &[Vector2], polygon: &[usize], i: usize, point: Vector2) -> bool {
+    let length = polygon.len();
+    let (previous, current, next) = (
+        vertices[polygon[(i + length - 1) % length]],
+        vertices[polygon[i]],
+        vertices[polygon[(i + 1) % length]],
+    );
+    let (left_of_next, left_of_previous) = (
+        cross(current, next, point) > 0.,
+        cross(previous, current, point) > 0.,
+    );
+    if cross(previous, current, next) > 0. {
+        left_of_next && left_of_previous
+    } else {
+        left_of_next || left_of_previous
+    }
+}
+
+/// Returns the positions in `polygon` of two vertices joined by a diagonal that lies inside the
+/// polygon and crosses none of its edges, with the first position before the second
+fn find_diagonal(vertices: &[Vector2], polygon: &[usize]) -> Option<(usize, usize)> {
+    let length = polygon.len();
+    let point = |i: usize| vertices[polygon[i % length]];
+    let edges_cross = |a: Vector2, b: Vector2| {
+        (0..length).any(|i| {
+            let (c, d) = (point(i), point(i + 1));
+            cross(a, b, c) * cross(a, b, d) < 0. && cross(c, d, a) * cross(c, d, b) < 0.
+        })
+    };
+    // whether the middle of the diagonal is inside the polygon, by counting the edges crossed
+    // by a ray cast to its right
+    let middle_inside = |a: Vector2, b: Vector2| {
+        let middle = a.add(&b).scale(0.5);
+        let mut inside = false;
+        for i in 0..length {
+            let (c, d) = (point(i), point(i + 1));
+            if (c.y > middle.y) != (d.y > middle.y)
+                && middle.x < c.x + (middle.y - c.y) / (d.y - c.y) * (d.x - c.x)
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    };
+
+    for start in 0..length {
+        for end in start + 2..length {
+            if start == 0 && end == length - 1 {
+                continue;
+            }
+            let (a, b) = (point(start), point(end));
+            if a != b
+                && locally_inside(vertices, polygon, start, b)
+                && locally_inside(vertices, polygon, end, a)
+                && !edges_cross(a, b)
+                && middle_inside(a, b)
+            {
+                return Some((start, end));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    /// Returns the total area of the triangles, checking that each is counter-clockwise
+    fn triangles_area(vertices: &[Vector2], indices: &[u32]) -> f32 {
+        assert_eq!(indices.len() % 3, 0);
+        indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|k| vertices[triangle[k] as usize]);
+                let area = cross(a, b, c);
+                assert!(area > 0., "{:?} is not counter-clockwise", triangle);
+                area * 0.5
+            })
+            .sum()
+    }
+
+    fn polygon_area(points: &[Vector2]) -> f32 {
+        signed_area(points).abs() * 0.5
+    }
+
+    fn points(coordinates: &[(f32, f32)]) -> Vec<Vector2> {
+        coordinates
+            .iter()
+            .map(|&(x, y)| Vector2::new(x, y))
+            .collect()
+    }
+
+    fn assert_fills(outline: &[Vector2], holes: &[Vec<Vector2>]) {
+        let (vertices, indices) = triangulate(outline, holes);
+        let expected =
+            polygon_area(outline) - holes.iter().map(|hole| polygon_area(hole)).sum::<f32>();
+        let area = triangles_area(&vertices, &indices);
+        assert!(
+            (area - expected).abs() < EPSILON,
+            "{} != {}",
+            area,
+            expected
+        );
+    }
+
+    #[test]
+    fn convex() {
+        let hexagon: Vec<Vector2> = (0..6)
+            .map(|i| Vector2::new_euclidean(2., i as f32 * std::f32::consts::PI / 3.))
+            .collect();
+        assert_fills(&hexagon, &[]);
+        assert_fills(&points(&[(0., 0.), (4., 0.), (4., 3.), (0., 3.)]), &[]);
+    }
+
+    #[test]
+    fn either_winding() {
+        let mut square = points(&[(0., 0.), (4., 0.), (4., 3.), (0., 3.)]);
+        square.reverse();
+        assert_fills(&square, &[]);
+    }
+
+    #[test]
+    fn concave() {
+        let l_shape = points(&[(0., 0.), (3., 0.), (3., 1.), (1., 1.), (1., 3.), (0., 3.)]);
+        assert_fills(&l_shape, &[]);
+        let star: Vec<Vector2> = (0..10)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 5. } else { 2. };
+                Vector2::new_euclidean(radius, i as f32 * std::f32::consts::PI / 5.)
+            })
+            .collect();
+        assert_fills(&star, &[]);
+        let comb = points(&[
+            (0., 0.),
+            (5., 0.),
+            (5., 3.),
+            (4., 3.),
+            (4., 1.),
+            (3., 1.),
+            (3., 3.),
+            (2., 3.),
+            (2., 1.),
+            (1., 1.),
+            (1., 3.),
+            (0., 3.),
+        ]);
+        assert_fills(&comb, &[]);
+    }
+
+    #[test]
+    fn with_holes() {
+        let square = points(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let hole = points(&[(2., 2.), (4., 2.), (4., 4.), (2., 4.)]);
+        assert_fills(&square, &[hole.clone()]);
+        let other_hole = points(&[(6., 6.), (8., 6.), (7., 8.)]);
+        assert_fills(&square, &[hole, other_hole]);
+        // a hole level with an outline vertex
+        let diamond = points(&[(0., -5.), (5., 0.), (0., 5.), (-5., 0.)]);
+        let hole = points(&[(-1., -1.), (1., -1.), (1., 0.), (-1., 1.)]);
+        assert_fills(&diamond, &[hole]);
+    }
+
+    #[test]
+    fn touching_holes() {
+        let square = points(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)]);
+        let (first, second) = (
+            points(&[(1., 1.), (2., 1.), (2., 2.), (1., 2.)]),
+            points(&[(2., 2.), (3., 2.), (3., 3.), (2., 3.)]),
+        );
+        assert_fills(&square, &[first, second]);
+        let (first, second) = (
+            points(&[(1., 1.), (3., 1.), (2., 2.)]),
+            points(&[(1., 3.), (2., 2.), (3., 3.)]),
+        );
+        assert_fills(&square, &[first, second]);
+        // a hole touching the outline
+        let outline = points(&[(0., 0.), (4., 0.), (4., 2.), (4., 4.), (0., 4.)]);
+        assert_fills(&outline, &[points(&[(4., 2.), (2., 3.), (2., 1.)])]);
+    }
+
+    #[test]
+    fn collinear_vertices() {
+        let square = points(&[
+            (0., 0.),
+            (1., 0.),
+            (2., 0.),
+            (2., 1.),
+            (2., 2.),
+            (1., 2.),
+            (0., 2.),
+        ]);
+        assert_fills(&square, &[]);
+        // a vertex lies on the edge of every ear
+        let notched = points(&[(0., 0.), (2., 0.), (2., 2.), (1., 1.), (0., 2.), (1., 0.)]);
+        let (vertices, indices) = triangulate(&notched, &[]);
+        triangles_area(&vertices, &indices);
+    }
+
+    #[test]
+    fn degenerate_input_does_not_panic() {
+        for outline in [
+            vec![],
+            points(&[(1., 1.)]),
+            points(&[(0., 0.), (1., 1.)]),
+            points(&[(0., 0.), (1., 1.), (2., 2.), (3., 3.)]),
+            points(&[(0., 0.), (0., 0.), (0., 0.)]),
+            points(&[(0., 0.), (1., 0.), (1., 0.), (1., 1.), (0., 0.)]),
+        ] {
+            let (vertices, indices) = triangulate(&outline, &[]);
+            assert!(indices
+                .iter()
+                .all(|&index| (index as usize) < vertices.len()));
+            assert!(triangles_area(&vertices, &indices) <= polygon_area(&outline) + EPSILON);
+        }
+        let square = points(&[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        let (_, indices) = triangulate(&square, &[points(&[(0.5, 0.5), (0.5, 0.5), (0.5, 0.5)])]);
+        assert!(!indices.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use cgmath::num_traits::Signed;
 
 use crate::*;
 use std::f32::consts::{FRAC_PI_2, PI};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 #[repr(C)]
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
@@ -135,6 +136,64 @@ impl Vector2 {
         projection_target
             .with_magnitude(self.dot(projection_target) / projection_target.magnitude())
     }
+
+    /// Returns the vector `t` of the way from this vector to `other`
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+        )
+    }
+
+    /// Returns the dot product of this vector rotated pi / 2 radians counter clockwise with
+    /// `rhs`, also known as the 2D cross product. It is positive if `rhs` is counter
+    /// clockwise from this vector, and its magnitude is the area of the parallelogram they
+    /// span
+    pub fn perp_dot(&self, rhs: &Self) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    /// Returns the smallest of each component of this vector and `rhs`
+    pub fn min(&self, rhs: &Self) -> Self {
+        Self::new(self.x.min(rhs.x), self.y.min(rhs.y))
+    }
+
+    /// Returns the largest of each component of this vector and `rhs`
+    pub fn max(&self, rhs: &Self) -> Self {
+        Self::new(self.x.max(rhs.x), self.y.max(rhs.y))
+    }
+
+    /// Returns this vector with the absolute value of each component
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Returns this vector with its magnitude clamped between `min` and `max`. A zero vector
+    /// has no direction, so it is returned unchanged
+    pub fn clamp_length(self, min: f32, max: f32) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude == 0. {
+            self
+        } else if magnitude < min {
+            self.scale(min / magnitude)
+        } else if magnitude > max {
+            self.scale(max / magnitude)
+        } else {
+            self
+        }
+    }
+
+    /// Returns whether each component of this vector is within `epsilon` of the same
+    /// component of `other`
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon
+    }
+
+    /// Reflects this vector off a surface facing `normal`, which should be normalized,
+    /// returning the result
+    pub fn reflect(self, normal: &Self) -> Self {
+        self.sub(&normal.scale(2. * self.dot(normal)))
+    }
 }
 
 // ops
@@ -195,3 +254,218 @@ impl Vector2 {
         self.y += x * rhs.i.y;
     }
 }
+
+// std::ops, for writing arithmetic with operators. These match the methods above
+impl Add for Vector2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Vector2::add(self, &rhs)
+    }
+}
+
+impl AddAssign for Vector2 {
+    fn add_assign(&mut self, rhs: Self) {
+        Vector2::add_assign(self, &rhs);
+    }
+}
+
+impl Sub for Vector2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Vector2::sub(self, &rhs)
+    }
+}
+
+impl SubAssign for Vector2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        Vector2::sub_assign(self, &rhs);
+    }
+}
+
+impl Mul<f32> for Vector2 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        self.scale(rhs)
+    }
+}
+
+impl Mul<Vector2> for f32 {
+    type Output = Vector2;
+
+    fn mul(self, rhs: Vector2) -> Vector2 {
+        rhs.scale(self)
+    }
+}
+
+impl MulAssign<f32> for Vector2 {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.scale_assign(rhs);
+    }
+}
+
+impl Div<f32> for Vector2 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl DivAssign<f32> for Vector2 {
+    fn div_assign(&mut self, rhs: f32) {
+        *self = *self / rhs;
+    }
+}
+
+impl Neg for Vector2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+// conversions
+impl From<(f32, f32)> for Vector2 {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vector2> for (f32, f32) {
+    fn from(vector: Vector2) -> Self {
+        (vector.x, vector.y)
+    }
+}
+
+impl From<cgmath::Vector2<f32>> for Vector2 {
+    fn from(vector: cgmath::Vector2<f32>) -> Self {
+        Self::new(vector.x, vector.y)
+    }
+}
+
+impl From<Vector2> for cgmath::Vector2<f32> {
+    fn from(vector: Vector2) -> Self {
+        cgmath::Vector2::new(vector.x, vector.y)
+    }
+}
+
+impl From<cgmath::Point2<f32>> for Vector2 {
+    fn from(point: cgmath::Point2<f32>) -> Self {
+        Self::new(point.x, point.y)
+    }
+}
+
+impl From<Vector2> for cgmath::Point2<f32> {
+    fn from(vector: Vector2) -> Self {
+        cgmath::Point2::new(vector.x, vector.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn a() -> Vector2 {
+        Vector2::new(3., -4.)
+    }
+
+    fn b() -> Vector2 {
+        Vector2::new(-1.5, 2.)
+    }
+
+    #[test]
+    fn operators_match_methods() {
+        assert_eq!(a() + b(), a().add(&b()));
+        assert_eq!(a() - b(), a().sub(&b()));
+        assert_eq!(a() * 2.5, a().scale(2.5));
+        assert_eq!(2.5 * a(), a().scale(2.5));
+        assert_eq!(a() / 2., a().scale(0.5));
+        assert_eq!(-a(), a().rotate_180());
+
+        let mut vector = a();
+        vector += b();
+        assert_eq!(vector, a().add(&b()));
+        vector -= b();
+        assert_eq!(vector, a());
+        vector *= 3.;
+        assert_eq!(vector, a().scale(3.));
+        vector /= 3.;
+        assert_eq!(vector, a());
+    }
+
+    #[test]
+    fn lerp() {
+        assert_eq!(a().lerp(&b(), 0.), a());
+        assert_eq!(a().lerp(&b(), 1.), b());
+        assert_eq!(a().lerp(&b(), 0.5), a().midpoint(&b()));
+        assert_eq!(a().lerp(&b(), 2.), Vector2::new(-6., 8.));
+    }
+
+    #[test]
+    fn perp_dot() {
+        assert_eq!(Vector2::RIGHT.perp_dot(&Vector2::UP), 1.);
+        assert_eq!(Vector2::UP.perp_dot(&Vector2::RIGHT), -1.);
+        assert_eq!(a().perp_dot(&a().scale(2.)), 0.);
+        // the same as the dot product with the vector rotated counter clockwise
+        assert_eq!(a().perp_dot(&b()), a().rotate_90_ccw().dot(&b()));
+    }
+
+    #[test]
+    fn min_max_abs() {
+        assert_eq!(a().min(&b()), Vector2::new(-1.5, -4.));
+        assert_eq!(a().max(&b()), Vector2::new(3., 2.));
+        assert_eq!(a().abs(), Vector2::new(3., 4.));
+        assert_eq!(b().abs(), Vector2::new(1.5, 2.));
+    }
+
+    #[test]
+    fn clamp_length() {
+        assert_eq!(a().clamp_length(1., 10.), a());
+        assert!((a().clamp_length(0., 2.).magnitude() - 2.).abs() < EPSILON);
+        assert!((a().clamp_length(8., 10.).magnitude() - 8.).abs() < EPSILON);
+        assert!(a()
+            .clamp_length(0., 2.)
+            .approx_eq(&Vector2::new(1.2, -1.6), EPSILON));
+        // a zero vector has no direction to lengthen it in
+        assert_eq!(Vector2::ZERO.clamp_length(1., 2.), Vector2::ZERO);
+    }
+
+    #[test]
+    fn reflect() {
+        assert_eq!(
+            Vector2::new(1., -1.).reflect(&Vector2::UP),
+            Vector2::new(1., 1.)
+        );
+        assert_eq!(Vector2::RIGHT.reflect(&Vector2::LEFT), Vector2::LEFT);
+        // vectors along the surface are unchanged
+        assert_eq!(Vector2::RIGHT.reflect(&Vector2::UP), Vector2::RIGHT);
+        // reflecting twice gives the original vector
+        let normal = Vector2::new(1., 2.).normalize();
+        assert!(a()
+            .reflect(&normal)
+            .reflect(&normal)
+            .approx_eq(&a(), EPSILON));
+        assert!((a().reflect(&normal).magnitude() - a().magnitude()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn conversions() {
+        let tuple: (f32, f32) = a().into();
+        assert_eq!(tuple, (3., -4.));
+        assert_eq!(Vector2::from((3., -4.)), a());
+
+        let vector: cgmath::Vector2<f32> = a().into();
+        assert_eq!(vector, cgmath::Vector2::new(3., -4.));
+        assert_eq!(Vector2::from(vector), a());
+
+        let point: cgmath::Point2<f32> = a().into();
+        assert_eq!(point, cgmath::Point2::new(3., -4.));
+        assert_eq!(Vector2::from(point), a());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::*;
+
+/// Identifies a stencil mask drawn with `Oge::draw_mask`. There can be up to 255 different
+/// masks in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilMask(u8);
+
+impl StencilMask {
+    /// Creates a mask with the given id. Panics if `id` is `0`, which is the value of the
+    /// stencil buffer wherever no mask has been drawn.
+    pub fn new(id: u8) -> Self {
+        if id == 0 {
+            panic!("StencilMask id must not be 0");
+        }
+        Self(id)
+    }
+
+    /// Returns the id of this mask
+    pub fn id(&self) -> u8 {
+        self.0
+    }
+}
+
+/// Describes which part of a stencil mask a render bundle is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskTest {
+    /// Only draw where the mask was drawn
+    Inside(StencilMask),
+    /// Only draw where the mask was not drawn
+    Outside(StencilMask),
+}
+
+/// Configure how a render bundle drawn with `Oge::draw_clipped` is clipped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClipConfiguration {
+    /// Only draw inside this region, in the same coordinates as `Oge::set_window_bounds`
+    pub scissor: Option<Bounds>,
+    /// Only draw inside or outside a stencil mask. The mask must have a `ZIndex` that is lower
+    /// than or the same as the render bundle's.
+    pub mask: Option<MaskTest>,
+}
@@ -0,0 +1,284 @@
+use crate::*;
+use std::f32::consts::TAU;
+
+/// Describes how a debug shape is drawn
+#[derive(Debug, Clone, Copy)]
+pub struct DebugStyle {
+    /// The color of the shape
+    pub color: Color,
+    /// The width of the shape's lines
+    pub width: f32,
+    /// How long, in seconds, the shape stays on screen. Shapes with a duration of `0.` are
+    /// only drawn for the frame they are added in
+    pub duration: f32,
+}
+
+impl Default for DebugStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::RED,
+            width: 2.,
+            duration: 0.,
+        }
+    }
+}
+
+impl DebugStyle {
+    /// A style with the given color, drawn for a single frame
+    pub fn color(color: Color) -> Self {
+        Self {
+            color,
+            ..Default::default()
+        }
+    }
+
+    /// Returns this style with the given line width
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Returns this style, kept on screen for `seconds` instead of a single frame
+    pub fn for_seconds(mut self, seconds: f32) -> Self {
+        self.duration = seconds;
+        self
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DebugVertex {
+    pub(crate) position: Vector2,
+    pub(crate) color: [f32; 4],
+}
+
+impl DebugVertex {
+    pub(crate) const SIZE: usize = std::mem::size_of::<Self>();
+}
+
+#[derive(Debug)]
+struct TimedShape {
+    vertices: Vec<DebugVertex>,
+    /// Seconds left until this shape is removed
+    remaining: f32,
+}
+
+/// Immediate-mode drawing of lines, shapes and labels for debugging.
+///
+/// Every shape added in a frame is batched into a single vertex buffer and drawn above all
+/// sprites. Access it with `Oge::debug_draw()`. Debug drawing can be switched on and off at
+/// runtime with the toggle key, which is `KeyCode::F3` by default.
+#[derive(Debug)]
+pub struct DebugDraw {
+    pub(crate) enabled: bool,
+    pub(crate) toggle_key: Option<KeyCode>,
+    /// Triangles that are only drawn this frame
+    vertices: Vec<DebugVertex>,
+    timed_shapes: Vec<TimedShape>,
+    /// Bound in place of a texture, which the debug pipeline never samples
+    pub(crate) texture: Option<Texture>,
+}
+
+impl DebugDraw {
+    /// The number of segments used to draw a circle
+    const CIRCLE_DETAIL: usize = 32;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            toggle_key: Some(KeyCode::F3),
+            vertices: vec![],
+            timed_shapes: vec![],
+            texture: None,
+        }
+    }
+
+    /// Enables or disables debug drawing. Shapes added while disabled are ignored.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.vertices.clear();
+            self.timed_shapes.clear();
+        }
+    }
+
+    /// Returns `true` if debug shapes are being drawn
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets the key that switches debug drawing on and off, or `None` to disable toggling
+    pub fn set_toggle_key(&mut self, toggle_key: Option<KeyCode>) {
+        self.toggle_key = toggle_key;
+    }
+
+    /// Draws a line from `from` to `to`
+    pub fn line(&mut self, from: Vector2, to: Vector2, style: DebugStyle) {
+        self.push(style, |vertices| push_segment(vertices, from, to, &style));
+    }
+
+    /// Draws a line through each of `points`
+    pub fn polyline(&mut self, points: &[Vector2], style: DebugStyle) {
+        self.push(style, |vertices| {
+            for segment in points.windows(2) {
+                push_segment(vertices, segment[0], segment[1], &style);
+            }
+        });
+    }
+
+    /// Draws a line from `from` to `to` with an arrow head at `to`
+    pub fn arrow(&mut self, from: Vector2, to: Vector2, style: DebugStyle) {
+        self.push(style, |vertices| {
+            let direction = to.sub(&from).normalize();
+            let head_length = 3_f32.sqrt() * style.width * 2.;
+            let base = to.sub(&direction.scale(head_length));
+            let side = direction.rotate_90_ccw().scale(style.width * 2.);
+            push_segment(vertices, from, base, &style);
+            push_triangle(
+                vertices,
+                [base.add(&side), base.sub(&side), to],
+                &style.color,
+            );
+        });
+    }
+
+    /// Draws the outline of a `width` by `height` rectangle centered on `center`
+    pub fn rect(&mut self, center: Vector2, width: f32, height: f32, style: DebugStyle) {
+        let half_size = Vector2::new(width, height).scale(0.5);
+        self.bounds(
+            &Bounds {
+                bottom_left: center.sub(&half_size),
+                top_right: center.add(&half_size),
+            },
+            style,
+        );
+    }
+
+    /// Draws the outline of a bounding box
+    pub fn bounds(&mut self, bounds: &Bounds, style: DebugStyle) {
+        let bottom_left = bounds.bottom_left;
+        let top_right = bounds.top_right;
+        self.polyline(
+            &[
+                bottom_left,
+                Vector2::new(top_right.x, bottom_left.y),
+                top_right,
+                Vector2::new(bottom_left.x, top_right.y),
+                bottom_left,
+            ],
+            style,
+        );
+    }
+
+    /// Draws the outline of a circle
+    pub fn circle(&mut self, center: Vector2, radius: f32, style: DebugStyle) {
+        let points: Vec<Vector2> = (0..=Self::CIRCLE_DETAIL)
+            .map(|i| {
+                let angle = TAU * i as f32 / Self::CIRCLE_DETAIL as f32;
+                center.add(&Vector2::new(angle.cos(), angle.sin()).scale(radius))
+            })
+            .collect();
+        self.polyline(&points, style);
+    }
+
+    /// Draws an X with arms `size` long, centered on `center`
+    pub fn cross(&mut self, center: Vector2, size: f32, style: DebugStyle) {
+        let arm = Vector2::new(size, size).scale(0.5);
+        let other_arm = arm.rotate_90_ccw();
+        self.line(center.sub(&arm), center.add(&arm), style);
+        self.line(center.sub(&other_arm), center.add(&other_arm), style);
+    }
+
+    /// Draws `text` with the bottom left of its first character at `position`. Characters are
+    /// `height` tall. Only letters, digits and common punctuation can be drawn, and lowercase
+    /// letters are drawn as uppercase.
+    pub fn label(&mut self, position: Vector2, text: &str, height: f32, style: DebugStyle) {
+        self.push(style, |vertices| {
+            DebugFont::layout(text, position, height, |from, to| {
+                push_segment(vertices, from, to, &style)
+            });
+        });
+    }
+
+    fn push(&mut self, style: DebugStyle, build: impl FnOnce(&mut Vec<DebugVertex>)) {
+        if !self.enabled {
+            return;
+        }
+        if style.duration > 0. {
+            let mut vertices = vec![];
+            build(&mut vertices);
+            self.timed_shapes.push(TimedShape {
+                vertices,
+                remaining: style.duration,
+            });
+        } else {
+            build(&mut self.vertices);
+        }
+    }
+
+    /// Returns the vertex buffer contents of every shape that should be drawn this frame
+    pub(crate) fn vertex_buffer_contents(&self) -> Vec<u8> {
+        let vertices = self
+            .vertices
+            .iter()
+            .chain(self.timed_shapes.iter().flat_map(|shape| shape.vertices.iter()));
+        let mut contents = vec![];
+        for vertex in vertices {
+            for component in [vertex.position.x, vertex.position.y]
+                .iter()
+                .chain(vertex.color.iter())
+            {
+                contents.extend_from_slice(&component.to_ne_bytes());
+            }
+        }
+        contents
+    }
+
+    /// Removes shapes that have been drawn for long enough
+    pub(crate) fn update(&mut self, delta_time: f32) {
+        self.vertices.clear();
+        self.timed_shapes.retain(|shape| shape.remaining > delta_time);
+        for shape in self.timed_shapes.iter_mut() {
+            shape.remaining -= delta_time;
+        }
+    }
+}
+
+/// Pushes a triangle, reordering its points so that it is counter-clockwise and is not
+/// culled
+fn push_triangle(vertices: &mut Vec<DebugVertex>, mut points: [Vector2; 3], color: &Color) {
+    let signed_area = (points[1].x - points[0].x) * (points[2].y - points[0].y)
+        - (points[2].x - points[0].x) * (points[1].y - points[0].y);
+    if signed_area < 0. {
+        points.swap(1, 2);
+    }
+    let color = color.as_f32_array();
+    vertices.extend(
+        points
+            .iter()
+            .map(|&position| DebugVertex { position, color }),
+    );
+}
+
+/// Pushes a rectangle `style.width` wide, running from `from` to `to`. Its ends are extended
+/// by half the width so that segments of a polyline meet without gaps.
+fn push_segment(vertices: &mut Vec<DebugVertex>, from: Vector2, to: Vector2, style: &DebugStyle) {
+    let half_width = style.width * 0.5;
+    let direction = if from.distance_to(&to) > 0. {
+        to.sub(&from).normalize()
+    } else {
+        Vector2::RIGHT
+    };
+    let along = direction.scale(half_width);
+    let across = direction.rotate_90_ccw().scale(half_width);
+    let start = from.sub(&along);
+    let end = to.add(&along);
+    let corners = [
+        start.sub(&across),
+        end.sub(&across),
+        end.add(&across),
+        start.add(&across),
+    ];
+    push_triangle(vertices, [corners[0], corners[1], corners[2]], &style.color);
+    push_triangle(vertices, [corners[0], corners[2], corners[3]], &style.color);
+}
@@ -0,0 +1,129 @@
+use crate::*;
+
+/// A tiny stroke font in the style of a 16-segment display, used to draw debug labels
+/// without loading a font. Glyphs sit in a cell that is 1 unit wide and 2 units tall, with
+/// the origin at the bottom left.
+pub(crate) struct DebugFont;
+
+impl DebugFont {
+    pub(crate) const CELL_HEIGHT: f32 = 2.;
+    /// Horizontal distance between the origins of two characters
+    pub(crate) const ADVANCE: f32 = 1.5;
+    /// Vertical distance between the origins of two lines
+    pub(crate) const LINE_HEIGHT: f32 = 2.75;
+
+    /// Returns the end points of the segment with the given name. The outline runs clockwise
+    /// from the top left (`a` to `h`) and the spokes run from the outline to the center
+    /// (`k` to `u`).
+    fn segment(name: u8) -> (Vector2, Vector2) {
+        let point = |x: f32, y: f32| Vector2::new(x, y);
+        let center = point(0.5, 1.);
+        match name {
+            b'a' => (point(0., 2.), point(0.5, 2.)),
+            b'b' => (point(0.5, 2.), point(1., 2.)),
+            b'c' => (point(1., 2.), point(1., 1.)),
+            b'd' => (point(1., 1.), point(1., 0.)),
+            b'e' => (point(1., 0.), point(0.5, 0.)),
+            b'f' => (point(0.5, 0.), point(0., 0.)),
+            b'g' => (point(0., 0.), point(0., 1.)),
+            b'h' => (point(0., 1.), point(0., 2.)),
+            b'k' => (point(0., 2.), center),
+            b'm' => (point(0.5, 2.), center),
+            b'n' => (point(1., 2.), center),
+            b'p' => (point(1., 1.), center),
+            b'r' => (point(1., 0.), center),
+            b's' => (point(0.5, 0.), center),
+            b't' => (point(0., 0.), center),
+            b'u' => (point(0., 1.), center),
+            // dots
+            b'o' => (point(0.5, 0.), point(0.5, 0.2)),
+            b'v' => (point(0.5, 1.3), point(0.5, 1.5)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the names of the segments that make up `character`. Lowercase letters are
+    /// drawn as uppercase and unknown characters as `?`.
+    fn glyph(character: char) -> &'static [u8] {
+        match character.to_ascii_uppercase() {
+            ' ' => b"",
+            '0' => b"abcdefghnt",
+            '1' => b"cdn",
+            '2' => b"abcpugfe",
+            '3' => b"abcdefp",
+            '4' => b"hupcd",
+            '5' | 'S' => b"abhupdef",
+            '6' => b"abhgfedpu",
+            '7' => b"abcd",
+            '8' => b"abcdefghpu",
+            '9' => b"abchupdef",
+            'A' => b"abcdghpu",
+            'B' => b"abcdefmsp",
+            'C' => b"abhgfe",
+            'D' => b"abcdefms",
+            'E' => b"abhgfeu",
+            'F' => b"abhgu",
+            'G' => b"abhgfedp",
+            'H' => b"hgcdup",
+            'I' => b"abmsef",
+            'J' => b"cdefg",
+            'K' => b"hgunr",
+            'L' => b"hgfe",
+            'M' => b"hgcdkn",
+            'N' => b"hgcdkr",
+            'O' => b"abcdefgh",
+            'P' => b"abchgup",
+            'Q' => b"abcdefghr",
+            'R' => b"abchgupr",
+            'T' => b"abms",
+            'U' => b"hgfedc",
+            'V' => b"hgtn",
+            'W' => b"hgcdtr",
+            'X' => b"kntr",
+            'Y' => b"kns",
+            'Z' => b"abntfe",
+            '-' => b"up",
+            '+' => b"upms",
+            '=' => b"upef",
+            '_' => b"ef",
+            '/' => b"nt",
+            '\\' => b"kr",
+            '|' => b"ms",
+            '(' | '<' => b"nr",
+            ')' | '>' => b"kt",
+            '*' => b"kmnrst",
+            '.' => b"o",
+            ',' => b"t",
+            ':' => b"ov",
+            '!' => b"mo",
+            '\'' => b"m",
+            _ => b"abcpo",
+        }
+    }
+
+    /// Calls `segment` with the end points of every segment in `text`, scaled so that
+    /// characters are `height` tall, with the first character's bottom left at `position`
+    pub(crate) fn layout(
+        text: &str,
+        position: Vector2,
+        height: f32,
+        mut segment: impl FnMut(Vector2, Vector2),
+    ) {
+        let scale = height / Self::CELL_HEIGHT;
+        let mut origin = position;
+        for character in text.chars() {
+            if character == '\n' {
+                origin = Vector2::new(position.x, origin.y - Self::LINE_HEIGHT * scale);
+                continue;
+            }
+            for name in Self::glyph(character) {
+                let (start, end) = Self::segment(*name);
+                segment(
+                    start.scale(scale).add(&origin),
+                    end.scale(scale).add(&origin),
+                );
+            }
+            origin.x += Self::ADVANCE * scale;
+        }
+    }
+}
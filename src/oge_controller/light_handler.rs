@@ -0,0 +1,27 @@
+use crate::*;
+
+/// Keeps track of the lights that shade normal-mapped sprites during the current frame.
+pub(crate) struct LightHandler {
+    pub(crate) ambient_light: Color,
+    pub(crate) point_lights: Vec<PointLight>,
+}
+
+impl LightHandler {
+    pub(crate) fn new() -> Self {
+        Self {
+            ambient_light: Color::new(0.25, 0.25, 0.25, 1.),
+            point_lights: Vec::with_capacity(PointLight::MAX_COUNT),
+        }
+    }
+
+    pub(crate) fn add_light(&mut self, light: PointLight) {
+        if self.point_lights.len() < PointLight::MAX_COUNT {
+            self.point_lights.push(light);
+        }
+    }
+
+    /// Lights only last a single frame
+    pub(crate) fn update(&mut self) {
+        self.point_lights.clear();
+    }
+}
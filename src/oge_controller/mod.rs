@@ -3,6 +3,11 @@ usemod![
     pub window_handler,
     pub input_monitor,
     pub render_pipeline_configuration,
+    pub clip_configuration,
+    pub profiler,
+    pub debug_draw,
+    pub(crate) debug_font,
     pub(crate) queued_operations,
     pub(crate) meta_handler,
+    pub(crate) light_handler,
 ];
\ No newline at end of file
@@ -1,4 +1,5 @@
 use crate::*;
+use std::rc::Rc;
 
 pub struct Oge<'a, 'b: 'a> {
     pub(crate) handlers: &'a mut OgeHandlers,
@@ -11,6 +12,9 @@ pub struct OgeHandlers {
     pub(crate) window_handler: WindowHandler,
     pub(crate) input_handler: InputHandler,
     pub(crate) meta_handler: MetaHandler,
+    pub(crate) light_handler: LightHandler,
+    pub(crate) profiler: Profiler,
+    pub(crate) debug_draw: DebugDraw,
 }
 
 impl OgeHandlers {
@@ -19,6 +23,9 @@ impl OgeHandlers {
             window_handler: WindowHandler::new(&window),
             input_handler: InputHandler::new(),
             meta_handler: MetaHandler::new(),
+            light_handler: LightHandler::new(),
+            profiler: Profiler::new(),
+            debug_draw: DebugDraw::new(),
         }
     }
 }
@@ -34,6 +41,26 @@ impl<'a, 'b> Oge<'a, 'b> {
         Texture::new(&self.render_state, config)
     }
 
+    /// Create a new normal map `Texture` for use as `SpriteConfiguration::normal_map`.
+    /// Unlike `Oge::create_texture`, the texels are not treated as sRGB colors.
+    pub fn create_normal_map(&self, config: &TextureConfiguration) -> Result<Texture> {
+        Texture::new_normal_map(&self.render_state, config)
+    }
+
+    /// Adds a light that shades normal-mapped sprites for this frame only. Lights should be
+    /// added during `update`, so that they are known before anything is drawn.
+    ///
+    /// At most `PointLight::MAX_COUNT` lights are used each frame.
+    pub fn add_light(&mut self, light: PointLight) {
+        self.handlers.light_handler.add_light(light);
+    }
+
+    /// Sets the color of the light that reaches every part of a normal-mapped sprite,
+    /// regardless of its orientation. Defaults to a dark grey.
+    pub fn set_ambient_light(&mut self, color: Color) {
+        self.handlers.light_handler.ambient_light = color;
+    }
+
     /// Draws a single render bundle
     pub fn draw_once(&mut self, render_bundle: impl IntoRenderBundle) {
         self.render_pass
@@ -48,19 +75,59 @@ impl<'a, 'b> Oge<'a, 'b> {
         }
     }
 
+    /// Returns the `DebugDraw` for adding debug shapes that are drawn above everything else
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.handlers.debug_draw
+    }
+
+    /// Draws a single render bundle, clipped to a region and/or a stencil mask
+    pub fn draw_clipped(&mut self, render_bundle: impl IntoRenderBundle, clip: ClipConfiguration) {
+        let mut render_bundle = render_bundle.get_render_bundle(&self);
+        if let Some(scissor) = clip.scissor {
+            match self.handlers.window_handler.scissor_rect(&scissor) {
+                Some(scissor_rect) => render_bundle.scissor_rect = Some(scissor_rect),
+                // entirely clipped
+                None => return,
+            }
+        }
+        if let Some(mask_test) = clip.mask {
+            let (stencil_mode, mask) = match mask_test {
+                MaskTest::Inside(mask) => (StencilMode::Inside, mask),
+                MaskTest::Outside(mask) => (StencilMode::Outside, mask),
+            };
+            render_bundle.stencil_mode = stencil_mode;
+            render_bundle.stencil_reference = mask.id() as u32;
+        }
+        self.render_pass.render_bundles.push(render_bundle);
+    }
+
+    /// Draws the shape of a render bundle into the stencil buffer as `mask`, without drawing
+    /// any color. Render bundles drawn with `Oge::draw_clipped` and a `MaskTest` on `mask` are
+    /// then clipped to the mask's mesh (not its texture) for the rest of the frame, as long as
+    /// their `ZIndex` is the same as or higher than the mask's.
+    ///
+    /// Where masks overlap, the one drawn last wins.
+    pub fn draw_mask(&mut self, render_bundle: impl IntoRenderBundle, mask: StencilMask) {
+        let mut render_bundle = render_bundle.get_render_bundle(&self);
+        render_bundle.stencil_mode = StencilMode::Write;
+        render_bundle.stencil_reference = mask.id() as u32;
+        self.render_pass.render_bundles.push(render_bundle);
+    }
+
     fn destructure_color_width_opacity(
         &self,
         color_width_opacity: Option<(Option<Color>, Option<f32>, Option<f32>)>,
-    ) -> (Color, f32, f32) {
+    ) -> DebugStyle {
         let (color, width, opacity) = color_width_opacity.unwrap_or((None, None, None));
-        let color = color.unwrap_or(Color::RED);
+        let color = color.unwrap_or(Color::RED).as_f32_array();
         let width = width.unwrap_or(5.);
         let opacity = opacity.unwrap_or(1.);
-        (color, width, opacity)
+        DebugStyle::color(Color::new(color[0], color[1], color[2], color[3] * opacity))
+            .with_width(width)
     }
 
     /// Draws a line for debugging. Do not use this for actual line drawing - make a curve and
-    /// modify its points instead.
+    /// modify its points instead. This is a shorthand for `DebugDraw::polyline`.
     ///
     /// `color_width_opacity` defaults to `(Color::RED, 5., 1.)`
     pub fn draw_debug_line(
@@ -68,30 +135,8 @@ impl<'a, 'b> Oge<'a, 'b> {
         points: Vec<Vector2>,
         color_width_opacity: Option<(Option<Color>, Option<f32>, Option<f32>)>,
     ) {
-        let (color, width, opacity) = self.destructure_color_width_opacity(color_width_opacity);
-        let default_texture =
-            if let Ok(texture) = self.create_texture(&TextureConfiguration::color(color)) {
-                texture
-            } else {
-                return;
-            };
-        let mut curve = if let Ok(curve) = sprite::Curve::new(sprite::CurveConfiguration {
-            label: Some("Debug Line"),
-            points,
-            width,
-            opacity: 1.,
-            style: CurveStyle::DoubleJointed,
-            z_index: ZIndex::AboveAll,
-            default_texture,
-            is_loop: false,
-            texture_projection_method: TextureProjectionMethod::SingleColor,
-        }) {
-            curve
-        } else {
-            return;
-        };
-
-        self.draw_once(curve.get_sprite());
+        let style = self.destructure_color_width_opacity(color_width_opacity);
+        self.debug_draw().polyline(&points, style);
     }
 
     /// Same effect as `Oge::draw_debug_line`, but adds an arrow tip to the end of the line.
@@ -100,40 +145,14 @@ impl<'a, 'b> Oge<'a, 'b> {
         points: Vec<Vector2>,
         color_width_opacity: Option<(Option<Color>, Option<f32>, Option<f32>)>,
     ) {
-        let (color, width, opacity) = self.destructure_color_width_opacity(color_width_opacity);
-
-        let default_texture =
-            if let Ok(texture) = self.create_texture(&TextureConfiguration::color(color)) {
-                texture
-            } else {
-                return;
-            };
-
-        let arrow_head_width = width * 2.;
-        let arrow_head_height = 3_f32.sqrt() * arrow_head_width;
-        let mut arrow_head_sprite = if let Ok(sprite) = self.create_sprite(SpriteConfiguration {
-            label: Some("Debug Arrow Head"),
-            mesh: SpriteMesh::new_triangle([
-                Vector2::new(-arrow_head_width, 0.),
-                Vector2::new(arrow_head_width, 0.),
-                Vector2::new(0., arrow_head_height),
-            ]),
-            z_index: ZIndex::AboveAll,
-            default_texture,
-            opacity,
-            texture_projection_method: TextureProjectionMethod::SingleColor,
-        }) {
-            sprite
-        } else {
+        if points.len() < 2 {
             return;
-        };
-        arrow_head_sprite.set_position(*points.last().unwrap());
-        arrow_head_sprite.transform(&Matrix2::rotation(
-            points[points.len() - 1].relative_direction(&points[points.len() - 2]),
-        ));
-
-        self.draw_debug_line(points, color_width_opacity);
-        self.draw_once(&arrow_head_sprite);
+        }
+        let style = self.destructure_color_width_opacity(color_width_opacity);
+        let last = points.len() - 1;
+        self.debug_draw().polyline(&points[..last], style);
+        self.debug_draw()
+            .arrow(points[last - 1], points[last], style);
     }
 
     /// Configures the render pipeline used
@@ -142,6 +161,17 @@ impl<'a, 'b> Oge<'a, 'b> {
             .push(Operation::UpdateRenderPipelineConfiguration(config));
     }
 
+    /// Configures how frames are profiled. Returns an error if the trace file could not be
+    /// created.
+    pub fn configure_profiler(&mut self, config: ProfilerConfiguration) -> Result<()> {
+        self.handlers.profiler.configure(config)
+    }
+
+    /// Returns the timings of the previous frame
+    pub fn frame_stats(&self) -> FrameStats {
+        self.handlers.profiler.frame_stats()
+    }
+
     /// Sets the region of the coordinate system that should be displayed to the window.
     /// It is your responsibilty to ensure that this region has the same aspect ratio as the
     /// window.
@@ -273,7 +303,17 @@ impl<'a, 'b> Oge<'a, 'b> {
                 .begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: None,
                     color_attachments: &_color_attachments,
-                    depth_stencil_attachment: None,
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &render_pass_resources.depth_stencil_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0),
+                            store: false,
+                        }),
+                    }),
                 });
 
         Self {
@@ -288,6 +328,46 @@ impl<'a, 'b> Oge<'a, 'b> {
         }
     }
 
+    /// Toggles debug drawing if its key was just pressed, then draws every debug shape that
+    /// is still alive in a single render bundle.
+    pub(crate) fn draw_debug_shapes(&mut self) {
+        if let Some(toggle_key) = self.handlers.debug_draw.toggle_key {
+            if self.get_key_status(toggle_key).just_pressed() {
+                let enabled = !self.handlers.debug_draw.is_enabled();
+                self.handlers.debug_draw.set_enabled(enabled);
+            }
+        }
+        if self.handlers.debug_draw.texture.is_none() {
+            self.handlers.debug_draw.texture = Texture::default(self).ok();
+        }
+
+        let contents = self.handlers.debug_draw.vertex_buffer_contents();
+        if let (Some(texture), false) = (&self.handlers.debug_draw.texture, contents.is_empty()) {
+            let vertex_count = (contents.len() / DebugVertex::SIZE) as u32;
+            let indices: Vec<u32> = (0..vertex_count).collect();
+            let device_wrapper = &self.render_state.device_wrapper;
+            let (index_buffer, index_format) = device_wrapper.create_index_buffer(&indices);
+            let render_bundle = RenderBundle {
+                bind_group: Sprite::create_bind_group(self, &Affine2::default(), texture, None, 1.),
+                vertex_buffer: Rc::new(device_wrapper.create_vertex_buffer(None, &contents)),
+                index_buffer: Rc::new(index_buffer),
+                index_format,
+                index_count: vertex_count,
+                pipeline: PipelineKind::Debug,
+                instance_buffer: None,
+                instance_count: 1,
+                stencil_mode: StencilMode::None,
+                stencil_reference: 0,
+                scissor_rect: None,
+                z_index: ZIndex::AboveAll,
+            };
+            self.render_pass.render_bundles.push(render_bundle);
+        }
+
+        let delta_time = self.delta_time();
+        self.handlers.debug_draw.update(delta_time);
+    }
+
     /// Consume this controller and return its `RenderPass` for drawing.
     pub(crate) fn finish(self) -> (RenderPass<'b>, Vec<Operation>) {
         (self.render_pass, self.queued_operations)
@@ -0,0 +1,186 @@
+use crate::*;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Timings for a single frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    /// Time spent in the `update` method of every script
+    pub update: Duration,
+    /// Time spent in the `render` method of every script, which includes building render
+    /// bundles
+    pub render: Duration,
+    /// Time spent sorting render bundles by their `ZIndex`
+    pub sorting: Duration,
+    /// Time spent recording draw commands and submitting them to the GPU
+    pub submission: Duration,
+    /// Time the GPU spent executing the latest timed frame that finished during this frame,
+    /// which is usually a frame or two earlier. This is `None` if none finished, and unless
+    /// GPU timing is enabled with `ProfilerConfiguration::gpu_timing` and the adapter
+    /// supports timestamp queries
+    pub gpu: Option<Duration>,
+    /// Total CPU time spent on the frame
+    pub total: Duration,
+    /// The number of render bundles that were drawn
+    pub render_bundle_count: usize,
+}
+
+/// Configure the profiler
+#[derive(Debug, Clone, Default)]
+pub struct ProfilerConfiguration {
+    /// Measure how long the GPU spends on each frame using timestamp queries. The timings
+    /// are read back without waiting for the GPU, so they arrive a frame or two late.
+    pub gpu_timing: bool,
+    /// Write the timings of every frame to this file, in the Chrome trace event format. The
+    /// file can be opened with `chrome://tracing` or Perfetto.
+    pub trace_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum FramePhase {
+    Update,
+    Render,
+    Sorting,
+    Submission,
+}
+
+impl FramePhase {
+    fn name(&self) -> &'static str {
+        match self {
+            FramePhase::Update => "update",
+            FramePhase::Render => "render",
+            FramePhase::Sorting => "sorting",
+            FramePhase::Submission => "submission",
+        }
+    }
+}
+
+/// Collects the timings of each frame
+pub(crate) struct Profiler {
+    gpu_timing: bool,
+    trace: Option<BufWriter<File>>,
+    /// Trace timestamps are measured from this instant
+    epoch: Instant,
+    frame_start: Instant,
+    phase_start: Instant,
+    current: FrameStats,
+    last: FrameStats,
+}
+
+impl Profiler {
+    const CPU_THREAD: u32 = 0;
+    const GPU_THREAD: u32 = 1;
+
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            gpu_timing: false,
+            trace: None,
+            epoch: now,
+            frame_start: now,
+            phase_start: now,
+            current: FrameStats::default(),
+            last: FrameStats::default(),
+        }
+    }
+
+    pub(crate) fn configure(&mut self, config: ProfilerConfiguration) -> Result<()> {
+        self.gpu_timing = config.gpu_timing;
+        self.trace = match config.trace_path {
+            Some(path_buf) => {
+                let mut trace = File::create(&path_buf)
+                    .map(BufWriter::new)
+                    .map_err(|_| ProfilerError::create_trace(&path_buf))?;
+                // The closing bracket may be left out of a Chrome trace, so events can be
+                // written as they happen
+                write!(
+                    trace,
+                    "[\n{},\n{},\n",
+                    Self::thread_name_event(Self::CPU_THREAD, "CPU"),
+                    Self::thread_name_event(Self::GPU_THREAD, "GPU"),
+                )
+                .map_err(|_| ProfilerError::create_trace(&path_buf))?;
+                Some(trace)
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    pub(crate) fn gpu_timing(&self) -> bool {
+        self.gpu_timing
+    }
+
+    /// Returns the timings of the last complete frame
+    pub(crate) fn frame_stats(&self) -> FrameStats {
+        self.last
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+        self.current = FrameStats::default();
+    }
+
+    pub(crate) fn begin_phase(&mut self) {
+        self.phase_start = Instant::now();
+    }
+
+    pub(crate) fn end_phase(&mut self, phase: FramePhase) {
+        let duration = self.phase_start.elapsed();
+        *match phase {
+            FramePhase::Update => &mut self.current.update,
+            FramePhase::Render => &mut self.current.render,
+            FramePhase::Sorting => &mut self.current.sorting,
+            FramePhase::Submission => &mut self.current.submission,
+        } = duration;
+        self.write_event(phase.name(), Self::CPU_THREAD, self.phase_start, duration);
+    }
+
+    pub(crate) fn end_frame(&mut self, gpu: Option<Duration>, render_bundle_count: usize) {
+        self.current.total = self.frame_start.elapsed();
+        self.current.gpu = gpu;
+        self.current.render_bundle_count = render_bundle_count;
+        self.write_event("frame", Self::CPU_THREAD, self.frame_start, self.current.total);
+        if let Some(gpu) = gpu {
+            // The GPU's clock can't be compared with the CPU's, so the GPU's work is shown as
+            // starting when this frame was submitted, although it belongs to an earlier one
+            self.write_event("gpu", Self::GPU_THREAD, self.phase_start, gpu);
+        }
+        if let Some(trace) = &mut self.trace {
+            if trace.flush().is_err() {
+                self.trace = None;
+            }
+        }
+        self.last = self.current;
+    }
+
+    fn thread_name_event(thread: u32, name: &str) -> String {
+        format!(
+            r#"{{"name":"thread_name","ph":"M","pid":0,"tid":{},"args":{{"name":"{}"}}}}"#,
+            thread, name
+        )
+    }
+
+    /// Writes a complete event to the trace, if there is one. The trace is closed if it can no
+    /// longer be written to.
+    fn write_event(&mut self, name: &str, thread: u32, start: Instant, duration: Duration) {
+        let start = start.saturating_duration_since(self.epoch);
+        if let Some(trace) = &mut self.trace {
+            let result = writeln!(
+                trace,
+                r#"{{"name":"{}","ph":"X","pid":0,"tid":{},"ts":{},"dur":{}}},"#,
+                name,
+                thread,
+                start.as_micros(),
+                duration.as_micros()
+            );
+            if result.is_err() {
+                self.trace = None;
+            }
+        }
+    }
+}
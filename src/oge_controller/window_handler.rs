@@ -59,13 +59,9 @@ impl WindowHandler {
             .bottom_left
             .add(&Vector2::new(frac_width_2, frac_height_2));
 
-        self.affine2 = Affine2 {
-            matrix2: Matrix2 {
-                i: Vector2::new(2.0 / width, 0.0),
-                j: Vector2::new(0.0, 2.0 / height),
-            },
-            translation: Vector2::new(-center.x, -center.y),
-        };
+        // move the center of the region to the origin, then scale it to fit
+        self.affine2 = Affine2::stretch(2.0 / width, 2.0 / height)
+            .compose(&Affine2::translation(center.rotate_180()));
 
         let (window_width, window_height) =
             (self.dimensions.width as f32, self.dimensions.height as f32);
@@ -80,6 +76,37 @@ impl WindowHandler {
         self.bounds = bounds;
     }
 
+    /// Converts a region of the viewable region to the rectangle of pixels it covers,
+    /// clamped to the window. Returns `None` if it does not cover any pixels.
+    pub(crate) fn scissor_rect(&self, region: &Bounds) -> Option<ScissorRect> {
+        let (window_width, window_height) =
+            (self.dimensions.width as f32, self.dimensions.height as f32);
+        let to_pixel_x = |x: f32| {
+            ((x - self.bounds.bottom_left.x) / self.bounds.width() * window_width)
+                .max(0.)
+                .min(window_width)
+        };
+        let to_pixel_y = |y: f32| {
+            ((self.bounds.top_right.y - y) / self.bounds.height() * window_height)
+                .max(0.)
+                .min(window_height)
+        };
+
+        let (left, right) = (to_pixel_x(region.bottom_left.x), to_pixel_x(region.top_right.x));
+        let (top, bottom) = (to_pixel_y(region.top_right.y), to_pixel_y(region.bottom_left.y));
+        let (left, right) = (left.min(right).round(), left.max(right).round());
+        let (top, bottom) = (top.min(bottom).round(), top.max(bottom).round());
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some(ScissorRect {
+            x: left as u32,
+            y: top as u32,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+
     pub(crate) fn resize(&mut self, dimensions: WindowDimensions) {
         self.dimensions = dimensions;
         self.dimensions_updated = true;
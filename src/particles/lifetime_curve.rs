@@ -0,0 +1,80 @@
+use crate::*;
+
+/// A value that can be blended between two keys of a `LifetimeCurve`
+pub trait Interpolate: Copy {
+    /// Returns the value `t` of the way from `self` to `other`
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vector2 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.add(&other.sub(self).scale(t))
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+/// A value that changes over the lifetime of a particle.
+///
+/// The curve is made of `(t, value)` keys, where `t` is `0.` when a particle is spawned and
+/// `1.` when it dies. Values between two keys are linearly interpolated, and values before
+/// the first or after the last key are held.
+#[derive(Debug, Clone)]
+pub struct LifetimeCurve<T: Interpolate> {
+    pub(crate) keys: Vec<(f32, T)>,
+}
+
+impl<T: Interpolate> LifetimeCurve<T> {
+    /// Creates a new curve from the given keys. Panics if `keys` is empty.
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        if keys.is_empty() {
+            panic!("LifetimeCurve must have at least 1 key");
+        }
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { keys }
+    }
+
+    /// A curve that is always `value`
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0., value)],
+        }
+    }
+
+    /// A curve that goes from `start` when a particle is spawned to `end` when it dies
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0., start), (1., end)],
+        }
+    }
+
+    /// Returns the value of this curve at `t`
+    pub fn sample(&self, t: f32) -> T {
+        let first = &self.keys[0];
+        if t <= first.0 {
+            return first.1;
+        }
+        for window in self.keys.windows(2) {
+            let (start, end) = (&window[0], &window[1]);
+            if t < end.0 {
+                let span = end.0 - start.0;
+                return if span > 0. {
+                    start.1.interpolate(&end.1, (t - start.0) / span)
+                } else {
+                    end.1
+                };
+            }
+        }
+        self.keys[self.keys.len() - 1].1
+    }
+}
@@ -0,0 +1 @@
+usemod![pub lifetime_curve, pub particle_emitter];
@@ -0,0 +1,400 @@
+use crate::*;
+use rand::Rng;
+
+/// Determines which coordinate space particles live in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleSpace {
+    /// Particles are left behind where they were spawned when the emitter moves, like smoke
+    /// or dust
+    World,
+    /// Particles move along with the emitter, like a flame on a torch
+    Local,
+}
+
+/// A number of particles that are all spawned at once
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleBurst {
+    /// The time in seconds, after the emitter is created or restarted, at which this burst
+    /// happens
+    pub time: f32,
+    /// The number of particles to spawn
+    pub count: u32,
+    /// If set, the burst repeats every `interval` seconds after `time`
+    pub interval: Option<f32>,
+}
+
+impl ParticleBurst {
+    /// Returns how many times this burst has happened at `elapsed` seconds
+    fn occurrences(&self, elapsed: f32) -> u32 {
+        if elapsed <= self.time {
+            return 0;
+        }
+        match self.interval {
+            Some(interval) if interval > 0. => ((elapsed - self.time) / interval) as u32 + 1,
+            _ => 1,
+        }
+    }
+}
+
+pub struct ParticleEmitterConfiguration<'a> {
+    /// A label used for debugging
+    pub label: Option<&'a str>,
+    /// The shape of a single particle with a size of `1.`. Particles are drawn as instances of
+    /// this mesh, so it should be small, e.g. `SpriteMesh::new_elipse(0.5, 0.5, 8)`
+    pub mesh: SpriteMesh,
+    /// The texture drawn on each particle. It is scaled to fit the mesh
+    pub texture: Texture,
+    /// Same as the `z_index` attribute on `SpriteConfiguration`
+    pub z_index: ZIndex,
+    /// The opacity of the whole emitter, multiplied with `opacity_over_lifetime`
+    pub opacity: f32,
+    /// The coordinate space that spawned particles live in
+    pub space: ParticleSpace,
+    /// The position of the emitter
+    pub position: Vector2,
+    /// The maximum number of particles that can be alive at once. Particles are not spawned
+    /// while this many are alive
+    pub max_count: usize,
+    /// The number of particles spawned every second
+    pub emission_rate: f32,
+    /// Particles spawned all at once at given times
+    pub bursts: Vec<ParticleBurst>,
+    /// The range that the lifetime (in seconds) of each particle is randomly chosen from
+    pub lifetime: (f32, f32),
+    /// The range that the initial speed of each particle is randomly chosen from
+    pub speed: (f32, f32),
+    /// The direction particles are emitted in, with `0.` being directly up
+    pub direction: f32,
+    /// The angle of the cone that particles are emitted in, centered on `direction`. `TAU`
+    /// emits particles in every direction
+    pub spread: f32,
+    /// Particles are spawned at a random point within this distance of the emitter
+    pub spawn_radius: f32,
+    /// The range that the initial size of each particle is randomly chosen from
+    pub size: (f32, f32),
+    /// The range that the angular velocity (in radians per second) of each particle is
+    /// randomly chosen from
+    pub angular_velocity: (f32, f32),
+    /// A constant acceleration applied to every particle
+    pub gravity: Vector2,
+    /// The fraction of its velocity that a particle loses every second
+    pub drag: f32,
+    /// Multiplies the initial size of each particle over its lifetime
+    pub size_over_lifetime: LifetimeCurve<f32>,
+    /// The opacity of each particle over its lifetime
+    pub opacity_over_lifetime: LifetimeCurve<f32>,
+    /// Multiplies the texture of each particle over its lifetime
+    pub color_over_lifetime: LifetimeCurve<Color>,
+}
+
+impl ParticleEmitterConfiguration<'_> {
+    pub fn default(oge: &Oge) -> Result<Self> {
+        Ok(Self {
+            label: None,
+            mesh: SpriteMesh::new_rectangle(1., 1.),
+            texture: Texture::default(oge)?,
+            z_index: ZIndex::default(),
+            opacity: 1.,
+            space: ParticleSpace::World,
+            position: Vector2::ZERO,
+            max_count: 1024,
+            emission_rate: 10.,
+            bursts: vec![],
+            lifetime: (1., 1.),
+            speed: (100., 100.),
+            direction: 0.,
+            spread: std::f32::consts::TAU,
+            spawn_radius: 0.,
+            size: (8., 8.),
+            angular_velocity: (0., 0.),
+            gravity: Vector2::ZERO,
+            drag: 0.,
+            size_over_lifetime: LifetimeCurve::constant(1.),
+            opacity_over_lifetime: LifetimeCurve::linear(1., 0.),
+            color_over_lifetime: LifetimeCurve::constant(Color::WHITE),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Particle {
+    pub(crate) position: Vector2,
+    pub(crate) velocity: Vector2,
+    pub(crate) rotation: f32,
+    pub(crate) angular_velocity: f32,
+    pub(crate) size: f32,
+    pub(crate) age: f32,
+    pub(crate) lifetime: f32,
+}
+
+/// The data uploaded to the instance buffer for each particle
+pub(crate) struct ParticleInstance;
+
+impl ParticleInstance {
+    /// center (8), size (4), rotation (4), color (16)
+    pub(crate) const SIZE: usize = 32;
+}
+
+/// Spawns, simulates and draws many small particles.
+///
+/// All the particles of an emitter are drawn with a single draw call, so an emitter is much
+/// cheaper than drawing a `Sprite` for each particle.
+#[derive(Debug)]
+pub struct ParticleEmitter {
+    pub(crate) label: Option<Box<str>>,
+    pub(crate) mesh: SpriteMesh,
+    pub(crate) texture: Texture,
+    pub(crate) z_index: ZIndex,
+    pub(crate) opacity: f32,
+    pub(crate) space: ParticleSpace,
+    pub(crate) position: Vector2,
+    pub(crate) max_count: usize,
+    pub(crate) emission_rate: f32,
+    pub(crate) bursts: Vec<ParticleBurst>,
+    pub(crate) lifetime: (f32, f32),
+    pub(crate) speed: (f32, f32),
+    pub(crate) direction: f32,
+    pub(crate) spread: f32,
+    pub(crate) spawn_radius: f32,
+    pub(crate) size: (f32, f32),
+    pub(crate) angular_velocity: (f32, f32),
+    pub(crate) gravity: Vector2,
+    pub(crate) drag: f32,
+    pub(crate) size_over_lifetime: LifetimeCurve<f32>,
+    pub(crate) opacity_over_lifetime: LifetimeCurve<f32>,
+    pub(crate) color_over_lifetime: LifetimeCurve<Color>,
+    pub(crate) particles: Vec<Particle>,
+    pub(crate) emitting: bool,
+    pub(crate) elapsed: f32,
+    /// Fractional particles carried over between updates, so low emission rates still spawn
+    pub(crate) emission_accumulator: f32,
+}
+
+/// Returns a random value in `range`
+fn sample_range(rng: &mut impl Rng, range: (f32, f32)) -> f32 {
+    range.0 + (range.1 - range.0) * rng.gen::<f32>()
+}
+
+impl ParticleEmitter {
+    /// Creates a new emitter from the given `ParticleEmitterConfiguration`. It starts emitting
+    /// immediately.
+    pub fn new(config: ParticleEmitterConfiguration) -> Self {
+        let mut mesh = config.mesh;
+        mesh.update_texture_coordinates(&config.texture, &TextureProjectionMethod::ScaleToFit);
+        let mut this = Self {
+            label: config.label.map(|label| label.to_owned().into_boxed_str()),
+            mesh,
+            texture: config.texture,
+            z_index: config.z_index,
+            opacity: 1.,
+            space: config.space,
+            position: Vector2::ZERO,
+            max_count: config.max_count,
+            emission_rate: config.emission_rate,
+            bursts: config.bursts,
+            lifetime: config.lifetime,
+            speed: config.speed,
+            direction: config.direction,
+            spread: config.spread,
+            spawn_radius: config.spawn_radius,
+            size: config.size,
+            angular_velocity: config.angular_velocity,
+            gravity: config.gravity,
+            drag: config.drag,
+            size_over_lifetime: config.size_over_lifetime,
+            opacity_over_lifetime: config.opacity_over_lifetime,
+            color_over_lifetime: config.color_over_lifetime,
+            particles: Vec::with_capacity(config.max_count),
+            emitting: true,
+            elapsed: 0.,
+            emission_accumulator: 0.,
+        };
+        this.set_position(config.position);
+        this.set_opacity(config.opacity);
+        this
+    }
+
+    /// Ages, moves and removes particles, then spawns new ones from the emission rate and
+    /// bursts. Call this once per update with `oge.delta_time()`.
+    pub fn update(&mut self, delta_time: f32) {
+        let velocity_retained = (1. - self.drag * delta_time).max(0.);
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            particle.age += delta_time;
+            if particle.age >= particle.lifetime {
+                self.particles.swap_remove(i);
+                continue;
+            }
+            particle
+                .velocity
+                .add_assign(&self.gravity.scale(delta_time));
+            particle.velocity.scale_assign(velocity_retained);
+            particle
+                .position
+                .add_assign(&particle.velocity.scale(delta_time));
+            particle.rotation += particle.angular_velocity * delta_time;
+            i += 1;
+        }
+
+        let previous_elapsed = self.elapsed;
+        self.elapsed += delta_time;
+        if !self.emitting {
+            return;
+        }
+
+        self.emission_accumulator += self.emission_rate * delta_time;
+        let mut count = self.emission_accumulator as u32;
+        self.emission_accumulator -= count as f32;
+        for burst in self.bursts.iter() {
+            count += burst.count
+                * (burst.occurrences(self.elapsed) - burst.occurrences(previous_elapsed));
+        }
+        self.emit(count);
+    }
+
+    /// Spawns `count` particles immediately, as long as there is room for them
+    pub fn emit(&mut self, count: u32) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..count {
+            if self.particles.len() >= self.max_count {
+                break;
+            }
+
+            let offset = Vector2::new_euclidean(
+                rng.gen::<f32>() * std::f32::consts::TAU,
+                self.spawn_radius * rng.gen::<f32>().sqrt(),
+            );
+            let direction = self.direction + self.spread * (rng.gen::<f32>() - 0.5);
+            self.particles.push(Particle {
+                position: match self.space {
+                    ParticleSpace::World => self.position.add(&offset),
+                    ParticleSpace::Local => offset,
+                },
+                velocity: Vector2::new_euclidean(direction, sample_range(&mut rng, self.speed)),
+                rotation: 0.,
+                angular_velocity: sample_range(&mut rng, self.angular_velocity),
+                size: sample_range(&mut rng, self.size),
+                age: 0.,
+                lifetime: sample_range(&mut rng, self.lifetime),
+            });
+        }
+    }
+
+    /// Removes all particles and restarts the timing of bursts
+    pub fn restart(&mut self) {
+        self.particles.clear();
+        self.elapsed = 0.;
+        self.emission_accumulator = 0.;
+    }
+
+    /// Removes all particles that are alive
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Starts or stops spawning particles from the emission rate and bursts. Particles that
+    /// are alive keep being simulated.
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+    }
+
+    /// Returns whether particles are spawned from the emission rate and bursts
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Returns the number of particles that are alive
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Sets the position of the emitter. Particles in `ParticleSpace::Local` move with it.
+    pub fn set_position(&mut self, position: Vector2) {
+        self.position = position;
+        if self.space == ParticleSpace::Local {
+            self.mesh.affine2.translation = position;
+        }
+    }
+
+    /// Returns the position of the emitter
+    pub fn position(&self) -> Vector2 {
+        self.position
+    }
+
+    /// Sets the direction that particles are emitted in, with `0.` being directly up
+    pub fn set_direction(&mut self, direction: f32) {
+        self.direction = direction;
+    }
+
+    /// Sets the range that the initial speed of each particle is chosen from
+    pub fn set_speed(&mut self, speed: (f32, f32)) {
+        self.speed = speed;
+    }
+
+    /// Sets the number of particles spawned every second
+    pub fn set_emission_rate(&mut self, emission_rate: f32) {
+        self.emission_rate = emission_rate;
+    }
+
+    /// Set the opacity of the whole emitter. Values outside of `0.` to `1.` will be capped.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0., 1.);
+    }
+
+    /// Returns the opacity of the whole emitter
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Returns the per-particle data for the instance buffer. There is always at least one
+    /// instance, because buffers cannot be empty.
+    pub(crate) fn instance_buffer_contents(&self) -> Vec<u8> {
+        let mut contents = vec![0u8; ParticleInstance::SIZE * self.particles.len().max(1)];
+        for (particle, dst) in self
+            .particles
+            .iter()
+            .zip(contents.chunks_exact_mut(ParticleInstance::SIZE))
+        {
+            let t = particle.age / particle.lifetime;
+            let mut color = self.color_over_lifetime.sample(t).as_f32_array();
+            color[3] *= self.opacity_over_lifetime.sample(t);
+
+            for (i, component) in [
+                particle.position.x,
+                particle.position.y,
+                particle.size * self.size_over_lifetime.sample(t),
+                particle.rotation,
+            ]
+            .iter()
+            .chain(color.iter())
+            .enumerate()
+            {
+                dst[i * 4..i * 4 + 4].copy_from_slice(&component.to_ne_bytes());
+            }
+        }
+        contents
+    }
+}
+
+impl IntoRenderBundle for &ParticleEmitter {
+    fn get_render_bundle(self, oge: &Oge) -> RenderBundle {
+        let mut render_bundle = Sprite::create_render_bundle(
+            oge,
+            self.label.as_deref(),
+            &self.mesh,
+            &self.texture,
+            None,
+            self.opacity,
+            self.z_index,
+        );
+        render_bundle.pipeline = PipelineKind::Particle;
+        render_bundle.instance_buffer = Some(
+            oge.render_state
+                .device_wrapper
+                .create_instance_buffer(self.label.as_deref(), &self.instance_buffer_contents()),
+        );
+        render_bundle.instance_count = self.particles.len() as u32;
+        render_bundle
+    }
+}
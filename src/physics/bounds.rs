@@ -24,4 +24,21 @@ impl Bounds {
             y: self.height() / 2.0 + self.bottom_left.y,
         }
     }
+
+    /// Returns `true` if this bounding box overlaps `other`. Boxes that only share an edge
+    /// do not overlap.
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.bottom_left.x < other.top_right.x
+            && other.bottom_left.x < self.top_right.x
+            && self.bottom_left.y < other.top_right.y
+            && other.bottom_left.y < self.top_right.y
+    }
+
+    /// Returns `true` if `point` lies within this bounding box
+    pub fn contains(&self, point: &crate::Vector2) -> bool {
+        point.x >= self.bottom_left.x
+            && point.x <= self.top_right.x
+            && point.y >= self.bottom_left.y
+            && point.y <= self.top_right.y
+    }
 }
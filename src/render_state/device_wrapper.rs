@@ -26,7 +26,14 @@ impl DeviceWrapper {
             .expect("Could not acquire adapter.");
 
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    // used for GPU frame timings, if available
+                    features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                    ..Default::default()
+                },
+                None,
+            )
             .await
             .expect("Could not acquire device.");
 
@@ -90,15 +97,36 @@ impl DeviceWrapper {
                     },
                     count: None,
                 },
+                // normal map
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        comparison: false,
+                        filtering: true,
+                    },
+                    count: None,
+                },
             ],
         })
     }
 
-    /// Creates a new BindGroup, using `self.texture_bind_group_layout`.
+    /// Creates a new BindGroup, using `self.texture_bind_group_layout`. Sprites without a
+    /// normal map should pass their regular texture as `normal_map`; it is never sampled.
     pub(crate) fn create_texture_bind_group(
         &self,
-        texture_view: &wgpu::TextureView,
-        sampler: &wgpu::Sampler,
+        texture: &Texture,
+        normal_map: &Texture,
         uniform_buffer_contents: &[u8],
     ) -> wgpu::BindGroup {
         self.device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -107,11 +135,11 @@ impl DeviceWrapper {
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                    resource: wgpu::BindingResource::TextureView(&texture.texture_view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
@@ -127,11 +155,147 @@ impl DeviceWrapper {
                         size: Some(RenderState::UNIFORM_BUFFER_SIZE),
                     }),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&normal_map.texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&normal_map.sampler),
+                },
             ],
         })
     }
 
-    pub(crate) fn create_render_pipeline(&self, sample_count: u32) -> wgpu::RenderPipeline {
+    /// The layout of `VertexInput`, shared by every pipeline
+    const VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: VertexInput::SIZE as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 2]>() as u64,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            // color
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 4]>() as u64,
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    };
+
+    /// The layout of the instance buffer of `ParticleEmitter`s, one `ParticleInstance` per
+    /// particle
+    const PARTICLE_INSTANCE_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: ParticleInstance::SIZE as u64,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &[
+            // center
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 3,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            // size and rotation
+            wgpu::VertexAttribute {
+                offset: 8,
+                shader_location: 4,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            // color
+            wgpu::VertexAttribute {
+                offset: 16,
+                shader_location: 5,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    };
+
+    /// The layout of `DebugVertex`
+    const DEBUG_VERTEX_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: DebugVertex::SIZE as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float32x2,
+            },
+            wgpu::VertexAttribute {
+                offset: std::mem::size_of::<[f32; 2]>() as u64,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float32x4,
+            },
+        ],
+    };
+
+    /// Creates a pipeline for every combination of `PipelineKind` and `StencilMode`
+    pub(crate) fn create_render_pipelines(&self, sample_count: u32) -> RenderPipelines {
+        let sprite_shader = self
+            .device
+            .create_shader_module(&include_wgsl!("../wgsl/shader.wgsl"));
+        let particle_shader = self
+            .device
+            .create_shader_module(&include_wgsl!("../wgsl/particle.wgsl"));
+        let debug_shader = self
+            .device
+            .create_shader_module(&include_wgsl!("../wgsl/debug.wgsl"));
+
+        let mut render_pipelines = RenderPipelines::default();
+        for &stencil_mode in StencilMode::ALL.iter() {
+            render_pipelines.insert(
+                PipelineKind::Sprite,
+                stencil_mode,
+                self.create_pipeline_with_shader(
+                    "Render Pipeline",
+                    &sprite_shader,
+                    &[Self::VERTEX_BUFFER_LAYOUT],
+                    stencil_mode,
+                    sample_count,
+                ),
+            );
+            render_pipelines.insert(
+                PipelineKind::Particle,
+                stencil_mode,
+                self.create_pipeline_with_shader(
+                    "Particle Render Pipeline",
+                    &particle_shader,
+                    &[Self::VERTEX_BUFFER_LAYOUT, Self::PARTICLE_INSTANCE_LAYOUT],
+                    stencil_mode,
+                    sample_count,
+                ),
+            );
+            render_pipelines.insert(
+                PipelineKind::Debug,
+                stencil_mode,
+                self.create_pipeline_with_shader(
+                    "Debug Render Pipeline",
+                    &debug_shader,
+                    &[Self::DEBUG_VERTEX_LAYOUT],
+                    stencil_mode,
+                    sample_count,
+                ),
+            );
+        }
+        render_pipelines
+    }
+
+    fn create_pipeline_with_shader(
+        &self,
+        label: &str,
+        shader_module: &wgpu::ShaderModule,
+        buffers: &[wgpu::VertexBufferLayout],
+        stencil_mode: StencilMode,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let stencil_face_state = stencil_mode.stencil_face_state();
         let render_pipeline_layout =
             self.device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -140,37 +304,17 @@ impl DeviceWrapper {
                     ..Default::default()
                 });
 
-        use wgpu::include_wgsl;
-        let shader_module = self
-            .device
-            .create_shader_module(&include_wgsl!("../wgsl/shader.wgsl"));
-
         self.device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
+                label: Some(label),
                 layout: Some(&render_pipeline_layout),
                 vertex: wgpu::VertexState {
-                    module: &shader_module,
+                    module: shader_module,
                     entry_point: "main",
-                    buffers: &[wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<[f32; 4]>() as u64,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: wgpu::VertexFormat::Float32x2,
-                            },
-                            wgpu::VertexAttribute {
-                                offset: std::mem::size_of::<[f32; 2]>() as u64,
-                                shader_location: 1,
-                                format: wgpu::VertexFormat::Float32x2,
-                            },
-                        ],
-                    }],
+                    buffers,
                 },
                 fragment: Some(wgpu::FragmentState {
-                    module: &shader_module,
+                    module: shader_module,
                     entry_point: "main",
                     targets: &[wgpu::ColorTargetState {
                         format: self.preferred_texture_format,
@@ -189,7 +333,12 @@ impl DeviceWrapper {
                                 operation: wgpu::BlendOperation::Add,
                             },
                         }),
-                        write_mask: wgpu::ColorWrites::ALL,
+                        // masks are only drawn to the stencil buffer
+                        write_mask: if stencil_mode == StencilMode::Write {
+                            wgpu::ColorWrites::empty()
+                        } else {
+                            wgpu::ColorWrites::ALL
+                        },
                     }],
                 }),
                 primitive: wgpu::PrimitiveState {
@@ -200,7 +349,18 @@ impl DeviceWrapper {
                     polygon_mode: wgpu::PolygonMode::Fill,
                     ..Default::default()
                 },
-                depth_stencil: None,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: RenderState::DEPTH_STENCIL_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState {
+                        front: stencil_face_state,
+                        back: stencil_face_state,
+                        read_mask: 0xff,
+                        write_mask: 0xff,
+                    },
+                    bias: wgpu::DepthBiasState::default(),
+                }),
                 multisample: wgpu::MultisampleState {
                     count: sample_count,
                     ..Default::default()
@@ -229,24 +389,85 @@ impl DeviceWrapper {
         })
     }
 
-    pub(crate) fn create_vertex_buffer(&self, contents: &[u8]) -> wgpu::Buffer {
+    /// Creates the depth-stencil attachment that stencil masks are drawn to
+    pub(crate) fn create_depth_stencil_texture(
+        &self,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Stencil Texture"),
+            sample_count,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: RenderState::DEPTH_STENCIL_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        })
+    }
+
+    /// Creates a vertex buffer, labeled with `label` if there is one
+    pub(crate) fn create_vertex_buffer(
+        &self,
+        label: Option<&str>,
+        contents: &[u8],
+    ) -> wgpu::Buffer {
         self.device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
+                label: Some(label.unwrap_or("Vertex Buffer")),
                 contents,
                 usage: wgpu::BufferUsages::VERTEX,
             })
     }
 
-    pub(crate) fn create_index_buffer(&self, contents: &[u8]) -> wgpu::Buffer {
+    /// Creates an instance buffer, labeled with `label` if there is one
+    pub(crate) fn create_instance_buffer(
+        &self,
+        label: Option<&str>,
+        contents: &[u8],
+    ) -> wgpu::Buffer {
         self.device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
+                label: Some(label.unwrap_or("Instance Buffer")),
                 contents,
-                usage: wgpu::BufferUsages::INDEX,
+                usage: wgpu::BufferUsages::VERTEX,
             })
     }
 
+    /// Creates an index buffer of `u16`s if every index fits in one, and of `u32`s otherwise.
+    /// Returns the buffer and the format it has to be bound with.
+    pub(crate) fn create_index_buffer(&self, indices: &[u32]) -> (wgpu::Buffer, wgpu::IndexFormat) {
+        let (mut contents, format) = if indices.iter().all(|&index| index <= u16::MAX as u32) {
+            let contents: Vec<u8> = indices
+                .iter()
+                .flat_map(|&index| (index as u16).to_ne_bytes())
+                .collect();
+            (contents, wgpu::IndexFormat::Uint16)
+        } else {
+            let contents: Vec<u8> = indices
+                .iter()
+                .flat_map(|index| index.to_ne_bytes())
+                .collect();
+            (contents, wgpu::IndexFormat::Uint32)
+        };
+        // buffer sizes must be a multiple of 4 bytes
+        contents.resize((contents.len() + 3) & !3, 0);
+
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: &contents,
+                usage: wgpu::BufferUsages::INDEX,
+            });
+        (buffer, format)
+    }
+
     pub(crate) fn create_command_encoder(&self) -> wgpu::CommandEncoder {
         self.device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
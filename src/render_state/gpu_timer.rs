@@ -0,0 +1,163 @@
+use crate::*;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    time::Duration,
+};
+
+type MapFuture = Pin<Box<dyn Future<Output = std::result::Result<(), wgpu::BufferAsyncError>>>>;
+
+/// Where a read buffer is in being written by the GPU and read back
+enum ReadState {
+    Free,
+    /// The timestamps of a frame are being resolved into the buffer
+    Resolved,
+    /// The buffer is being mapped so the timestamps can be read
+    Mapping(MapFuture),
+}
+
+struct ReadBuffer {
+    buffer: wgpu::Buffer,
+    state: ReadState,
+    /// The frame whose timestamps are in the buffer, counting from the first timed frame
+    frame: u64,
+}
+
+/// Measures how long the GPU spends executing a frame, by writing a timestamp before and
+/// after the frame's commands. Only available if the adapter supports
+/// `wgpu::Features::TIMESTAMP_QUERY`.
+///
+/// The timestamps are read back without waiting for the GPU, so the time of a frame is
+/// usually known a frame or two after it was submitted.
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    /// Each frame's timestamps are resolved into a free buffer, so one can be read while
+    /// the next frame is drawn
+    read_buffers: Vec<ReadBuffer>,
+    frame: u64,
+    /// Nanoseconds per timestamp tick
+    timestamp_period: f32,
+}
+
+impl GpuTimer {
+    const QUERY_COUNT: u32 = 2;
+    const READ_BUFFER_COUNT: usize = 2;
+
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Frame Timestamp Query Set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: Self::QUERY_COUNT,
+            }),
+            read_buffers: (0..Self::READ_BUFFER_COUNT)
+                .map(|_| ReadBuffer {
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Frame Timestamp Buffer"),
+                        size: Self::QUERY_COUNT as u64 * 8,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    }),
+                    state: ReadState::Free,
+                    frame: 0,
+                })
+                .collect(),
+            frame: 0,
+            timestamp_period: queue.get_timestamp_period(),
+        }
+    }
+
+    pub(crate) fn begin(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.write_timestamp(&self.query_set, 0);
+    }
+
+    /// Resolves the frame's timestamps into a free read buffer. Frames drawn while every
+    /// buffer is still being read back are not timed.
+    pub(crate) fn end(&mut self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.write_timestamp(&self.query_set, 1);
+        let read_buffer = self
+            .read_buffers
+            .iter_mut()
+            .find(|read_buffer| matches!(read_buffer.state, ReadState::Free));
+        if let Some(read_buffer) = read_buffer {
+            command_encoder.resolve_query_set(
+                &self.query_set,
+                0..Self::QUERY_COUNT,
+                &read_buffer.buffer,
+                0,
+            );
+            read_buffer.state = ReadState::Resolved;
+            read_buffer.frame = self.frame;
+            self.frame += 1;
+        }
+    }
+
+    /// Starts reading back the timestamps of frames that have been submitted, and returns
+    /// how long the GPU spent on the latest frame that has finished since this was last
+    /// called. Never waits for the GPU.
+    pub(crate) fn read(&mut self, device: &wgpu::Device) -> Option<Duration> {
+        let free = |read_buffer: &ReadBuffer| matches!(read_buffer.state, ReadState::Free);
+        if self.read_buffers.iter().all(free) {
+            return None;
+        }
+        for read_buffer in self.read_buffers.iter_mut() {
+            if let ReadState::Resolved = read_buffer.state {
+                let mapping = read_buffer.buffer.slice(..).map_async(wgpu::MapMode::Read);
+                read_buffer.state = ReadState::Mapping(Box::pin(mapping));
+            }
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        let timestamp_period = self.timestamp_period;
+        let mut latest: Option<(u64, Duration)> = None;
+        for read_buffer in self.read_buffers.iter_mut() {
+            let mapped = match &mut read_buffer.state {
+                ReadState::Mapping(mapping) => match mapping.as_mut().poll(&mut context) {
+                    Poll::Ready(result) => result.is_ok(),
+                    Poll::Pending => continue,
+                },
+                _ => continue,
+            };
+            read_buffer.state = ReadState::Free;
+            if !mapped {
+                continue;
+            }
+            let elapsed = Self::elapsed(&read_buffer.buffer, timestamp_period);
+            read_buffer.buffer.unmap();
+            if latest.map(|(frame, _)| frame) < Some(read_buffer.frame) {
+                latest = Some((read_buffer.frame, elapsed));
+            }
+        }
+        latest.map(|(_, elapsed)| elapsed)
+    }
+
+    /// Returns the time between the two timestamps in a mapped read buffer
+    fn elapsed(buffer: &wgpu::Buffer, timestamp_period: f32) -> Duration {
+        let elapsed_ticks = {
+            let contents = buffer.slice(..).get_mapped_range();
+            let mut timestamps = [0u64; 2];
+            for (timestamp, bytes) in timestamps.iter_mut().zip(contents.chunks_exact(8)) {
+                let mut buffer = [0u8; 8];
+                buffer.copy_from_slice(bytes);
+                *timestamp = u64::from_ne_bytes(buffer);
+            }
+            timestamps[1].saturating_sub(timestamps[0])
+        };
+        Duration::from_nanos((elapsed_ticks as f64 * timestamp_period as f64) as u64)
+    }
+}
+
+/// Returns a waker that does nothing, for checking whether a mapping has finished without
+/// waiting for it
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    // The functions in the vtable ignore the data pointer, so null is valid
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
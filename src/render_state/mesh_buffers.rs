@@ -0,0 +1,152 @@
+use crate::*;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Vertex and index buffers that are kept between frames and written in place, for meshes
+/// that change a little at a time
+#[derive(Debug)]
+pub(crate) struct MeshBuffers {
+    vertex_buffer: Rc<wgpu::Buffer>,
+    /// The number of vertices that fit in `vertex_buffer`
+    vertex_capacity: usize,
+    index_buffer: Rc<wgpu::Buffer>,
+    /// The number of indices that fit in `index_buffer`
+    index_capacity: usize,
+    index_format: wgpu::IndexFormat,
+    index_count: u32,
+}
+
+impl MeshBuffers {
+    /// Creates buffers holding `mesh`, with room for it to grow
+    pub(crate) fn new(device_wrapper: &DeviceWrapper, mesh: &SpriteMesh) -> Self {
+        let index_format = Self::index_format(mesh);
+        // there is room for twice as many vertices, and an even number of `u16` indices
+        let vertex_capacity = (mesh.vertices.len() * 2).max(64);
+        let index_capacity = (mesh.indices.len() * 2).max(64);
+        let index_size = match index_format {
+            wgpu::IndexFormat::Uint16 => 2,
+            wgpu::IndexFormat::Uint32 => 4,
+        };
+        let create_buffer = |label, size, usage| {
+            Rc::new(
+                device_wrapper
+                    .device
+                    .create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(label),
+                        size,
+                        usage: usage | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }),
+            )
+        };
+
+        Self {
+            vertex_buffer: create_buffer(
+                "Vertex Buffer",
+                (vertex_capacity * VertexInput::SIZE) as u64,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            vertex_capacity,
+            index_buffer: create_buffer(
+                "Index Buffer",
+                (index_capacity * index_size) as u64,
+                wgpu::BufferUsages::INDEX,
+            ),
+            index_capacity,
+            index_format,
+            index_count: 0,
+        }
+    }
+
+    /// Returns the smallest index format that can address every vertex of `mesh`
+    fn index_format(mesh: &SpriteMesh) -> wgpu::IndexFormat {
+        if mesh.vertices.len() <= u16::MAX as usize + 1 {
+            wgpu::IndexFormat::Uint16
+        } else {
+            wgpu::IndexFormat::Uint32
+        }
+    }
+
+    /// Writes the `vertices` and `indices` ranges of `mesh` to the buffers. If the mesh no
+    /// longer fits, or needs a larger index format, the buffers are recreated and all of it is
+    /// written.
+    pub(crate) fn write(
+        &mut self,
+        render_state: &RenderState,
+        mesh: &SpriteMesh,
+        vertices: Range<usize>,
+        indices: Range<usize>,
+    ) {
+        if mesh.vertices.len() > self.vertex_capacity
+            || mesh.indices.len() > self.index_capacity
+            || Self::index_format(mesh) != self.index_format
+        {
+            *self = Self::new(&render_state.device_wrapper, mesh);
+            self.write(
+                render_state,
+                mesh,
+                0..mesh.vertices.len(),
+                0..mesh.indices.len(),
+            );
+            return;
+        }
+        self.index_count = mesh.indices.len() as u32;
+
+        if !vertices.is_empty() {
+            let contents = mesh.vertex_buffer_contents();
+            render_state.queue.write_buffer(
+                &self.vertex_buffer,
+                (vertices.start * VertexInput::SIZE) as u64,
+                &contents[vertices.start * VertexInput::SIZE..vertices.end * VertexInput::SIZE],
+            );
+        }
+        if !indices.is_empty() {
+            let (offset, contents) = match self.index_format {
+                wgpu::IndexFormat::Uint16 => {
+                    // writes must start and end on a multiple of 4 bytes, so whole pairs of
+                    // indices are written
+                    let start = indices.start & !1;
+                    let end = (indices.end + 1) & !1;
+                    let contents: Vec<u8> = (start..end)
+                        .flat_map(|i| (*mesh.indices.get(i).unwrap_or(&0) as u16).to_ne_bytes())
+                        .collect();
+                    (start * 2, contents)
+                }
+                wgpu::IndexFormat::Uint32 => {
+                    let contents: Vec<u8> = mesh.indices[indices.clone()]
+                        .iter()
+                        .flat_map(|index| index.to_ne_bytes())
+                        .collect();
+                    (indices.start * 4, contents)
+                }
+            };
+            render_state
+                .queue
+                .write_buffer(&self.index_buffer, offset as u64, &contents);
+        }
+    }
+
+    /// Creates a render bundle that draws the contents of the buffers like `sprite`
+    pub(crate) fn render_bundle(&self, oge: &Oge, sprite: &Sprite) -> RenderBundle {
+        RenderBundle {
+            bind_group: Sprite::create_bind_group(
+                oge,
+                &sprite.mesh.affine2,
+                &sprite.default_texture,
+                sprite.normal_map.as_ref(),
+                sprite.opacity,
+            ),
+            vertex_buffer: self.vertex_buffer.clone(),
+            index_buffer: self.index_buffer.clone(),
+            index_format: self.index_format,
+            index_count: self.index_count,
+            pipeline: PipelineKind::Sprite,
+            instance_buffer: None,
+            instance_count: 1,
+            stencil_mode: StencilMode::None,
+            stencil_reference: 0,
+            scissor_rect: None,
+            z_index: sprite.z_index,
+        }
+    }
+}
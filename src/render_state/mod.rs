@@ -3,4 +3,7 @@ usemod![
     pub(crate) render_state_struct, 
     pub(crate) device_wrapper,
     pub(crate) render_bundle,
+    pub(crate) mesh_buffers,
+    pub(crate) gpu_timer,
+    pub(crate) render_pipelines,
 ];
\ No newline at end of file
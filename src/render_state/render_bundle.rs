@@ -1,26 +1,58 @@
 use rand::Rng;
 
 use crate::*;
+use std::rc::Rc;
+
+/// The render pipeline that a `RenderBundle` is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PipelineKind {
+    /// Textured `SpriteMesh`es
+    Sprite,
+    /// Instances of a `SpriteMesh`, described by an instance buffer
+    Particle,
+    /// Untextured triangles with a color per vertex, used by `DebugDraw`
+    Debug,
+}
 
 #[derive(Debug)]
 /// An object that can be rendered
 pub struct RenderBundle {
     pub(crate) bind_group: wgpu::BindGroup,
-    pub(crate) vertex_buffer: wgpu::Buffer,
-    pub(crate) index_buffer: wgpu::Buffer,
+    /// Shared with drawables that keep their buffers between frames
+    pub(crate) vertex_buffer: Rc<wgpu::Buffer>,
+    pub(crate) index_buffer: Rc<wgpu::Buffer>,
+    pub(crate) index_format: wgpu::IndexFormat,
     pub(crate) index_count: u32,
+    pub(crate) pipeline: PipelineKind,
+    /// Per-instance data for bundles that draw their mesh many times, such as particles
+    pub(crate) instance_buffer: Option<wgpu::Buffer>,
+    pub(crate) instance_count: u32,
+    pub(crate) stencil_mode: StencilMode,
+    /// The id of the `StencilMask` that is written or tested
+    pub(crate) stencil_reference: u32,
+    /// Only draw inside this rectangle of the frame
+    pub(crate) scissor_rect: Option<ScissorRect>,
     pub(crate) z_index: ZIndex,
 }
 
+/// A rectangle of the frame in physical pixels, measured from the top left
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScissorRect {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
 impl PartialOrd for RenderBundle {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.z_index.partial_cmp(&other.z_index)
+        Some(self.cmp(other))
     }
 }
 
 impl PartialEq for RenderBundle {
     fn eq(&self, other: &Self) -> bool {
-        other.z_index.eq(&other.z_index)
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
 
@@ -29,8 +61,12 @@ impl Eq for RenderBundle {
 }
 
 impl Ord for RenderBundle {
+    /// Orders by `ZIndex`. Stencil masks come before other bundles with the same `ZIndex`,
+    /// so that those bundles can be clipped to them.
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let is_mask = |render_bundle: &Self| render_bundle.stencil_mode == StencilMode::Write;
         <ZIndex as Ord>::cmp(&self.z_index, &other.z_index)
+            .then_with(|| is_mask(other).cmp(&is_mask(self)))
     }
 }
 
@@ -7,10 +7,22 @@ pub(crate) struct RenderPassResources {
     pub(crate) _surface_texture: wgpu::SurfaceTexture,
     pub(crate) render_bundles: Vec<RenderBundle>,
     pub(crate) multisampled_frame_buffer_view: wgpu::TextureView,
+    pub(crate) depth_stencil_view: wgpu::TextureView,
+    /// Whether a GPU timestamp was written at the start of this frame
+    pub(crate) gpu_timed: bool,
 }
 
 impl RenderPassResources {
-    pub(crate) fn finish(self, render_state: &RenderState) {
+    /// Submits the frame. If the GPU's time is being measured, it can be read with
+    /// `RenderState::read_gpu_time` once it has finished.
+    pub(crate) fn finish(mut self, render_state: &mut RenderState) {
+        let gpu_timer = render_state
+            .gpu_timer
+            .as_mut()
+            .filter(|_| self.gpu_timed);
+        if let Some(gpu_timer) = gpu_timer {
+            gpu_timer.end(&mut self.command_encoder);
+        }
         render_state
             .queue
             .submit(std::iter::once(self.command_encoder.finish()));
@@ -25,20 +37,49 @@ pub struct RenderPass<'a> {
 }
 
 impl<'a> RenderPass<'a> {
-    pub(crate) fn draw_render_bundles(mut self, render_state: &'a RenderState) {
+    /// Sorts the render bundles by their `ZIndex`, so they are drawn in order
+    pub(crate) fn sort_render_bundles(&mut self) {
         self.render_bundles.sort_unstable();
+    }
+
+    /// Records the commands for drawing the render bundles. They should be sorted first.
+    pub(crate) fn draw_render_bundles(mut self, render_state: &'a RenderState) {
         for render_bundle in self.render_bundles.iter() {
-            self.render_pass.set_pipeline(&render_state.render_pipeline);
+            self.render_pass.set_pipeline(
+                render_state
+                    .render_pipelines
+                    .get(render_bundle.pipeline, render_bundle.stencil_mode),
+            );
+            self.render_pass
+                .set_stencil_reference(render_bundle.stencil_reference);
+            match render_bundle.scissor_rect {
+                Some(ScissorRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                }) => self.render_pass.set_scissor_rect(x, y, width, height),
+                None => self.render_pass.set_scissor_rect(
+                    0,
+                    0,
+                    render_state.surface_configuration.width,
+                    render_state.surface_configuration.height,
+                ),
+            }
+            if let Some(instance_buffer) = &render_bundle.instance_buffer {
+                self.render_pass
+                    .set_vertex_buffer(1, instance_buffer.slice(..));
+            }
             self.render_pass
                 .set_bind_group(0, &render_bundle.bind_group, &[]);
             self.render_pass
                 .set_vertex_buffer(0, render_bundle.vertex_buffer.slice(..));
             self.render_pass.set_index_buffer(
                 render_bundle.index_buffer.slice(..),
-                wgpu::IndexFormat::Uint16,
+                render_bundle.index_format,
             );
             self.render_pass
-                .draw_indexed(0..render_bundle.index_count, 0, 0..1);
+                .draw_indexed(0..render_bundle.index_count, 0, 0..render_bundle.instance_count);
         }
     }
 }
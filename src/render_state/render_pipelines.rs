@@ -0,0 +1,67 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// How a render bundle interacts with the stencil buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum StencilMode {
+    /// Ignore the stencil buffer
+    None,
+    /// Write the stencil reference wherever the bundle covers, without drawing any color
+    Write,
+    /// Only draw where the stencil buffer equals the stencil reference
+    Inside,
+    /// Only draw where the stencil buffer does not equal the stencil reference
+    Outside,
+}
+
+impl StencilMode {
+    pub(crate) const ALL: [StencilMode; 4] = [
+        StencilMode::None,
+        StencilMode::Write,
+        StencilMode::Inside,
+        StencilMode::Outside,
+    ];
+
+    pub(crate) fn stencil_face_state(&self) -> wgpu::StencilFaceState {
+        let (compare, pass_op) = match self {
+            StencilMode::None => (wgpu::CompareFunction::Always, wgpu::StencilOperation::Keep),
+            StencilMode::Write => (
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Replace,
+            ),
+            StencilMode::Inside => (wgpu::CompareFunction::Equal, wgpu::StencilOperation::Keep),
+            StencilMode::Outside => (
+                wgpu::CompareFunction::NotEqual,
+                wgpu::StencilOperation::Keep,
+            ),
+        };
+        wgpu::StencilFaceState {
+            compare,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        }
+    }
+}
+
+/// Every render pipeline, keyed by the kind of bundle they draw and how they use the stencil
+/// buffer
+#[derive(Default)]
+pub(crate) struct RenderPipelines {
+    pipelines: HashMap<(PipelineKind, StencilMode), wgpu::RenderPipeline>,
+}
+
+impl RenderPipelines {
+    pub(crate) fn insert(
+        &mut self,
+        kind: PipelineKind,
+        stencil_mode: StencilMode,
+        pipeline: wgpu::RenderPipeline,
+    ) {
+        self.pipelines.insert((kind, stencil_mode), pipeline);
+    }
+
+    pub(crate) fn get(&self, kind: PipelineKind, stencil_mode: StencilMode) -> &wgpu::RenderPipeline {
+        &self.pipelines[&(kind, stencil_mode)]
+    }
+}
@@ -5,21 +5,29 @@ pub(crate) struct RenderState {
     pub(crate) surface_configuration: wgpu::SurfaceConfiguration,
     pub(crate) device_wrapper: crate::DeviceWrapper,
     pub(crate) queue: wgpu::Queue,
-    pub(crate) render_pipeline: wgpu::RenderPipeline,
+    pub(crate) render_pipelines: RenderPipelines,
     pub(crate) multisampled_frame_buffer: wgpu::Texture,
+    pub(crate) depth_stencil_texture: wgpu::Texture,
     pub(crate) sample_count: u32,
+    /// `None` if the adapter does not support timestamp queries
+    pub(crate) gpu_timer: Option<GpuTimer>,
 }
 
 impl RenderState {
     pub(crate) const UNIFORM_BUFFER_SIZE: std::num::NonZeroU64 = unsafe {
         std::num::NonZeroU64::new_unchecked(
-            3 * (8 + 8) + // 3 x vector2 with 8 padding [[size(48)]]
-            1 * (4 + 12), // 1 x f32 with 12 padding
+            2 * 3 * (8 + 8) + // 2 x (3 x vector2 with 8 padding [[size(48)]])
+            4 * 4 + // opacity, light count, normal mapped flag and 4 padding
+            4 * 4 + // ambient light
+            PointLight::MAX_COUNT as u64 * PointLight::UNIFORM_SIZE as u64, // lights
         )
     };
 
     pub(crate) const INITIAL_SAMPLE_COUNT: u32 = 4;
 
+    pub(crate) const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat =
+        wgpu::TextureFormat::Depth24PlusStencil8;
+
     pub fn new(window: &winit::window::Window) -> Self {
         pollster::block_on(Self::new_async(window))
     }
@@ -27,14 +35,27 @@ impl RenderState {
     async fn new_async(window: &winit::window::Window) -> Self {
         let (device_wrapper, surface, surface_configuration, queue) =
             DeviceWrapper::new(window).await;
-        let render_pipeline = device_wrapper.create_render_pipeline(Self::INITIAL_SAMPLE_COUNT);
-        let multisampled_frame_buffer = {
-            let inner_size = window.inner_size();
-            device_wrapper.create_multisampled_frame_buffer(
-                inner_size.width,
-                inner_size.height,
-                Self::INITIAL_SAMPLE_COUNT,
-            )
+        let render_pipelines = device_wrapper.create_render_pipelines(Self::INITIAL_SAMPLE_COUNT);
+        let inner_size = window.inner_size();
+        let multisampled_frame_buffer = device_wrapper.create_multisampled_frame_buffer(
+            inner_size.width,
+            inner_size.height,
+            Self::INITIAL_SAMPLE_COUNT,
+        );
+        let depth_stencil_texture = device_wrapper.create_depth_stencil_texture(
+            inner_size.width,
+            inner_size.height,
+            Self::INITIAL_SAMPLE_COUNT,
+        );
+
+        let gpu_timer = if device_wrapper
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            Some(GpuTimer::new(&device_wrapper.device, &queue))
+        } else {
+            None
         };
 
         Self {
@@ -42,9 +63,11 @@ impl RenderState {
             surface_configuration,
             device_wrapper,
             queue,
-            render_pipeline,
+            render_pipelines,
             multisampled_frame_buffer,
+            depth_stencil_texture,
             sample_count: Self::INITIAL_SAMPLE_COUNT,
+            gpu_timer,
         }
     }
 
@@ -52,6 +75,7 @@ impl RenderState {
         &self,
         label: Option<&str>,
         dynamic_image: image::DynamicImage,
+        format: wgpu::TextureFormat,
     ) -> Result<wgpu::Texture> {
         use image::GenericImageView;
 
@@ -64,7 +88,7 @@ impl RenderState {
             panic!("Image dimensions must be at least (1, 1)");
         }
 
-        self.create_image_texture_from_buffer(label, image_buffer, dimensions)
+        self.create_image_texture_from_buffer(label, image_buffer, dimensions, format)
     }
 
     pub(crate) fn create_image_texture_from_buffer(
@@ -72,6 +96,7 @@ impl RenderState {
         label: Option<&str>,
         image_buffer: &[u8],
         dimensions: (u32, u32),
+        format: wgpu::TextureFormat,
     ) -> Result<wgpu::Texture> {
         let texture_extent_3d = wgpu::Extent3d {
             width: dimensions.0,
@@ -88,7 +113,7 @@ impl RenderState {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                format,
                 usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             });
 
@@ -120,14 +145,29 @@ impl RenderState {
     pub(crate) fn recreate_surface(&mut self) {
         self.surface
             .configure(&self.device_wrapper.device, &self.surface_configuration);
+        self.recreate_frame_buffers();
+    }
+
+    /// Recreates the attachments that must match the size of the surface and the sample count
+    fn recreate_frame_buffers(&mut self) {
         self.multisampled_frame_buffer = self.device_wrapper.create_multisampled_frame_buffer(
             self.surface_configuration.width,
             self.surface_configuration.height,
             self.sample_count,
         );
+        self.depth_stencil_texture = self.device_wrapper.create_depth_stencil_texture(
+            self.surface_configuration.width,
+            self.surface_configuration.height,
+            self.sample_count,
+        );
     }
 
-    pub(crate) fn create_render_pass_resources(&self) -> Result<RenderPassResources> {
+    /// Creates the resources for drawing a frame. If `gpu_timing` is set and timestamp
+    /// queries are supported, the time the GPU spends on the frame is measured.
+    pub(crate) fn create_render_pass_resources(
+        &self,
+        gpu_timing: bool,
+    ) -> Result<RenderPassResources> {
         let surface_texture = self
             .surface
             .get_current_frame()
@@ -137,21 +177,42 @@ impl RenderState {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        let mut command_encoder = self.device_wrapper.create_command_encoder();
+        let gpu_timer = self.gpu_timer.as_ref().filter(|_| gpu_timing);
+        if let Some(gpu_timer) = gpu_timer {
+            gpu_timer.begin(&mut command_encoder);
+        }
+
         Ok(RenderPassResources {
-            command_encoder: self.device_wrapper.create_command_encoder(),
+            command_encoder,
+            gpu_timed: gpu_timer.is_some(),
             _surface_texture: surface_texture,
             surface_texture_view,
             render_bundles: vec![],
             multisampled_frame_buffer_view: self
                 .multisampled_frame_buffer
                 .create_view(&wgpu::TextureViewDescriptor::default()),
+            depth_stencil_view: self
+                .depth_stencil_texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
         })
     }
 
+    /// Returns how long the GPU spent on the latest timed frame that has finished since this
+    /// was last called, without waiting for the GPU. This is usually a frame or two behind
+    /// the frame that was just submitted.
+    pub(crate) fn read_gpu_time(&mut self) -> Option<std::time::Duration> {
+        let device = &self.device_wrapper.device;
+        self.gpu_timer
+            .as_mut()
+            .and_then(|gpu_timer| gpu_timer.read(device))
+    }
+
     pub(crate) fn configure_render_pipeline(&mut self, config: RenderPipelineConfiguration) {
         self.sample_count = config.anti_aliasing as u32;
-        self.render_pipeline = self
+        self.render_pipelines = self
             .device_wrapper
-            .create_render_pipeline(self.sample_count);
+            .create_render_pipelines(self.sample_count);
+        self.recreate_frame_buffers();
     }
 }
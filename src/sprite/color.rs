@@ -45,4 +45,19 @@ impl Color {
             Color::as_u8(self.a),
         ]
     }
+
+    /// Returns the components of this color as `[r, g, b, a]`
+    pub fn as_f32_array(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// Linearly interpolates each component between `self` and `other`
+    pub(crate) fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
 }
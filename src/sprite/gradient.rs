@@ -0,0 +1,89 @@
+use crate::*;
+
+/// Colors that blend into each other, used by `TextureSource::LinearGradient` and
+/// `TextureSource::RadialGradient`.
+///
+/// The gradient is made of `(offset, color)` stops, where `offset` is `0.` at the start of the
+/// gradient and `1.` at its end. Colors between two stops are linearly interpolated, and colors
+/// before the first or after the last stop are held.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub(crate) stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// The width and height of the textures that gradients are drawn to. Gradient textures are
+    /// square so that they are projected like any other square image.
+    const RESOLUTION: u32 = 256;
+
+    /// Creates a new gradient from the given stops. Panics if `stops` is empty.
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        if stops.is_empty() {
+            panic!("Gradient must have at least 1 stop");
+        }
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { stops }
+    }
+
+    /// A gradient that goes from `start` to `end`
+    pub fn two_color(start: Color, end: Color) -> Self {
+        Self {
+            stops: vec![(0., start), (1., end)],
+        }
+    }
+
+    /// Returns the color of the gradient at `offset`
+    pub fn sample(&self, offset: f32) -> Color {
+        let first = self.stops[0];
+        if offset <= first.0 {
+            return first.1;
+        }
+        for stops in self.stops.windows(2) {
+            let (start, end) = (stops[0], stops[1]);
+            if offset < end.0 {
+                let t = (offset - start.0) / (end.0 - start.0);
+                return start.1.lerp(&end.1, t);
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+
+    /// Returns the RGBA8 image buffer and dimensions of a linear gradient running in
+    /// `direction`. Offset `0.` and `1.` touch opposite corners or edges of the texture.
+    pub(crate) fn linear_image_buffer(&self, direction: f32) -> (Vec<u8>, (u32, u32)) {
+        let direction = Vector2::new_euclidean(direction, 1.);
+        // the length of the gradient line across a unit square, so that its ends meet the
+        // corners furthest along `direction`
+        let length = direction.x.abs() + direction.y.abs();
+        self.image_buffer(|point| 0.5 + point.dot(&direction) / length)
+    }
+
+    /// Returns the RGBA8 image buffer and dimensions of a radial gradient. See
+    /// `TextureSource::RadialGradient` for the meaning of `center` and `radius`.
+    pub(crate) fn radial_image_buffer(
+        &self,
+        center: Vector2,
+        radius: f32,
+    ) -> (Vec<u8>, (u32, u32)) {
+        let center = center.sub(&Vector2::new(0.5, 0.5));
+        self.image_buffer(|point| point.distance_to(&center) / radius)
+    }
+
+    /// Fills a square image by sampling the gradient at the offset returned by `offset`, which
+    /// is given the position of each texel's center relative to the center of the image, from
+    /// `-0.5` to `0.5` with the y axis pointing up
+    fn image_buffer(&self, offset: impl Fn(Vector2) -> f32) -> (Vec<u8>, (u32, u32)) {
+        let resolution = Self::RESOLUTION;
+        let mut image_buffer = Vec::with_capacity((resolution * resolution * 4) as usize);
+        for row in 0..resolution {
+            for column in 0..resolution {
+                let point = Vector2::new(
+                    (column as f32 + 0.5) / resolution as f32 - 0.5,
+                    0.5 - (row as f32 + 0.5) / resolution as f32,
+                );
+                image_buffer.extend_from_slice(&self.sample(offset(point)).as_rgba8());
+            }
+        }
+        (image_buffer, (resolution, resolution))
+    }
+}
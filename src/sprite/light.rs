@@ -0,0 +1,58 @@
+use crate::*;
+
+/// A light that shades normal-mapped sprites. Lights only last for the frame they are
+/// added in, so add them every `update` with `Oge::add_light(&mut self, light: PointLight)`
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    /// The position of this light, in the same coordinate system as the window bounds
+    pub position: Vector2,
+    /// How far above the sprites this light sits. Lower values give grazing, more
+    /// pronounced shading
+    pub height: f32,
+    /// The distance at which this light no longer has any effect
+    pub radius: f32,
+    /// The color of this light. The alpha component is ignored
+    pub color: Color,
+    /// Multiplier applied to `color`
+    pub intensity: f32,
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self {
+            position: Vector2::ZERO,
+            height: 50.,
+            radius: 500.,
+            color: Color::WHITE,
+            intensity: 1.,
+        }
+    }
+}
+
+impl PointLight {
+    /// The maximum number of lights that can affect a sprite in one frame. Any lights
+    /// added after this are ignored.
+    pub const MAX_COUNT: usize = 8;
+
+    /// Size in bytes of a light, as laid out in the uniform buffer
+    pub(crate) const UNIFORM_SIZE: usize = 32;
+
+    /// Writes this light into `dst` in the layout expected by the `Light` struct in
+    /// `shader.wgsl`
+    pub(crate) fn write_uniform(&self, dst: &mut [u8]) {
+        let [r, g, b, _] = self.color.as_f32_array();
+        let contents = [
+            self.position.x,
+            self.position.y,
+            self.height,
+            self.radius,
+            r * self.intensity,
+            g * self.intensity,
+            b * self.intensity,
+            1.,
+        ];
+        for (i, float) in contents.iter().enumerate() {
+            dst[i * 4..i * 4 + 4].copy_from_slice(&float.to_ne_bytes());
+        }
+    }
+}
@@ -1,7 +1,11 @@
 usemod![
     pub texture, 
     pub sprite_mesh,
+    pub primitives,
+    pub(crate) stroke,
     pub sprite_struct,
     pub color,
+    pub gradient,
+    pub light,
     pub sub_structs,
 ];
\ No newline at end of file
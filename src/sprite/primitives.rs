@@ -0,0 +1,398 @@
+use crate::*;
+use std::f32::consts::TAU;
+
+/// Controls how many segments the curved edges of primitive meshes are split into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeshDetail {
+    /// Curved edges are split into segments that are at most this far from the true curve,
+    /// in world units. Larger shapes get more segments.
+    Tolerance(f32),
+    /// A full circle is split into this many segments, and partial circles into a
+    /// proportional number
+    Segments(u16),
+}
+
+impl MeshDetail {
+    /// The largest distance between a curve and its segments, in pixels, used by `on_screen`
+    const SCREEN_TOLERANCE: f32 = 0.25;
+
+    /// Curved edges stay within a quarter of a pixel of the true curve at the current window
+    /// size and bounds. Shapes that are scaled up after being created may need more detail.
+    pub fn on_screen(oge: &Oge) -> Self {
+        let bounds = oge.window_bounds();
+        let dimensions = oge.window_dimensions();
+        let units_per_pixel = (bounds.width() / dimensions.width.max(1) as f32)
+            .abs()
+            .max((bounds.height() / dimensions.height.max(1) as f32).abs());
+        MeshDetail::Tolerance(units_per_pixel * Self::SCREEN_TOLERANCE)
+    }
+
+    /// Returns the number of segments needed for an arc with the given radius that turns
+    /// through `sweep` radians
+    pub(crate) fn segments(&self, radius: f32, sweep: f32) -> u16 {
+        let turns = sweep.abs() / TAU;
+        let segments = match *self {
+            MeshDetail::Tolerance(tolerance) if tolerance > 0. && tolerance < radius => {
+                // the sagitta of each segment is within the tolerance
+                let segment_angle = 2. * (1. - tolerance / radius).acos();
+                (sweep.abs() / segment_angle).ceil()
+            }
+            MeshDetail::Tolerance(_) => 0.,
+            MeshDetail::Segments(segments) => (segments as f32 * turns).ceil(),
+        };
+        // a full circle needs at least 3 segments to have any area
+        segments.max((3. * turns).ceil()).clamp(1., 4096.) as u16
+    }
+}
+
+/// Pushes a triangle, reordering its indices so that it is counter-clockwise and is not
+/// culled
+pub(crate) fn push_triangle(
+    vertices: &[VertexInput],
+    indices: &mut Vec<u32>,
+    mut triangle: [u32; 3],
+) {
+    let [a, b, c] = triangle.map(|index| vertices[index as usize].position);
+    if (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y) < 0. {
+        triangle.swap(1, 2);
+    }
+    indices.extend(triangle);
+}
+
+/// Returns `segments + 1` points along an arc centered on the origin, starting at `start` and
+/// turning through `sweep`. Angles are in radians, with `0` pointing up and positive angles
+/// turning clockwise.
+fn arc_points(radius: f32, start: f32, sweep: f32, segments: u16) -> impl Iterator<Item = Vector2> {
+    (0..=segments)
+        .map(move |i| Vector2::new_euclidean(start + sweep * i as f32 / segments as f32, radius))
+}
+
+// Primitive meshes are centered on the origin. Their texture coordinates are set by the
+// `TextureProjectionMethod` of the sprite they are used in.
+impl SpriteMesh {
+    /// Creates a mesh with a vertex at the origin, joined to each point of `outline`. The
+    /// outline must be star-shaped around the origin. It is closed if `closed` is `true`.
+    fn new_center_fan(outline: impl Iterator<Item = Vector2>, closed: bool) -> Self {
+        let mut vertices = vec![VertexInput::new(Vector2::ZERO)];
+        vertices.extend(outline.map(VertexInput::new));
+        let outline_length = vertices.len() as u32 - 1;
+
+        let mut indices = Vec::with_capacity(outline_length as usize * 3);
+        let edges = if closed {
+            outline_length
+        } else {
+            outline_length - 1
+        };
+        for i in 0..edges {
+            let next = (i + 1) % outline_length;
+            push_triangle(&vertices, &mut indices, [0, i + 1, next + 1]);
+        }
+
+        Self {
+            vertices,
+            indices,
+            affine2: Affine2::default(),
+        }
+    }
+
+    /// Creates a band between two arcs with the same angles, as a strip of quads
+    fn new_band(
+        outer: impl Iterator<Item = Vector2>,
+        inner: impl Iterator<Item = Vector2>,
+        closed: bool,
+    ) -> Self {
+        let mut vertices = vec![];
+        for (outer, inner) in outer.zip(inner) {
+            vertices.extend([VertexInput::new(outer), VertexInput::new(inner)]);
+        }
+        let pairs = vertices.len() as u32 / 2;
+
+        let mut indices = Vec::with_capacity(pairs as usize * 6);
+        let quads = if closed { pairs } else { pairs - 1 };
+        for i in 0..quads {
+            let (a, b) = (i * 2, (i + 1) % pairs * 2);
+            push_triangle(&vertices, &mut indices, [a, a + 1, b]);
+            push_triangle(&vertices, &mut indices, [b, a + 1, b + 1]);
+        }
+
+        Self {
+            vertices,
+            indices,
+            affine2: Affine2::default(),
+        }
+    }
+
+    /// Creates a rectangle with rounded corners. `radius` is limited to half the shorter side
+    pub fn new_rounded_rectangle(width: f32, height: f32, radius: f32, detail: MeshDetail) -> Self {
+        let radius = radius.max(0.).min(width.min(height) / 2.);
+        if radius == 0. {
+            return Self::new_rectangle(width, height);
+        }
+        let segments = detail.segments(radius, TAU / 4.);
+        let (x, y) = (width / 2. - radius, height / 2. - radius);
+        // the corners, clockwise from the top right, and the angle each one starts at
+        let corners = [
+            (Vector2::new(x, y), 0.),
+            (Vector2::new(x, -y), TAU / 4.),
+            (Vector2::new(-x, -y), TAU / 2.),
+            (Vector2::new(-x, y), TAU * 3. / 4.),
+        ];
+        let outline = corners.iter().flat_map(move |&(center, start)| {
+            arc_points(radius, start, TAU / 4., segments).map(move |point| point.add(&center))
+        });
+        Self::new_center_fan(outline, true)
+    }
+
+    /// Creates a rectangle with semicircles at its short ends, `width` by `height` in total
+    pub fn new_capsule(width: f32, height: f32, detail: MeshDetail) -> Self {
+        Self::new_rounded_rectangle(width, height, width.min(height) / 2., detail)
+    }
+
+    /// Creates a ring between `inner_radius` and `outer_radius`
+    pub fn new_ring(outer_radius: f32, inner_radius: f32, detail: MeshDetail) -> Self {
+        let segments = detail.segments(outer_radius, TAU);
+        Self::new_band(
+            arc_points(outer_radius, 0., TAU, segments).take(segments as usize),
+            arc_points(inner_radius, 0., TAU, segments).take(segments as usize),
+            true,
+        )
+    }
+
+    /// Creates a section of a ring, starting at `start` and turning through `sweep`. Angles
+    /// are in radians, with `0` pointing up and positive angles turning clockwise.
+    pub fn new_arc(
+        outer_radius: f32,
+        inner_radius: f32,
+        start: f32,
+        sweep: f32,
+        detail: MeshDetail,
+    ) -> Self {
+        let segments = detail.segments(outer_radius, sweep);
+        Self::new_band(
+            arc_points(outer_radius, start, sweep, segments),
+            arc_points(inner_radius, start, sweep, segments),
+            false,
+        )
+    }
+
+    /// Creates a slice of a circle, starting at `start` and turning through `sweep`. Angles
+    /// are in radians, with `0` pointing up and positive angles turning clockwise.
+    pub fn new_pie(radius: f32, start: f32, sweep: f32, detail: MeshDetail) -> Self {
+        let segments = detail.segments(radius, sweep);
+        let full_circle = sweep.abs() >= TAU;
+        let outline = arc_points(radius, start, sweep, segments);
+        if full_circle {
+            Self::new_center_fan(outline.take(segments as usize), true)
+        } else {
+            Self::new_center_fan(outline, false)
+        }
+    }
+
+    /// Creates a regular polygon with `sides` sides and a vertex `radius` above the origin.
+    /// Panics if `sides` is less than 3
+    pub fn new_regular_polygon(radius: f32, sides: u16) -> Self {
+        if sides < 3 {
+            panic!("Cannot create a regular polygon with fewer than 3 sides");
+        }
+        Self::new_center_fan(
+            arc_points(radius, 0., TAU, sides).take(sides as usize),
+            true,
+        )
+    }
+
+    /// Creates a star with `points` points at `outer_radius`, the first of which is directly
+    /// above the origin, and inner corners at `inner_radius`. Panics if `points` is less
+    /// than 2
+    pub fn new_star(outer_radius: f32, inner_radius: f32, points: u16) -> Self {
+        if points < 2 {
+            panic!("Cannot create a star with fewer than 2 points");
+        }
+        let corners = points * 2;
+        let outline = arc_points(1., 0., TAU, corners)
+            .take(corners as usize)
+            .enumerate()
+            .map(|(i, direction)| {
+                let radius = if i % 2 == 0 {
+                    outer_radius
+                } else {
+                    inner_radius
+                };
+                direction.scale(radius)
+            });
+        Self::new_center_fan(outline, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const EPSILON: f32 = 1e-4;
+
+    /// Checks that every triangle indexes valid vertices and is counter-clockwise or
+    /// degenerate, and returns the total area of the triangles
+    fn area(mesh: &SpriteMesh) -> f32 {
+        assert_eq!(mesh.indices.len() % 3, 0);
+        let mut area = 0.;
+        for triangle in mesh.indices.chunks_exact(3) {
+            assert!(triangle
+                .iter()
+                .all(|&index| (index as usize) < mesh.vertices.len()));
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].position);
+            let triangle_area = b.sub(&a).perp_dot(&c.sub(&a)) / 2.;
+            assert!(triangle_area >= -EPSILON, "{:?} is clockwise", triangle);
+            area += triangle_area;
+        }
+        area
+    }
+
+    fn assert_area(mesh: &SpriteMesh, expected: f32) {
+        let area = area(mesh);
+        assert!(
+            (area - expected).abs() < expected * 0.001,
+            "area {} is not close to {}",
+            area,
+            expected
+        );
+    }
+
+    #[test]
+    fn segments_within_tolerance() {
+        // segments of a unit circle that turn through an eighth of a circle are this far
+        // from the curve
+        let sagitta = 1. - (TAU / 16.).cos();
+        let detail = MeshDetail::Tolerance(sagitta * 1.001);
+        assert_eq!(detail.segments(1., TAU), 8);
+        assert_eq!(detail.segments(1., TAU / 4.), 2);
+        assert_eq!(detail.segments(1., -TAU / 4.), 2);
+        assert_eq!(detail.segments(1., TAU / 3.), 3);
+
+        for &radius in &[0.5, 3., 40., 1000.] {
+            for &tolerance in &[0.001, 0.01, 0.2] {
+                let segments = MeshDetail::Tolerance(tolerance).segments(radius, TAU);
+                let sagitta = radius * (1. - (PI / segments as f32).cos());
+                // `acos` loses precision close to 1, when the tolerance is tiny compared to
+                // the radius
+                assert!(sagitta <= tolerance * 1.02);
+            }
+        }
+    }
+
+    #[test]
+    fn segments_of_large_tolerances() {
+        // a tolerance of at least the radius allows any number of segments, so the fewest
+        // that give a circle some area are used
+        assert_eq!(MeshDetail::Tolerance(1.).segments(1., TAU), 3);
+        assert_eq!(MeshDetail::Tolerance(5.).segments(1., TAU), 3);
+        assert_eq!(MeshDetail::Tolerance(5.).segments(1., TAU / 4.), 1);
+        assert_eq!(MeshDetail::Tolerance(0.).segments(1., TAU), 3);
+        assert_eq!(MeshDetail::Tolerance(-1.).segments(1., TAU * 2.), 6);
+    }
+
+    #[test]
+    fn segments_with_a_fixed_count() {
+        let detail = MeshDetail::Segments(32);
+        assert_eq!(detail.segments(1., TAU), 32);
+        assert_eq!(detail.segments(100., TAU), 32);
+        assert_eq!(detail.segments(1., TAU / 2.), 16);
+        assert_eq!(detail.segments(1., -TAU / 2.), 16);
+        assert_eq!(detail.segments(1., TAU / 5.), 7);
+        assert_eq!(MeshDetail::Segments(1).segments(1., TAU), 3);
+    }
+
+    #[test]
+    fn segments_of_zero_sweeps() {
+        assert_eq!(MeshDetail::Segments(32).segments(1., 0.), 1);
+        assert_eq!(MeshDetail::Tolerance(0.01).segments(1., 0.), 1);
+        assert_eq!(MeshDetail::Tolerance(5.).segments(1., 0.), 1);
+    }
+
+    #[test]
+    fn segments_are_capped() {
+        assert_eq!(MeshDetail::Tolerance(1e-6).segments(1000., TAU), 4096);
+        assert_eq!(MeshDetail::Segments(u16::MAX).segments(1., TAU), 4096);
+        assert_eq!(MeshDetail::Segments(4000).segments(1., TAU * 2.), 4096);
+    }
+
+    #[test]
+    fn rounded_rectangles() {
+        let detail = MeshDetail::Segments(4096);
+        let mesh = SpriteMesh::new_rounded_rectangle(4., 2., 0.5, detail);
+        assert_area(&mesh, 8. - (4. - PI) * 0.25);
+        // the radius is limited to half the shorter side
+        let mesh = SpriteMesh::new_rounded_rectangle(4., 2., 3., detail);
+        assert_area(&mesh, 8. - (4. - PI));
+        let mesh = SpriteMesh::new_rounded_rectangle(4., 2., 0., detail);
+        assert_area(&mesh, 8.);
+    }
+
+    #[test]
+    fn capsules() {
+        let detail = MeshDetail::Segments(4096);
+        assert_area(&SpriteMesh::new_capsule(4., 2., detail), 4. + PI);
+        assert_area(&SpriteMesh::new_capsule(2., 4., detail), 4. + PI);
+        assert_area(&SpriteMesh::new_capsule(2., 2., detail), PI);
+    }
+
+    #[test]
+    fn rings_and_arcs() {
+        let detail = MeshDetail::Segments(4096);
+        assert_area(&SpriteMesh::new_ring(2., 1., detail), 3. * PI);
+        assert_area(
+            &SpriteMesh::new_arc(2., 1., 0.5, TAU / 4., detail),
+            0.75 * PI,
+        );
+        assert_area(
+            &SpriteMesh::new_arc(2., 1., 0.5, -TAU / 4., detail),
+            0.75 * PI,
+        );
+        assert_area(&SpriteMesh::new_arc(2., 0., -1., TAU / 2., detail), 2. * PI);
+    }
+
+    #[test]
+    fn pies() {
+        let detail = MeshDetail::Segments(4096);
+        assert_area(&SpriteMesh::new_pie(2., 0., TAU / 3., detail), 4. * PI / 3.);
+        assert_area(
+            &SpriteMesh::new_pie(2., 1., -TAU / 3., detail),
+            4. * PI / 3.,
+        );
+        assert_area(&SpriteMesh::new_pie(2., 0., TAU, detail), 4. * PI);
+        assert_area(&SpriteMesh::new_pie(2., 0., -TAU, detail), 4. * PI);
+    }
+
+    #[test]
+    fn regular_polygons() {
+        for sides in 3..10 {
+            let mesh = SpriteMesh::new_regular_polygon(2., sides);
+            assert_eq!(mesh.vertices.len(), sides as usize + 1);
+            assert_area(&mesh, 2. * sides as f32 * (TAU / sides as f32).sin());
+        }
+    }
+
+    #[test]
+    fn stars() {
+        for points in 2..10 {
+            let mesh = SpriteMesh::new_star(2., 0.5, points);
+            assert_eq!(mesh.indices.len(), points as usize * 6);
+            assert_area(&mesh, points as f32 * (PI / points as f32).sin());
+        }
+    }
+
+    #[test]
+    fn coarse_meshes_are_counter_clockwise() {
+        let detail = MeshDetail::Tolerance(10.);
+        let meshes = [
+            SpriteMesh::new_rounded_rectangle(4., 2., 0.5, detail),
+            SpriteMesh::new_capsule(4., 2., detail),
+            SpriteMesh::new_ring(2., 1., detail),
+            SpriteMesh::new_arc(2., 1., 0., -0.1, detail),
+            SpriteMesh::new_pie(2., 3., 0.1, detail),
+            SpriteMesh::new_pie(2., 0., TAU, detail),
+        ];
+        for mesh in meshes.iter() {
+            assert!(area(mesh) > 0.);
+        }
+    }
+}
@@ -4,15 +4,28 @@ use std::f32::consts::{FRAC_PI_2, PI};
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct VertexInput {
-    position: Vector2,
-    texture_coordinates: Vector2,
+    pub(crate) position: Vector2,
+    pub(crate) texture_coordinates: Vector2,
+    /// Multiplied with the texture sample. White unless the mesh's colors have been set
+    pub(crate) color: [f32; 4],
 }
 
 impl VertexInput {
+    pub(crate) const SIZE: usize = std::mem::size_of::<Self>();
+
     pub fn new(position: Vector2) -> Self {
         Self {
             position,
             texture_coordinates: Vector2::ZERO,
+            color: [1.; 4],
+        }
+    }
+
+    pub(crate) fn with_texture_coordinates(position: Vector2, texture_coordinates: Vector2) -> Self {
+        Self {
+            position,
+            texture_coordinates,
+            color: [1.; 4],
         }
     }
 }
@@ -21,7 +34,7 @@ impl VertexInput {
 /// A Mesh that contains texture coordinates
 pub struct SpriteMesh {
     pub(crate) vertices: Vec<VertexInput>,
-    pub(crate) indices: Vec<u16>,
+    pub(crate) indices: Vec<u32>,
     pub(crate) affine2: Affine2,
 }
 
@@ -36,9 +49,7 @@ impl SpriteMesh {
                 VertexInput::new(Vector2::new(x, y)),
                 VertexInput::new(Vector2::new(-x, y)),
             ],
-            indices: vec![
-                0, 1, 2, 0, 2, 3, /* padding (though I don't think we need this) */ 0, 0,
-            ],
+            indices: vec![0, 1, 2, 0, 2, 3],
             affine2: Affine2::default(),
         }
     }
@@ -51,16 +62,9 @@ impl SpriteMesh {
 
         let mut vertices: Vec<VertexInput> = Vec::with_capacity(detail as usize);
         vertices.push(VertexInput::new(vector));
-        let mut indices: Vec<u16> = Vec::with_capacity({
-            let capacity = (detail - 2) * 3;
-            if capacity.is_power_of_two() {
-                capacity
-            } else {
-                capacity + 1
-            }
-        } as usize);
+        let mut indices: Vec<u32> = Vec::with_capacity((detail as usize - 2) * 3);
 
-        for i in 1..detail {
+        for i in 1..detail as u32 {
             vector.mul_assign(&matrix);
             vertices.push(VertexInput::new(vector));
             indices.push(0);
@@ -93,91 +97,62 @@ impl SpriteMesh {
         }
     }
 
-    pub(crate) fn new_line(width: f32, points: &[Vector2]) -> Self {
-        if points.len() < 2 {
-            panic!("Cannot create a line using fewer than 2 points");
-        }
-
-        let half_width = 0.5 * width;
-        let mut vertices = Vec::<VertexInput>::with_capacity(points.len() * 2);
-        let mut indices = Vec::<u16>::with_capacity(points.len() * 6);
-
-        // Returns the left and right translations required to move from `point` to a point on the
-        // line mesh. Left and right being relative to relative_point.relative_direction(&point)
-        let get_left_right_shifts = |point: &Vector2, relative_point: &Vector2| {
-            let left_shift = point
-                .sub(&relative_point)
-                .with_magnitude(half_width)
-                .rotate_90_ccw();
-            let right_shift = left_shift.rotate_180();
-            (left_shift, right_shift)
-        };
+    /// Creates a mesh filling the outline of a simple polygon, which may be concave. The
+    /// outline can be given in either winding order. Panics if there are fewer than 3 points
+    pub fn new_polygon(points: &[Vector2]) -> Self {
+        Self::new_polygon_with_holes(points, &[])
+    }
 
-        // for each point after the first [0], find a line either side of it that is half_width
-        // away from it, angled away from the previous point
-        let mut lines = Vec::<(Line, Line)>::with_capacity(points.len() * 2 - 1);
-        for (prev_point_index, point) in points.iter().skip(1).enumerate() {
-            let prev = unsafe { points.get_unchecked(prev_point_index) };
-            let (shift_left, shift_right) = get_left_right_shifts(point, &prev);
-            let line = Line::connect(point, &prev);
-            lines.push((line.shift(&shift_left), line.shift(&shift_right)));
+    /// Creates a mesh filling the outline of a simple polygon, with a hole cut out for each
+    /// of `holes`. Holes must be inside the outline and must not overlap each other. Panics if
+    /// the outline has fewer than 3 points
+    pub fn new_polygon_with_holes(outline: &[Vector2], holes: &[Vec<Vector2>]) -> Self {
+        if outline.len() < 3 {
+            panic!("Cannot create a polygon using fewer than 3 points");
         }
+        let (points, indices) = triangulation::triangulate(outline, holes);
 
-        // The first point is a special case, so here we get the direction relative to the second point [1]
-        {
-            let point_0 = points[0];
-            let (left_shift, right_shift) = get_left_right_shifts(&points[1], &point_0);
-            vertices.extend([
-                VertexInput::new(left_shift.add(&point_0)),
-                VertexInput::new(right_shift.add(&point_0)),
-            ]);
-            indices.extend([0, 1, 2, 1, 3, 2]);
+        Self {
+            vertices: points.into_iter().map(VertexInput::new).collect(),
+            indices,
+            affine2: Affine2::default(),
         }
+    }
 
-        for next_line_index in 1..points.len() - 1 {
-            let line_index = next_line_index - 1;
-            // I have extensively checked this and verified that this does not, under all scenarios result
-            // in undefined behaviour. The performance gain is not substantial, but can be noticeable
-            // for very detailed paths.
-            let (left_line, right_line) = unsafe { lines.get_unchecked(line_index) };
-            let (next_left_line, next_right_line) = unsafe { lines.get_unchecked(next_line_index) };
+    /// Returns the number of vertices in this mesh
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
 
-            let vector_1 = Line::intersection(left_line, next_left_line);
-            if vector_1.is_some() {
-                let vector_2 = Line::intersection(right_line, next_right_line);
-                vertices.extend([
-                    VertexInput::new(vector_1.unwrap()),
-                    VertexInput::new(vector_2.unwrap()),
-                ]);
-            } else {
-                vertices.extend([
-                    VertexInput::new(left_line.position),
-                    VertexInput::new(right_line.position),
-                ]);
-            }
-            let i = (line_index as u16) << 1;
-            indices.extend([i, i + 1, i + 2, i + 1, i + 3, i + 2]);
+    /// Sets the color of every vertex. Vertex colors are multiplied with the texture, so a
+    /// white texture takes on the vertex colors
+    pub fn set_color(&mut self, color: Color) {
+        let color = color.as_f32_array();
+        for vertex in self.vertices.iter_mut() {
+            vertex.color = color;
         }
+    }
 
-        {
-            // len - 1 points to the last vertex and len - 2 points to the penultimate vertex
-            // we always draw in the patter bl, br, tl, br, tr, tl and so, viewing the last two vertices as
-            // ..bl, br], we need to point to bl (len - 2).
-            let i = (vertices.len() - 2) as u16;
-            let point = &points[points.len() - 1];
-            let (left_point, right_point) =
-                get_left_right_shifts(&point, &points[points.len() - 2]);
-            vertices.extend([
-                VertexInput::new(left_point.add(&point)),
-                VertexInput::new(right_point.add(&point)),
-            ]);
-            indices.extend([i, i + 1, i + 2, i + 1, i + 3, i + 2]);
+    /// Sets the color of each vertex, in order. Colors are blended across each triangle.
+    /// Panics if there is not exactly one color per vertex
+    pub fn set_vertex_colors(&mut self, colors: &[Color]) {
+        if colors.len() != self.vertices.len() {
+            panic!(
+                "Expected {} vertex colors, but got {}",
+                self.vertices.len(),
+                colors.len()
+            );
         }
+        for (vertex, color) in self.vertices.iter_mut().zip(colors) {
+            vertex.color = color.as_f32_array();
+        }
+    }
 
-        Self {
-            vertices,
-            indices,
-            affine2: Affine2::default(),
+    /// Sets the color of each vertex to the color returned for its position, before this
+    /// mesh's transformation is applied
+    pub fn set_vertex_colors_with(&mut self, mut color: impl FnMut(Vector2) -> Color) {
+        for vertex in self.vertices.iter_mut() {
+            vertex.color = color(vertex.position).as_f32_array();
         }
     }
 
@@ -215,7 +190,10 @@ impl SpriteMesh {
         texture_projection_method: &TextureProjectionMethod,
     ) {
         match texture_projection_method {
-            TextureProjectionMethod::ScaleToFit => {
+            // only curves know their length, so other meshes are scaled to fit
+            TextureProjectionMethod::ScaleToFit
+            | TextureProjectionMethod::RepeatAlongLength
+            | TextureProjectionMethod::StretchAlongLength => {
                 let bounds = self.bounds();
                 let width = bounds.width();
                 let height = bounds.height();
@@ -248,17 +226,11 @@ impl SpriteMesh {
         }
     }
 
-    pub(crate) fn index_buffer_contents(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(self.indices.as_ptr() as *const u8, self.indices.len() << 1)
-        }
-    }
-
     pub(crate) fn vertex_buffer_contents(&self) -> &[u8] {
         unsafe {
             std::slice::from_raw_parts(
                 self.vertices.as_ptr() as *const u8,
-                self.vertices.len() << 4,
+                self.vertices.len() * VertexInput::SIZE,
             )
         }
     }
@@ -1,6 +1,7 @@
 use rand::distributions::Uniform;
 
 use crate::*;
+use std::rc::Rc;
 
 pub struct SpriteConfiguration<'a> {
     pub label: Option<&'a str>,
@@ -9,6 +10,10 @@ pub struct SpriteConfiguration<'a> {
     pub z_index: ZIndex,
     pub opacity: f32,
     pub texture_projection_method: TextureProjectionMethod,
+    /// A normal map used to shade this sprite with the lights added through
+    /// `Oge::add_light`. Create it with `Oge::create_normal_map`. It is sampled with the same
+    /// texture coordinates as `default_texture`
+    pub normal_map: Option<Texture>,
 }
 
 impl SpriteConfiguration<'_> {
@@ -20,6 +25,7 @@ impl SpriteConfiguration<'_> {
             z_index: ZIndex::Specific(0),
             opacity: 1.,
             texture_projection_method: TextureProjectionMethod::SingleColor,
+            normal_map: None,
         })
     }
 }
@@ -66,6 +72,7 @@ pub struct Sprite {
     pub(crate) z_index: ZIndex,
     pub(crate) opacity: f32,
     pub(crate) texture_projection_method: TextureProjectionMethod,
+    pub(crate) normal_map: Option<Texture>,
 }
 
 impl Sprite {
@@ -81,6 +88,7 @@ impl Sprite {
             z_index: config.z_index,
             opacity: 1.,
             texture_projection_method: config.texture_projection_method,
+            normal_map: config.normal_map,
         };
         this.mesh.update_texture_coordinates(&this.default_texture, &this.texture_projection_method);
         this.set_opacity(config.opacity);
@@ -88,27 +96,86 @@ impl Sprite {
     }
 
     pub(crate) fn get_render_bundle(&self, oge: &Oge) -> RenderBundle {
+        Self::create_render_bundle(
+            oge,
+            self.label.as_deref(),
+            &self.mesh,
+            &self.default_texture,
+            self.normal_map.as_ref(),
+            self.opacity,
+            self.z_index,
+        )
+    }
+
+    /// Creates a `RenderBundle` from the parts that make up a sprite. This lets other
+    /// drawables share a single texture between several meshes. `label` names the
+    /// vertex buffer when debugging.
+    pub(crate) fn create_render_bundle(
+        oge: &Oge,
+        label: Option<&str>,
+        mesh: &SpriteMesh,
+        texture: &Texture,
+        normal_map: Option<&Texture>,
+        opacity: f32,
+        z_index: ZIndex,
+    ) -> RenderBundle {
+        let bind_group =
+            Self::create_bind_group(oge, &mesh.affine2, texture, normal_map, opacity);
+        let (index_buffer, index_format) = oge
+            .render_state
+            .device_wrapper
+            .create_index_buffer(&mesh.indices);
+
+        RenderBundle {
+            vertex_buffer: Rc::new(
+                oge.render_state
+                    .device_wrapper
+                    .create_vertex_buffer(label, &mesh.vertex_buffer_contents()),
+            ),
+            index_buffer: Rc::new(index_buffer),
+            index_format,
+            index_count: mesh.indices.len() as u32,
+            pipeline: PipelineKind::Sprite,
+            instance_buffer: None,
+            instance_count: 1,
+            stencil_mode: StencilMode::None,
+            stencil_reference: 0,
+            scissor_rect: None,
+            bind_group,
+            z_index,
+        }
+    }
+
+    /// Creates the bind group used by every pipeline. Its uniform buffer maps positions from
+    /// the space described by `model_affine2` to the window.
+    pub(crate) fn create_bind_group(
+        oge: &Oge,
+        model_affine2: &Affine2,
+        texture: &Texture,
+        normal_map: Option<&Texture>,
+        opacity: f32,
+    ) -> wgpu::BindGroup {
         #[repr(C)]
-        pub(crate) struct UniformBufferContents {
+        pub(crate) struct UniformBufferContents<'a> {
             affine2: Affine2,
+            model_affine2: Affine2,
             opacity: f32,
+            normal_mapped: bool,
+            light_handler: &'a LightHandler,
         }
 
-        impl UniformBufferContents {
+        impl UniformBufferContents<'_> {
             fn as_vec_u8(&self) -> Vec<u8> {
                 let size = RenderState::UNIFORM_BUFFER_SIZE.get() as usize;
-                let mut dst = Vec::<u8>::with_capacity(size);
-                unsafe {
-                    dst.set_len(size);
-                }
-                for el in dst.iter_mut() {
-                    *el = 0;
-                }
+                let mut dst = vec![0u8; size];
 
                 for (i, vector2) in [
                     self.affine2.matrix2.i,
                     self.affine2.matrix2.j,
                     self.affine2.translation,
+                    self.model_affine2.matrix2.i,
+                    self.model_affine2.matrix2.j,
+                    self.model_affine2.translation,
                 ]
                 .iter()
                 .enumerate()
@@ -116,48 +183,44 @@ impl Sprite {
                     let dst = &mut dst[i * 16] as *mut u8;
                     unsafe { std::ptr::copy(vector2 as *const Vector2 as *const u8, dst, 8) }
                 }
-                unsafe {
-                    std::ptr::copy(
-                        &self.opacity as *const f32 as *const u8,
-                        &mut dst[size - 16] as *mut u8,
-                        4,
-                    );
+
+                let light_count = self.light_handler.point_lights.len() as u32;
+                dst[96..100].copy_from_slice(&self.opacity.to_ne_bytes());
+                dst[100..104].copy_from_slice(&light_count.to_ne_bytes());
+                dst[104..108].copy_from_slice(&(self.normal_mapped as u32).to_ne_bytes());
+                for (i, component) in self
+                    .light_handler
+                    .ambient_light
+                    .as_f32_array()
+                    .iter()
+                    .enumerate()
+                {
+                    dst[112 + i * 4..116 + i * 4].copy_from_slice(&component.to_ne_bytes());
+                }
+                for (i, light) in self.light_handler.point_lights.iter().enumerate() {
+                    let offset = 128 + i * PointLight::UNIFORM_SIZE;
+                    light.write_uniform(&mut dst[offset..offset + PointLight::UNIFORM_SIZE]);
                 }
 
                 dst
             }
         }
 
-        let mut affine2 = self.mesh.affine2;
-        affine2 = affine2.reverse_compose(&oge.handlers.window_handler.affine2);
-        affine2
-            .translation
-            .mul_assign(&oge.handlers.window_handler.affine2.matrix2);
+        let affine2 = oge.handlers.window_handler.affine2.compose(model_affine2);
 
         let uniform_buffer_contents = UniformBufferContents {
             affine2,
-            opacity: self.opacity,
+            model_affine2: *model_affine2,
+            opacity,
+            normal_mapped: normal_map.is_some(),
+            light_handler: &oge.handlers.light_handler,
         };
 
-        let bind_group = oge.render_state.device_wrapper.create_texture_bind_group(
-            &self.default_texture.texture_view,
-            &self.default_texture.sampler,
+        oge.render_state.device_wrapper.create_texture_bind_group(
+            texture,
+            normal_map.unwrap_or(texture),
             &uniform_buffer_contents.as_vec_u8(),
-        );
-
-        RenderBundle {
-            vertex_buffer: oge
-                .render_state
-                .device_wrapper
-                .create_vertex_buffer(&self.mesh.vertex_buffer_contents()),
-            index_buffer: oge
-                .render_state
-                .device_wrapper
-                .create_index_buffer(&self.mesh.index_buffer_contents()),
-            index_count: self.mesh.indices.len() as u32,
-            bind_group,
-            z_index: self.z_index,
-        }
+        )
     }
 
     /// Queue an additional 2x2 linear transformation to be executed on this sprite during the shader stage.  
@@ -182,6 +245,12 @@ impl Sprite {
         self.mesh.affine2 = Affine2::new(matrix.i, matrix.j, self.mesh.affine2.translation);
     }
 
+    /// Returns the 2x2 transformation applied to this sprite by `set_transformation` and
+    /// `transform`. Use `Matrix2::polar_decomposition` to recover its rotation and scale
+    pub fn transformation(&self) -> Matrix2 {
+        self.mesh.affine2.matrix2
+    }
+
     /// Set the opacity of this sprite. Values should be from `0.` to `1.`.  
     ///
     /// Values outside of these bounds will be capped.
@@ -199,6 +268,28 @@ impl Sprite {
     pub fn opacity(&self) -> f32 {
         self.opacity
     }
+
+    /// Sets the color of every vertex of this sprite's mesh. See `SpriteMesh::set_color`
+    pub fn set_color(&mut self, color: Color) {
+        self.mesh.set_color(color);
+    }
+
+    /// Sets the color of each vertex of this sprite's mesh. See
+    /// `SpriteMesh::set_vertex_colors`
+    pub fn set_vertex_colors(&mut self, colors: &[Color]) {
+        self.mesh.set_vertex_colors(colors);
+    }
+
+    /// Sets the color of each vertex of this sprite's mesh from its position. See
+    /// `SpriteMesh::set_vertex_colors_with`
+    pub fn set_vertex_colors_with(&mut self, color: impl FnMut(Vector2) -> Color) {
+        self.mesh.set_vertex_colors_with(color);
+    }
+
+    /// Sets the normal map used to light this sprite, or stops it from being lit if `None`
+    pub fn set_normal_map(&mut self, normal_map: Option<Texture>) {
+        self.normal_map = normal_map;
+    }
 }
 
 impl IntoRenderBundle for &Sprite {
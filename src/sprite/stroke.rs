@@ -0,0 +1,883 @@
+use crate::*;
+use std::f32::consts::PI;
+use std::ops::Range;
+
+/// Below this, the sine of the angle between two segments is treated as zero
+const EPSILON: f32 = 1e-5;
+
+/// The most dashes and gaps a line is split into, so that a pattern much shorter than the
+/// line can't stall the frame
+const MAX_DASH_PIECES: usize = 1 << 16;
+
+/// Describes how a line was built into a mesh, so that the mesh can be updated one piece at a
+/// time when the line changes
+#[derive(Debug)]
+pub(crate) struct Stroke {
+    /// Where each vertex of the mesh is relative to the line
+    pub(crate) stroke_vertices: Vec<StrokeVertex>,
+    /// The points the line is drawn through, without points on top of the previous one
+    kept: Vec<StrokePoint>,
+    /// The first vertex and index of the piece of the mesh along each segment of the line.
+    /// Empty for dashed lines, which are always rebuilt completely
+    pieces: Vec<(usize, usize)>,
+    /// The number of points the line was built from
+    point_count: usize,
+    /// Whether the line is joined from its last point back to its first, which needs
+    /// `style.closed` and enough segments to turn back on itself
+    joined: bool,
+    style: StrokeStyle,
+}
+
+/// How a `Stroke` is drawn around its points
+#[derive(Debug, Clone)]
+pub(crate) struct StrokeStyle {
+    /// Whether the line continues from its last point back to its first, and is joined there
+    /// instead of capped
+    pub(crate) closed: bool,
+    pub(crate) cap: LineCap,
+    pub(crate) join: LineJoin,
+    /// The pattern the line is split into separate dashes with, each with its own caps
+    pub(crate) dashes: Option<DashPattern>,
+    /// How round caps and joins are split into segments
+    pub(crate) detail: MeshDetail,
+}
+
+/// Where a vertex of a `Stroke` is relative to the line it was built around
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StrokeVertex {
+    /// How far along the points the vertex is, where `1.5` is halfway between the second and
+    /// third points
+    pub(crate) parameter: f32,
+    /// How far past `parameter` the vertex is along the line, for vertices in caps
+    pub(crate) extension: f32,
+    /// Which side of the line the vertex is on, from `1.` on the left edge to `-1.` on the
+    /// right edge
+    pub(crate) side: f32,
+}
+
+impl StrokeVertex {
+    fn new(parameter: f32, side: f32) -> Self {
+        Self {
+            parameter,
+            extension: 0.,
+            side,
+        }
+    }
+}
+
+/// A point to draw a line through
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StrokePoint {
+    /// How far along the original points this point is
+    parameter: f32,
+    position: Vector2,
+    /// Half the width of the line at this point
+    half_width: f32,
+}
+
+/// A vertex position, and where it is relative to the line
+type Edge = (Vector2, StrokeVertex);
+
+/// A straight piece of a stroke, between two of the points it is drawn through
+#[derive(Clone, Copy)]
+struct Segment {
+    direction: Vector2,
+    /// The direction to the left of `direction`
+    normal: Vector2,
+    length: f32,
+}
+
+/// One of the pieces a dashed line is drawn in
+struct Dash {
+    points: Vec<StrokePoint>,
+    /// The direction of the line at the start of the dash, used for dashes with no length
+    direction: Vector2,
+}
+
+/// Returns the points a line is drawn through, skipping points on top of the previous one as
+/// they have no direction, and the segments between them. If every point is in the same
+/// place, there is a single segment with no length facing `direction`
+fn prepare_line(points: &[StrokePoint], direction: Vector2) -> (Vec<StrokePoint>, Vec<Segment>) {
+    let mut kept = vec![points[0]];
+    for &point in points.iter().skip(1) {
+        if point.position.distance_to(&kept[kept.len() - 1].position) > f32::EPSILON {
+            kept.push(point);
+        }
+    }
+    let mut segments: Vec<Segment> = kept
+        .windows(2)
+        .map(|pair| {
+            let offset = pair[1].position.sub(&pair[0].position);
+            let direction = offset.normalize();
+            Segment {
+                direction,
+                normal: direction.rotate_90_ccw(),
+                length: offset.magnitude(),
+            }
+        })
+        .collect();
+    if segments.is_empty() {
+        kept.push(*points.last().unwrap());
+        segments.push(Segment {
+            direction,
+            normal: direction.rotate_90_ccw(),
+            length: 0.,
+        });
+    }
+    (kept, segments)
+}
+
+/// Returns the left and right edges of a line that ends flat at `point`, moved `extension`
+/// along the line
+fn cap_edges(point: StrokePoint, segment: &Segment, extension: f32) -> [Edge; 2] {
+    let end = point.position.add(&segment.direction.scale(extension));
+    let offset = segment.normal.scale(point.half_width);
+    let vertex = |side| StrokeVertex {
+        parameter: point.parameter,
+        extension,
+        side,
+    };
+    [
+        (end.add(&offset), vertex(1.)),
+        (end.sub(&offset), vertex(-1.)),
+    ]
+}
+
+/// Where the edges of two segments meet, around one of the points of a line
+struct Corner {
+    point: StrokePoint,
+    incoming: Segment,
+    outgoing: Segment,
+    cosine: f32,
+    /// Whether the segments are in a straight line
+    straight: bool,
+    /// The side of the line on the outside of the turn, 1 for left and -1 for right
+    side: f32,
+    /// The offset from the point to where the outer edges would meet, if they aren't parallel
+    miter: Option<Vector2>,
+    /// Whether the inner edges meet. They overlap if the segments are too short for the
+    /// corner
+    inner_meets: bool,
+}
+
+impl Corner {
+    fn new(point: StrokePoint, incoming: Segment, outgoing: Segment) -> Self {
+        let sine = incoming.direction.x * outgoing.direction.y
+            - incoming.direction.y * outgoing.direction.x;
+        let cosine = incoming.direction.dot(&outgoing.direction);
+        let side = if sine > 0. { -1. } else { 1. };
+        let miter = if 1. + cosine > EPSILON {
+            let normal_sum = incoming.normal.add(&outgoing.normal);
+            Some(normal_sum.scale(point.half_width * side / (1. + cosine)))
+        } else {
+            None
+        };
+        let inner_meets = miter.map_or(false, |miter| {
+            let overlap = miter.dot(&incoming.direction).abs();
+            overlap <= incoming.length.min(outgoing.length) / 2.
+        });
+        Self {
+            point,
+            incoming,
+            outgoing,
+            cosine,
+            straight: sine.abs() <= EPSILON && cosine > 0.,
+            side,
+            miter,
+            inner_meets,
+        }
+    }
+
+    /// Returns the left and right edges of `segment`, which is the incoming or outgoing
+    /// segment, at this corner
+    fn edges(&self, segment: &Segment) -> [Edge; 2] {
+        let StrokePoint {
+            parameter,
+            position: point,
+            half_width,
+        } = self.point;
+        if self.straight {
+            return [
+                (
+                    point.add(&segment.normal.scale(half_width)),
+                    StrokeVertex::new(parameter, 1.),
+                ),
+                (
+                    point.sub(&segment.normal.scale(half_width)),
+                    StrokeVertex::new(parameter, -1.),
+                ),
+            ];
+        }
+        let outer = (
+            point.add(&segment.normal.scale(half_width * self.side)),
+            StrokeVertex::new(parameter, self.side),
+        );
+        let inner = match (self.miter, self.inner_meets) {
+            (Some(miter), true) => point.sub(&miter),
+            _ => point.sub(&segment.normal.scale(half_width * self.side)),
+        };
+        let inner = (inner, StrokeVertex::new(parameter, -self.side));
+        if self.side > 0. {
+            [outer, inner]
+        } else {
+            [inner, outer]
+        }
+    }
+}
+
+struct StrokeBuilder {
+    vertices: Vec<VertexInput>,
+    indices: Vec<u32>,
+    stroke_vertices: Vec<StrokeVertex>,
+    detail: MeshDetail,
+}
+
+impl StrokeBuilder {
+    fn new(detail: MeshDetail) -> Self {
+        Self {
+            vertices: vec![],
+            indices: vec![],
+            stroke_vertices: vec![],
+            detail,
+        }
+    }
+
+    fn vertex(&mut self, (position, stroke_vertex): Edge) -> u32 {
+        self.vertices.push(VertexInput::new(position));
+        self.stroke_vertices.push(stroke_vertex);
+        self.vertices.len() as u32 - 1
+    }
+
+    fn triangle(&mut self, triangle: [u32; 3]) {
+        push_triangle(&self.vertices, &mut self.indices, triangle);
+    }
+
+    /// Adds a quad between two pairs of left and right edges
+    fn quad(&mut self, [a_left, a_right]: [Edge; 2], [b_left, b_right]: [Edge; 2]) {
+        let (a_left, a_right) = (self.vertex(a_left), self.vertex(a_right));
+        let (b_left, b_right) = (self.vertex(b_left), self.vertex(b_right));
+        self.triangle([a_left, a_right, b_left]);
+        self.triangle([a_right, b_right, b_left]);
+    }
+
+    /// Adds triangles from `pivot` to an arc of `radius` around `center`, which starts in the
+    /// direction of `from` and turns through `angle` towards `toward`. `locate` returns where
+    /// each vertex is relative to the line, from its direction from `center`
+    fn fan(
+        &mut self,
+        pivot: u32,
+        (center, radius): (Vector2, f32),
+        (from, toward): (Vector2, Vector2),
+        angle: f32,
+        locate: impl Fn(Vector2) -> StrokeVertex,
+    ) {
+        let segments = self.detail.segments(radius, angle);
+        let mut previous = None;
+        for i in 0..=segments {
+            let (sin, cos) = (angle * i as f32 / segments as f32).sin_cos();
+            let offset = from.scale(cos).add(&toward.scale(sin));
+            let vertex = self.vertex((center.add(&offset.scale(radius)), locate(offset)));
+            if let Some(previous) = previous {
+                self.triangle([pivot, previous, vertex]);
+            }
+            previous = Some(vertex);
+        }
+    }
+
+    /// Adds a semicircle around `point`, facing away from the line in `direction`
+    fn round_cap(&mut self, point: StrokePoint, direction: Vector2) {
+        let StrokePoint {
+            parameter,
+            position,
+            half_width,
+        } = point;
+        let center = self.vertex((position, StrokeVertex::new(parameter, 0.)));
+        self.fan(
+            center,
+            (position, half_width),
+            (direction.rotate_90_ccw(), direction),
+            PI,
+            |offset| StrokeVertex {
+                parameter,
+                extension: offset.dot(&direction) * half_width,
+                side: offset.dot(&direction.rotate_90_ccw()),
+            },
+        );
+    }
+
+    /// Fills the outside of `corner` with `join`
+    fn join(&mut self, corner: &Corner, join: LineJoin) {
+        if corner.straight {
+            return;
+        }
+        let StrokePoint {
+            parameter,
+            position: point,
+            half_width,
+        } = corner.point;
+        let outer = StrokeVertex::new(parameter, corner.side);
+        let pivot = match (corner.miter, corner.inner_meets) {
+            (Some(miter), true) => self.vertex((
+                point.sub(&miter),
+                StrokeVertex::new(parameter, -corner.side),
+            )),
+            _ => self.vertex((point, StrokeVertex::new(parameter, 0.))),
+        };
+        let outer_incoming = self.vertex((
+            point.add(&corner.incoming.normal.scale(half_width * corner.side)),
+            outer,
+        ));
+        let outer_outgoing = self.vertex((
+            point.add(&corner.outgoing.normal.scale(half_width * corner.side)),
+            outer,
+        ));
+
+        match join {
+            LineJoin::Miter(limit) => match corner.miter {
+                // the miter length divided by the stroke's width
+                Some(miter) if miter.magnitude() / half_width <= limit => {
+                    let tip = self.vertex((point.add(&miter), outer));
+                    self.triangle([pivot, outer_incoming, tip]);
+                    self.triangle([pivot, tip, outer_outgoing]);
+                }
+                _ => self.triangle([pivot, outer_incoming, outer_outgoing]),
+            },
+            LineJoin::Bevel => self.triangle([pivot, outer_incoming, outer_outgoing]),
+            LineJoin::Round => {
+                let angle = corner.cosine.clamp(-1., 1.).acos();
+                let from = corner.incoming.normal.scale(corner.side);
+                self.fan(
+                    pivot,
+                    (point, half_width),
+                    (from, corner.incoming.direction),
+                    angle,
+                    |_| outer,
+                );
+            }
+        }
+    }
+
+    /// Adds the piece of a line along `segments[index]`, which starts at `kept[index]`. Each
+    /// piece has its own vertices, and is made of a quad along the segment, caps if it is at
+    /// either end of the line and the join at its end otherwise. It depends on the points from
+    /// `kept[index - 1]` to `kept[index + 2]`. If the line is `closed`, its last point is on
+    /// top of its first, and the last piece ends with the join between the last and first
+    /// segments
+    fn piece(
+        &mut self,
+        kept: &[StrokePoint],
+        segments: &[Segment],
+        index: usize,
+        (cap, join): (LineCap, LineJoin),
+        closed: bool,
+    ) {
+        let segment = segments[index];
+        let extension = |point: StrokePoint| match cap {
+            LineCap::Square => point.half_width,
+            _ => 0.,
+        };
+        let start = match index {
+            0 if !closed => cap_edges(kept[0], &segment, -extension(kept[0])),
+            0 => Corner::new(kept[0], segments[segments.len() - 1], segment).edges(&segment),
+            _ => Corner::new(kept[index], segments[index - 1], segment).edges(&segment),
+        };
+        let outgoing = match segments.get(index + 1) {
+            None if closed => segments.first(),
+            outgoing => outgoing,
+        };
+        let end_corner = outgoing.map(|&outgoing| Corner::new(kept[index + 1], segment, outgoing));
+        let end = match &end_corner {
+            Some(corner) => corner.edges(&segment),
+            None => cap_edges(kept[index + 1], &segment, extension(kept[index + 1])),
+        };
+        self.quad(start, end);
+
+        if cap == LineCap::Round {
+            if index == 0 && !closed {
+                self.round_cap(kept[0], segment.direction.rotate_180());
+            }
+            if end_corner.is_none() {
+                self.round_cap(kept[index + 1], segment.direction);
+            }
+        }
+        if let Some(corner) = end_corner {
+            self.join(&corner, join);
+        }
+    }
+
+    /// Adds a line through `points`, with `cap` at either end and `join` between each pair
+    /// of segments. If every point is in the same place, the caps are drawn facing
+    /// `direction`. If `closed`, the last point must be on top of the first, and the ends are
+    /// joined instead of capped. Returns the points the line is drawn through, the first
+    /// vertex and index of each segment's piece, and whether it was closed
+    fn line(
+        &mut self,
+        points: &[StrokePoint],
+        direction: Vector2,
+        style: (LineCap, LineJoin),
+        closed: bool,
+    ) -> (Vec<StrokePoint>, Vec<(usize, usize)>, bool) {
+        let (kept, segments) = prepare_line(points, direction);
+        // a loop needs at least two segments to turn back on itself
+        let closed = closed && segments.len() >= 2;
+        let mut pieces = Vec::with_capacity(segments.len());
+        for index in 0..segments.len() {
+            pieces.push((self.vertices.len(), self.indices.len()));
+            self.piece(&kept, &segments, index, style, closed);
+        }
+        (kept, pieces, closed)
+    }
+}
+
+/// Splits the line through `points` into the pieces drawn by `pattern`
+fn split_dashes(points: &[StrokePoint], pattern: &DashPattern) -> Vec<Dash> {
+    let mut distances = Vec::with_capacity(points.len());
+    let mut total = 0.;
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            total += point.position.distance_to(&points[i - 1].position);
+        }
+        distances.push(total);
+    }
+
+    // the index of the segment `distance` along the line, and the point there
+    let locate = |distance: f32| {
+        let i = distances
+            .partition_point(|&start| start <= distance)
+            .saturating_sub(1)
+            .min(points.len() - 2);
+        let (start, end) = (points[i], points[i + 1]);
+        let length = distances[i + 1] - distances[i];
+        let t = if length > 0. {
+            ((distance - distances[i]) / length).clamp(0., 1.)
+        } else {
+            0.
+        };
+        let point = StrokePoint {
+            parameter: start.parameter + (end.parameter - start.parameter) * t,
+            position: start
+                .position
+                .add(&end.position.sub(&start.position).scale(t)),
+            half_width: start.half_width + (end.half_width - start.half_width) * t,
+        };
+        (i, point)
+    };
+    let dash = |start: f32, end: f32| {
+        let (first_segment, first) = locate(start);
+        let (last_segment, last) = locate(end);
+        let mut dash_points = vec![first];
+        dash_points.extend(
+            (first_segment + 1..=last_segment)
+                .filter(|&i| distances[i] > start && distances[i] < end)
+                .map(|i| points[i]),
+        );
+        dash_points.push(last);
+        let direction = points[first_segment + 1]
+            .position
+            .sub(&points[first_segment].position);
+        Dash {
+            points: dash_points,
+            direction: if direction.magnitude() > 0. {
+                direction.normalize()
+            } else {
+                Vector2::RIGHT
+            },
+        }
+    };
+
+    // a pattern with no length is drawn solid, as in SVG
+    let period = pattern.period();
+    if period == 0. {
+        return vec![dash(0., total)];
+    }
+
+    // find where in the pattern the line starts
+    let lengths = &pattern.lengths;
+    let mut index = 0;
+    let mut remaining = lengths[0];
+    let mut skipped = pattern.phase.rem_euclid(period);
+    for _ in 0..lengths.len() {
+        // a dot at the very start of the pattern is still drawn
+        if skipped < remaining || skipped == 0. {
+            break;
+        }
+        skipped -= remaining;
+        index = (index + 1) % lengths.len();
+        remaining = lengths[index];
+    }
+    remaining -= skipped;
+
+    let mut dashes = vec![];
+    let mut start = 0.;
+    for _ in 0..MAX_DASH_PIECES {
+        let end = start + remaining;
+        // dashes that start at the very end are only drawn if they are dots
+        if index % 2 == 0 && (start < total || remaining == 0.) {
+            dashes.push(dash(start, end.min(total)));
+        }
+        if end > total {
+            break;
+        }
+        start = end;
+        index = (index + 1) % lengths.len();
+        remaining = lengths[index];
+    }
+    dashes
+}
+
+impl Stroke {
+    /// Creates a mesh of a line through `points`, `widths[i]` wide at `points[i]` and blended
+    /// between them. Each of `splits` is a distance along the line and the width there, and
+    /// the line is also split there so that its width can change between points. The line is
+    /// drawn with `style`, which is kept for `update`. Panics if there are fewer than 2
+    /// points, or fewer widths than points
+    pub(crate) fn new(
+        points: &[Vector2],
+        widths: (&[f32], &[(f32, f32)]),
+        style: StrokeStyle,
+    ) -> (SpriteMesh, Self) {
+        if points.len() < 2 {
+            panic!("Cannot create a line using fewer than 2 points");
+        }
+        let stroke_points = Self::stroke_points(points, widths, style.closed);
+
+        let mut builder = StrokeBuilder::new(style.detail);
+        let cap_join = (style.cap, style.join);
+        let (kept, pieces, joined) = match &style.dashes {
+            // a pattern without any length would never end
+            Some(pattern) if pattern.period() > 0. => {
+                // a dash across the start of a closed line is split in two there
+                for dash in split_dashes(&stroke_points, pattern) {
+                    builder.line(&dash.points, dash.direction, cap_join, false);
+                }
+                (vec![], vec![], false)
+            }
+            _ => builder.line(&stroke_points, Vector2::RIGHT, cap_join, style.closed),
+        };
+
+        let mesh = SpriteMesh {
+            vertices: builder.vertices,
+            indices: builder.indices,
+            affine2: Affine2::default(),
+        };
+        let stroke = Self {
+            stroke_vertices: builder.stroke_vertices,
+            kept,
+            pieces,
+            point_count: points.len(),
+            joined,
+            style,
+        };
+        (mesh, stroke)
+    }
+
+    /// Returns `points` along with how far along them each one is and their widths, with
+    /// points added at `splits`. A closed line returns to its first point, which is
+    /// `points.len()` along
+    fn stroke_points(
+        points: &[Vector2],
+        (widths, splits): (&[f32], &[(f32, f32)]),
+        closed: bool,
+    ) -> Vec<StrokePoint> {
+        if widths.len() < points.len() {
+            panic!("Cannot create a line with fewer widths than points");
+        }
+        let mut stroke_points: Vec<StrokePoint> = points
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(i, (&position, &width))| StrokePoint {
+                parameter: i as f32,
+                position,
+                half_width: 0.5 * width,
+            })
+            .collect();
+        if closed {
+            stroke_points.push(StrokePoint {
+                parameter: points.len() as f32,
+                ..stroke_points[0]
+            });
+        }
+        if splits.is_empty() {
+            return stroke_points;
+        }
+
+        let mut split_points = Vec::with_capacity(stroke_points.len() + splits.len());
+        split_points.push(stroke_points[0]);
+        let mut splits = splits.iter().peekable();
+        let mut distance = 0.;
+        for pair in stroke_points.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let length = start.position.distance_to(&end.position);
+            while let Some(&&(split, width)) = splits.peek() {
+                if split >= distance + length {
+                    break;
+                }
+                splits.next();
+                if split > distance {
+                    let t = (split - distance) / length;
+                    split_points.push(StrokePoint {
+                        parameter: start.parameter + (end.parameter - start.parameter) * t,
+                        position: start
+                            .position
+                            .add(&end.position.sub(&start.position).scale(t)),
+                        half_width: 0.5 * width,
+                    });
+                }
+            }
+            split_points.push(end);
+            distance += length;
+        }
+        split_points
+    }
+
+    /// Updates `mesh`, which was built by this stroke, to follow `points` and `widths` with
+    /// the style it was built with. Only the pieces of the mesh around points that have
+    /// moved, changed width, been added or been removed are rebuilt. Returns the ranges of
+    /// vertices and indices that have changed, which continue to the end of the mesh if the
+    /// number of vertices or indices has changed
+    pub(crate) fn update(
+        &mut self,
+        mesh: &mut SpriteMesh,
+        points: &[Vector2],
+        widths: (&[f32], &[(f32, f32)]),
+    ) -> (Range<usize>, Range<usize>) {
+        let stroke_points = Self::stroke_points(points, widths, self.style.closed);
+        let (kept, segments) = prepare_line(&stroke_points, Vector2::RIGHT);
+
+        // the points at the start and end that haven't changed. Points at the end may have
+        // moved along if points were added or removed before them
+        let shift = points.len() as f32 - self.point_count as f32;
+        let unchanged_start = self
+            .kept
+            .iter()
+            .zip(kept.iter())
+            .take_while(|(old, new)| old == new)
+            .count();
+        let unchanged_end = self
+            .kept
+            .iter()
+            .rev()
+            .zip(kept.iter().rev())
+            .take(self.kept.len().min(kept.len()) - unchanged_start)
+            .take_while(|(old, new)| {
+                old.position == new.position
+                    && old.half_width == new.half_width
+                    && new.parameter == old.parameter + shift
+            })
+            .count();
+
+        // dashes move whenever the length before them changes, and a line with no length is
+        // only its caps, so both are rebuilt completely. The first and last pieces of a
+        // closed line depend on each other, so changes next to where they meet rebuild the
+        // whole line too
+        let joined = self.style.closed && segments.len() >= 2;
+        if self.pieces.is_empty()
+            || segments[0].length == 0.
+            || joined != self.joined
+            || (joined && (unchanged_start < 2 || unchanged_end < 2))
+        {
+            let style = self.style.clone();
+            let (new_mesh, stroke) = Self::new(points, widths, style);
+            mesh.vertices = new_mesh.vertices;
+            mesh.indices = new_mesh.indices;
+            *self = stroke;
+            return (0..mesh.vertices.len(), 0..mesh.indices.len());
+        }
+        self.point_count = points.len();
+        if unchanged_start == kept.len() && unchanged_start == self.kept.len() {
+            return (
+                mesh.vertices.len()..mesh.vertices.len(),
+                mesh.indices.len()..mesh.indices.len(),
+            );
+        }
+
+        // each piece depends on the points from the one before it to the one two after it
+        let first = unchanged_start.saturating_sub(2);
+        let end = (kept.len() - unchanged_end + 1).min(segments.len());
+        let old_end = (self.kept.len() - unchanged_end + 1).min(self.pieces.len());
+        let (vertex_start, index_start) = self.pieces[first];
+        let (old_vertex_end, old_index_end) = self
+            .pieces
+            .get(old_end)
+            .copied()
+            .unwrap_or((mesh.vertices.len(), mesh.indices.len()));
+
+        let mut builder = StrokeBuilder::new(self.style.detail);
+        let style = (self.style.cap, self.style.join);
+        let mut pieces = Vec::with_capacity(end - first);
+        for index in first..end {
+            pieces.push((
+                vertex_start + builder.vertices.len(),
+                index_start + builder.indices.len(),
+            ));
+            builder.piece(&kept, &segments, index, style, joined);
+        }
+        let vertex_end = vertex_start + builder.vertices.len();
+        let index_end = index_start + builder.indices.len();
+        let vertex_delta = vertex_end as isize - old_vertex_end as isize;
+        let index_delta = index_end as isize - old_index_end as isize;
+
+        mesh.vertices
+            .splice(vertex_start..old_vertex_end, builder.vertices);
+        mesh.indices.splice(
+            index_start..old_index_end,
+            builder
+                .indices
+                .into_iter()
+                .map(|index| index + vertex_start as u32),
+        );
+        self.stroke_vertices
+            .splice(vertex_start..old_vertex_end, builder.stroke_vertices);
+        self.pieces.splice(first..old_end, pieces);
+        // the pieces after the rebuilt ones are unchanged, but may have moved
+        if vertex_delta != 0 {
+            for index in mesh.indices[index_end..].iter_mut() {
+                *index = (*index as isize + vertex_delta) as u32;
+            }
+        }
+        if shift != 0. {
+            for stroke_vertex in self.stroke_vertices[vertex_end..].iter_mut() {
+                stroke_vertex.parameter += shift;
+            }
+        }
+        for piece in self.pieces[end..].iter_mut() {
+            piece.0 = (piece.0 as isize + vertex_delta) as usize;
+            piece.1 = (piece.1 as isize + index_delta) as usize;
+        }
+        self.kept = kept;
+
+        if vertex_delta == 0 && index_delta == 0 {
+            (vertex_start..vertex_end, index_start..index_end)
+        } else {
+            (
+                vertex_start..mesh.vertices.len(),
+                index_start..mesh.indices.len(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn zigzag(count: usize) -> Vec<Vector2> {
+        (0..count)
+            .map(|i| Vector2::new(i as f32 * 10., if i % 2 == 0 { 0. } else { 5. }))
+            .collect()
+    }
+
+    fn dashed(points: &[Vector2], dashes: &DashPattern) -> SpriteMesh {
+        let widths = vec![2.; points.len()];
+        let style = StrokeStyle {
+            closed: false,
+            cap: LineCap::Round,
+            join: LineJoin::Miter(4.),
+            dashes: Some(dashes.clone()),
+            detail: MeshDetail::Segments(16),
+        };
+        Stroke::new(points, (&widths, &[]), style).0
+    }
+
+    #[test]
+    fn non_finite_dash_phase_is_zero() {
+        let points = zigzag(5);
+        let pattern = DashPattern::new(&[3., 2.]);
+        let expected = dashed(&points, &pattern);
+        for phase in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let with_phase = pattern.clone().with_phase(phase);
+            assert_eq!(with_phase.phase(), 0.);
+            assert_eq!(dashed(&points, &with_phase).indices, expected.indices);
+        }
+    }
+
+    #[test]
+    fn dash_patterns_without_length_are_solid() {
+        let points = zigzag(5);
+        let solid = dashed(&points, &DashPattern::new(&[f32::INFINITY]));
+        let mesh = dashed(&points, &DashPattern::new(&[0., 0.]));
+        assert_eq!(mesh.indices, solid.indices);
+    }
+
+    #[test]
+    fn dashes_are_bounded() {
+        let points = zigzag(3);
+        let dashes = split_dashes(
+            &Stroke::stroke_points(&points, (&[1.; 3], &[]), false),
+            &DashPattern::new(&[1e-6]),
+        );
+        assert!(dashes.len() <= MAX_DASH_PIECES);
+    }
+
+    fn style(closed: bool, (cap, join): (LineCap, LineJoin)) -> StrokeStyle {
+        StrokeStyle {
+            closed,
+            cap,
+            join,
+            dashes: None,
+            detail: MeshDetail::Segments(16),
+        }
+    }
+
+    fn assert_same_mesh(mesh: &SpriteMesh, stroke: &Stroke, expected: &(SpriteMesh, Stroke)) {
+        let positions = |mesh: &SpriteMesh| -> Vec<Vector2> {
+            mesh.vertices.iter().map(|vertex| vertex.position).collect()
+        };
+        let stroke_vertices = |stroke: &Stroke| -> Vec<(f32, f32, f32)> {
+            stroke
+                .stroke_vertices
+                .iter()
+                .map(|vertex| (vertex.parameter, vertex.extension, vertex.side))
+                .collect()
+        };
+        assert_eq!(positions(mesh), positions(&expected.0));
+        assert_eq!(mesh.indices, expected.0.indices);
+        assert_eq!(stroke_vertices(stroke), stroke_vertices(&expected.1));
+    }
+
+    fn random_point(rng: &mut StdRng) -> Vector2 {
+        Vector2::new(rng.gen_range(-50. ..50.), rng.gen_range(-50. ..50.))
+    }
+
+    #[test]
+    fn update_matches_new() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let styles = [
+            (LineCap::Butt, LineJoin::Miter(4.)),
+            (LineCap::Round, LineJoin::Round),
+            (LineCap::Square, LineJoin::Bevel),
+        ];
+        for closed in [false, true] {
+            for &cap_join in styles.iter() {
+                let mut points: Vec<Vector2> = (0..6).map(|_| random_point(&mut rng)).collect();
+                let mut widths: Vec<f32> = vec![4.; points.len()];
+                let (mut mesh, mut stroke) =
+                    Stroke::new(&points, (&widths, &[]), style(closed, cap_join));
+                for _ in 0..200 {
+                    let index = rng.gen_range(0..points.len());
+                    match rng.gen_range(0..5) {
+                        0 => points[index] = random_point(&mut rng),
+                        1 => {
+                            points.push(random_point(&mut rng));
+                            widths.push(4.);
+                        }
+                        2 => {
+                            points.insert(index, random_point(&mut rng));
+                            widths.insert(index, rng.gen_range(1. ..8.));
+                        }
+                        3 if points.len() > 2 => {
+                            points.remove(index);
+                            widths.remove(index);
+                        }
+                        _ => widths[index] = rng.gen_range(1. ..8.),
+                    }
+                    stroke.update(&mut mesh, &points, (&widths, &[]));
+                    let expected = Stroke::new(&points, (&widths, &[]), style(closed, cap_join));
+                    assert_same_mesh(&mesh, &stroke, &expected);
+                }
+            }
+        }
+    }
+}
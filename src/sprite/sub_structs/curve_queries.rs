@@ -0,0 +1,638 @@
+use crate::*;
+use std::borrow::Cow;
+
+/// A point on a `Curve`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub position: Vector2,
+    /// The index of the segment the point is on, which runs from `points()[segment]` to the
+    /// next point, or back to the first point for the last segment of a loop. Inserting a
+    /// point at `segment + 1` splits the segment there
+    pub segment: usize,
+    /// How far along the segment the point is, from `0.` at its start to `1.` at its end. On
+    /// smooth curves this follows the curve's parameter, so it is not exactly proportional to
+    /// the distance along the segment
+    pub parameter: f32,
+    /// The distance along the curve from its first point
+    pub distance: f32,
+}
+
+/// The straight lines a curve is drawn with
+pub(crate) struct CurvePath<'a> {
+    pub(crate) points: Cow<'a, [Vector2]>,
+    /// How far along the curve's points each of `points` is, where `1.5` is halfway between
+    /// the second and third points
+    pub(crate) parameters: Vec<f32>,
+    /// The distance along the curve to each of `points`
+    pub(crate) distances: Vec<f32>,
+    /// The number of points the curve has
+    pub(crate) point_count: usize,
+    pub(crate) is_loop: bool,
+}
+
+impl<'a> CurvePath<'a> {
+    /// Returns the lines a curve through `points` is drawn with. Smooth curves are drawn
+    /// through `mapped` if it is given, and are mapped again otherwise
+    pub(crate) fn new(
+        points: &'a [Vector2],
+        style: CurveStyle,
+        tolerance: f32,
+        is_loop: bool,
+        mapped: Option<&'a MappedPoints>,
+    ) -> Self {
+        let (mut path_points, mut parameters): (Cow<[Vector2]>, Vec<f32>) = match (style, mapped) {
+            (CurveStyle::PreserveAngles, _) => (
+                Cow::Borrowed(points),
+                (0..points.len()).map(|i| i as f32).collect(),
+            ),
+            (_, Some(mapped)) => (Cow::Borrowed(&mapped.points), mapped.parameters.clone()),
+            (_, None) => {
+                let mapped = MappedPoints::new(points, style, tolerance, is_loop);
+                (Cow::Owned(mapped.points), mapped.parameters)
+            }
+        };
+        if is_loop {
+            let first = path_points[0];
+            path_points.to_mut().push(first);
+            parameters.push(points.len() as f32);
+        }
+
+        let mut distances = Vec::with_capacity(path_points.len());
+        let mut distance = 0.;
+        for (i, point) in path_points.iter().enumerate() {
+            if i > 0 {
+                distance += point.distance_to(&path_points[i - 1]);
+            }
+            distances.push(distance);
+        }
+        CurvePath {
+            points: path_points,
+            parameters,
+            distances,
+            point_count: points.len(),
+            is_loop,
+        }
+    }
+}
+
+impl CurvePath<'_> {
+    pub(crate) fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    /// Returns the number of segments between the curve's points
+    fn segment_count(&self) -> usize {
+        if self.is_loop {
+            self.point_count
+        } else {
+            self.point_count - 1
+        }
+    }
+
+    /// Returns the point `fraction` of the way along the line from `points[index]`
+    fn locate(&self, index: usize, fraction: f32) -> CurvePoint {
+        let (start, end) = (self.points[index], self.points[index + 1]);
+        let (start_parameter, end_parameter) = (self.parameters[index], self.parameters[index + 1]);
+        let parameter = start_parameter + (end_parameter - start_parameter) * fraction;
+        let segment = (parameter.floor() as usize).min(self.segment_count() - 1);
+        CurvePoint {
+            position: start.add(&end.sub(&start).scale(fraction)),
+            segment,
+            parameter: (parameter - segment as f32).clamp(0., 1.),
+            distance: self.distances[index]
+                + (self.distances[index + 1] - self.distances[index]) * fraction,
+        }
+    }
+
+    /// Returns the index of the line `distance` along the path and how far along the line it
+    /// is
+    fn line_at_distance(&self, distance: f32) -> (usize, f32) {
+        let index = self
+            .distances
+            .partition_point(|&start| start <= distance)
+            .saturating_sub(1)
+            .min(self.points.len() - 2);
+        let length = self.distances[index + 1] - self.distances[index];
+        let fraction = if length > 0. {
+            ((distance - self.distances[index]) / length).clamp(0., 1.)
+        } else {
+            0.
+        };
+        (index, fraction)
+    }
+
+    /// Returns the index of the line closest to `point`, how far along the line the closest
+    /// point is and its distance from `point`
+    fn nearest_line(&self, point: &Vector2) -> (usize, f32, f32) {
+        let mut nearest = (0, 0., f32::INFINITY);
+        for (i, line) in self.points.windows(2).enumerate() {
+            let direction = line[1].sub(&line[0]);
+            let length_squared = direction.dot(&direction);
+            let fraction = if length_squared > 0. {
+                (point.sub(&line[0]).dot(&direction) / length_squared).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let distance = point.distance_to(&line[0].add(&direction.scale(fraction)));
+            if distance < nearest.2 {
+                nearest = (i, fraction, distance);
+            }
+        }
+        nearest
+    }
+
+    /// Returns the direction of the line at `index`, or of the nearest line with a length if
+    /// it has none
+    fn direction(&self, index: usize) -> Vector2 {
+        let line = |i: usize| self.points[i + 1].sub(&self.points[i]);
+        (index..self.points.len() - 1)
+            .chain((0..index).rev())
+            .map(line)
+            .find(|direction| direction.magnitude() > 0.)
+            .map_or(Vector2::RIGHT, Vector2::normalize)
+    }
+
+    /// Returns the value at each of `points`, given one value per point of the curve and
+    /// blending between the values on either side. Points without a value use the last one.
+    /// Panics if `values` is empty
+    pub(crate) fn per_point<T: Interpolate>(&self, values: &[T]) -> Vec<T> {
+        let value = |point: usize| {
+            // loops end at their first point again
+            values[(point % self.point_count).min(values.len() - 1)]
+        };
+        self.parameters
+            .iter()
+            .map(|parameter| {
+                let point = parameter.floor() as usize;
+                value(point).interpolate(&value(point + 1), parameter.fract())
+            })
+            .collect()
+    }
+
+    /// Returns the width of the curve at each of `points`, given how wide it is along its
+    /// length and its width where that is constant
+    pub(crate) fn widths(&self, widths: &CurveWidths, width: f32) -> Vec<f32> {
+        let length = self.length();
+        let mut widths: Vec<f32> = match widths {
+            CurveWidths::PerPoint(widths) if !widths.is_empty() => self.per_point(widths),
+            CurveWidths::AlongLength(widths) => self
+                .distances
+                .iter()
+                .map(|distance| widths.sample(if length > 0. { distance / length } else { 0. }))
+                .collect(),
+            _ => vec![width; self.points.len()],
+        };
+        if self.is_loop {
+            // the points back at the start of a loop have its first width
+            let first = widths[0];
+            for (width, &parameter) in widths.iter_mut().zip(self.parameters.iter()) {
+                if parameter >= self.point_count as f32 {
+                    *width = first;
+                }
+            }
+        }
+        for width in widths.iter_mut() {
+            *width = width.max(0.);
+        }
+        widths
+    }
+
+    /// Returns the distances along the path where the width of the curve changes between its
+    /// points, and the width there
+    pub(crate) fn width_splits(&self, widths: &CurveWidths) -> Vec<(f32, f32)> {
+        let length = self.length();
+        match widths {
+            CurveWidths::AlongLength(widths) => widths
+                .keys
+                .iter()
+                .map(|&(t, width)| (t * length, width.max(0.)))
+                .filter(|&(distance, _)| distance > 0. && distance < length)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the width of the curve `distance` along the path
+    fn width_at(&self, widths: &CurveWidths, width: f32, distance: f32) -> f32 {
+        let (index, _) = self.line_at_distance(distance);
+        let splits = self.width_splits(widths);
+        let widths = self.widths(widths, width);
+        // the closest points or splits on either side
+        let mut start = (self.distances[index], widths[index]);
+        let mut end = (self.distances[index + 1], widths[index + 1]);
+        for split in splits {
+            if split.0 <= distance && split.0 > start.0 {
+                start = split;
+            } else if split.0 > distance && split.0 < end.0 {
+                end = split;
+            }
+        }
+        let span = end.0 - start.0;
+        if span > 0. {
+            start.1 + (end.1 - start.1) * ((distance - start.0) / span).clamp(0., 1.)
+        } else {
+            start.1
+        }
+    }
+
+    /// Returns the point on the path closest to `point`, as well as the distance from `point`
+    pub(crate) fn nearest_point(&self, point: &Vector2) -> (CurvePoint, f32) {
+        let (index, fraction, distance) = self.nearest_line(point);
+        (self.locate(index, fraction), distance)
+    }
+
+    /// Returns the point `distance` along the path
+    pub(crate) fn point_at_distance(&self, distance: f32) -> CurvePoint {
+        let (index, fraction) = self.line_at_distance(self.wrap_distance(distance));
+        self.locate(index, fraction)
+    }
+
+    /// Returns the direction of the path `distance` along it, as a unit vector
+    pub(crate) fn tangent_at_distance(&self, distance: f32) -> Vector2 {
+        let (index, _) = self.line_at_distance(self.wrap_distance(distance));
+        self.direction(index)
+    }
+
+    /// Returns how wide the curve is `distance` along the path
+    pub(crate) fn width_at_distance(&self, widths: &CurveWidths, width: f32, distance: f32) -> f32 {
+        self.width_at(widths, width, self.wrap_distance(distance))
+    }
+
+    /// Wraps distances past either end of a loop around it, and clamps them to the ends of
+    /// other curves
+    fn wrap_distance(&self, distance: f32) -> f32 {
+        let length = self.length();
+        if self.is_loop && length > 0. {
+            distance.rem_euclid(length)
+        } else {
+            distance.clamp(0., length)
+        }
+    }
+
+    /// Returns the nearest point on the path if `point` is on the curve, as it is drawn with
+    /// `widths` and `cap`, or within `tolerance` of it
+    pub(crate) fn hit_test(
+        &self,
+        point: &Vector2,
+        tolerance: f32,
+        widths: &CurveWidths,
+        width: f32,
+        cap: LineCap,
+    ) -> Option<CurvePoint> {
+        let (index, fraction, distance) = self.nearest_line(point);
+        let nearest = self.locate(index, fraction);
+        let half_width = self.width_at(widths, width, nearest.distance) / 2.;
+        if distance > half_width + tolerance {
+            return None;
+        }
+        // flat caps only reach past the ends of the curve if they are square
+        let last = self.points.len() - 2;
+        let end = if index == 0 && fraction == 0. {
+            Some((self.points[0], self.direction(0).rotate_180()))
+        } else if index == last && fraction == 1. {
+            Some((self.points[last + 1], self.direction(last)))
+        } else {
+            None
+        };
+        if let (Some((end, outward)), false) = (end, self.is_loop) {
+            let extension = match cap {
+                LineCap::Butt => 0.,
+                LineCap::Square => half_width,
+                LineCap::Round => f32::INFINITY,
+            };
+            if point.sub(&end).dot(&outward) > extension + tolerance {
+                return None;
+            }
+        }
+        Some(nearest)
+    }
+}
+
+impl Curve {
+    /// Returns the straight lines this curve is drawn with. Smooth curves that have changed
+    /// since they were last drawn are mapped again
+    pub(crate) fn path(&self) -> CurvePath<'_> {
+        let mapped = !self.updated && self.unchanged.is_none();
+        CurvePath::new(
+            &self.points,
+            self.style,
+            self.tolerance,
+            self.is_loop,
+            Some(&self.mapped).filter(|_| mapped),
+        )
+    }
+
+    /// Returns the length of this curve, as it is drawn
+    pub fn length(&self) -> f32 {
+        self.path().length()
+    }
+
+    /// Returns the point on this curve closest to `point`, as well as the distance from
+    /// `point`
+    pub fn nearest_point(&self, point: &Vector2) -> (CurvePoint, f32) {
+        self.path().nearest_point(point)
+    }
+
+    /// Returns the point `distance` along this curve. Distances past either end of a loop
+    /// wrap around it, and are clamped to the ends of other curves
+    pub fn point_at_distance(&self, distance: f32) -> CurvePoint {
+        self.path().point_at_distance(distance)
+    }
+
+    /// Returns the direction this curve is heading in `distance` along it, as a unit vector
+    pub fn tangent_at_distance(&self, distance: f32) -> Vector2 {
+        self.path().tangent_at_distance(distance)
+    }
+
+    /// Returns how wide this curve is `distance` along it
+    pub fn width_at_distance(&self, distance: f32) -> f32 {
+        self.path()
+            .width_at_distance(&self.widths, self.width, distance)
+    }
+
+    /// Returns the nearest point on this curve if `point` is on it, as it is drawn with its
+    /// width and caps, or within `tolerance` of it. The gaps between dashes count as part of
+    /// the curve
+    pub fn hit_test(&self, point: &Vector2, tolerance: f32) -> Option<CurvePoint> {
+        self.path()
+            .hit_test(point, tolerance, &self.widths, self.width, self.cap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+    }
+
+    fn assert_point(point: &CurvePoint, position: Vector2, segment: usize, parameter: f32) {
+        assert!(
+            point.position.distance_to(&position) < EPSILON,
+            "{:?} != {:?}",
+            point.position,
+            position
+        );
+        assert_eq!(point.segment, segment);
+        assert_close(point.parameter, parameter);
+    }
+
+    fn corner() -> Vec<Vector2> {
+        vec![Vector2::ZERO, Vector2::new(10., 0.), Vector2::new(10., 10.)]
+    }
+
+    fn square() -> Vec<Vector2> {
+        vec![
+            Vector2::ZERO,
+            Vector2::new(10., 0.),
+            Vector2::new(10., 10.),
+            Vector2::new(0., 10.),
+        ]
+    }
+
+    fn path(points: &[Vector2], is_loop: bool) -> CurvePath<'_> {
+        CurvePath::new(points, CurveStyle::PreserveAngles, 0.25, is_loop, None)
+    }
+
+    #[test]
+    fn length() {
+        assert_close(path(&corner(), false).length(), 20.);
+        assert_close(path(&square(), false).length(), 30.);
+        assert_close(path(&square(), true).length(), 40.);
+    }
+
+    #[test]
+    fn points_at_distance() {
+        let points = corner();
+        let open = path(&points, false);
+        assert_point(&open.point_at_distance(5.), Vector2::new(5., 0.), 0, 0.5);
+        assert_point(&open.point_at_distance(15.), Vector2::new(10., 5.), 1, 0.5);
+        assert_close(open.point_at_distance(15.).distance, 15.);
+        // distances past the ends are clamped
+        assert_point(&open.point_at_distance(-3.), Vector2::ZERO, 0, 0.);
+        assert_point(&open.point_at_distance(25.), Vector2::new(10., 10.), 1, 1.);
+        assert_close(open.point_at_distance(25.).distance, 20.);
+    }
+
+    #[test]
+    fn loop_distances_wrap_around() {
+        let points = square();
+        let closed = path(&points, true);
+        assert_point(&closed.point_at_distance(45.), Vector2::new(5., 0.), 0, 0.5);
+        assert_point(&closed.point_at_distance(-5.), Vector2::new(0., 5.), 3, 0.5);
+        assert_close(closed.point_at_distance(-5.).distance, 35.);
+        assert_point(&closed.point_at_distance(80.), Vector2::ZERO, 0, 0.);
+        assert_eq!(closed.tangent_at_distance(35.), Vector2::new(0., -1.));
+        assert_eq!(closed.tangent_at_distance(-35.), Vector2::new(1., 0.));
+    }
+
+    #[test]
+    fn tangents() {
+        let points = corner();
+        let open = path(&points, false);
+        assert_eq!(open.tangent_at_distance(5.), Vector2::new(1., 0.));
+        assert_eq!(open.tangent_at_distance(15.), Vector2::new(0., 1.));
+        assert_eq!(open.tangent_at_distance(100.), Vector2::new(0., 1.));
+
+        // lines without a length use the direction of the nearest line with one
+        let points = [Vector2::ZERO, Vector2::ZERO, Vector2::new(0., 5.)];
+        assert_eq!(
+            path(&points, false).tangent_at_distance(0.),
+            Vector2::new(0., 1.)
+        );
+    }
+
+    #[test]
+    fn nearest_points() {
+        let points = corner();
+        let open = path(&points, false);
+        let (nearest, distance) = open.nearest_point(&Vector2::new(4., 3.));
+        assert_point(&nearest, Vector2::new(4., 0.), 0, 0.4);
+        assert_close(distance, 3.);
+        let (nearest, distance) = open.nearest_point(&Vector2::new(12., 7.));
+        assert_point(&nearest, Vector2::new(10., 7.), 1, 0.7);
+        assert_close(nearest.distance, 17.);
+        assert_close(distance, 2.);
+        // past the end of an open curve
+        let (nearest, distance) = open.nearest_point(&Vector2::new(10., 13.));
+        assert_point(&nearest, Vector2::new(10., 10.), 1, 1.);
+        assert_close(distance, 3.);
+
+        // the last segment of a loop runs back to its first point
+        let points = square();
+        let closed = path(&points, true);
+        let (nearest, distance) = closed.nearest_point(&Vector2::new(-1., 5.));
+        assert_point(&nearest, Vector2::new(0., 5.), 3, 0.5);
+        assert_close(distance, 1.);
+    }
+
+    #[test]
+    fn smooth_curve_segments_follow_points() {
+        let points = vec![
+            Vector2::ZERO,
+            Vector2::new(10., 5.),
+            Vector2::new(20., -5.),
+            Vector2::new(30., 0.),
+        ];
+        for is_loop in [false, true] {
+            let path = CurvePath::new(&points, CurveStyle::CatmullRom, 0.01, is_loop, None);
+            assert!(path.length() > 30.);
+            assert_point(&path.point_at_distance(0.), points[0], 0, 0.);
+            for (i, point) in points.iter().enumerate().skip(1) {
+                let (nearest, distance) = path.nearest_point(point);
+                assert_close(distance, 0.);
+                // either the end of the segment before the point or the start of the next
+                assert_close(nearest.segment as f32 + nearest.parameter, i as f32);
+            }
+            let (nearest, _) = path.nearest_point(&Vector2::new(15., 5.));
+            assert_eq!(nearest.segment, 1);
+            assert!(nearest.parameter > 0. && nearest.parameter < 1.);
+        }
+        let open = CurvePath::new(&points, CurveStyle::CatmullRom, 0.01, false, None);
+        assert_point(&open.point_at_distance(1000.), points[3], 2, 1.);
+    }
+
+    fn hits(cap: LineCap, point: Vector2, tolerance: f32) -> bool {
+        let points = [Vector2::ZERO, Vector2::new(10., 0.)];
+        path(&points, false)
+            .hit_test(&point, tolerance, &CurveWidths::Constant, 4., cap)
+            .is_some()
+    }
+
+    #[test]
+    fn hit_test_along_the_curve() {
+        for cap in [LineCap::Butt, LineCap::Square, LineCap::Round] {
+            assert!(hits(cap, Vector2::new(5., 1.9), 0.));
+            assert!(!hits(cap, Vector2::new(5., 2.1), 0.));
+            assert!(hits(cap, Vector2::new(5., 2.1), 0.2));
+            assert!(hits(cap, Vector2::new(0., 1.), 0.));
+            assert!(hits(cap, Vector2::new(10., -1.), 0.));
+        }
+    }
+
+    #[test]
+    fn hit_test_butt_caps() {
+        assert!(!hits(LineCap::Butt, Vector2::new(-0.5, 0.), 0.));
+        assert!(!hits(LineCap::Butt, Vector2::new(10.5, 1.), 0.));
+        assert!(hits(LineCap::Butt, Vector2::new(-0.5, 0.), 1.));
+    }
+
+    #[test]
+    fn hit_test_square_caps() {
+        assert!(hits(LineCap::Square, Vector2::new(-1.5, 0.), 0.));
+        assert!(hits(LineCap::Square, Vector2::new(11., 1.), 0.));
+        assert!(!hits(LineCap::Square, Vector2::new(-2.5, 0.), 0.));
+        assert!(!hits(LineCap::Square, Vector2::new(12.5, 0.), 0.));
+    }
+
+    #[test]
+    fn hit_test_round_caps() {
+        assert!(hits(LineCap::Round, Vector2::new(-1.5, 0.), 0.));
+        assert!(hits(LineCap::Round, Vector2::new(11., 1.), 0.));
+        // past the end, but outside the semicircle
+        assert!(!hits(LineCap::Round, Vector2::new(-1.5, 1.5), 0.));
+        assert!(!hits(LineCap::Round, Vector2::new(-2.5, 0.), 0.));
+    }
+
+    #[test]
+    fn hit_test_loops_have_no_caps() {
+        let points = square();
+        let closed = path(&points, true);
+        let hit = |point: Vector2| {
+            closed
+                .hit_test(&point, 0., &CurveWidths::Constant, 4., LineCap::Butt)
+                .is_some()
+        };
+        assert!(hit(Vector2::new(-1., -1.)));
+        assert!(hit(Vector2::new(0., 5.)));
+        assert!(!hit(Vector2::new(5., 5.)));
+    }
+
+    fn width_at(path: &CurvePath, widths: &CurveWidths, distance: f32) -> f32 {
+        path.width_at_distance(widths, 1., distance)
+    }
+
+    #[test]
+    fn per_point_widths() {
+        let points = corner();
+        let open = path(&points, false);
+        let widths = CurveWidths::PerPoint(vec![2., 4., 8.]);
+        assert_close(width_at(&open, &widths, 0.), 2.);
+        assert_close(width_at(&open, &widths, 5.), 3.);
+        assert_close(width_at(&open, &widths, 10.), 4.);
+        assert_close(width_at(&open, &widths, 15.), 6.);
+        assert_close(width_at(&open, &widths, 25.), 8.);
+        // points without a width use the last one, and widths can't be negative
+        let widths = CurveWidths::PerPoint(vec![-2., 4.]);
+        assert_close(width_at(&open, &widths, 0.), 0.);
+        assert_close(width_at(&open, &widths, 15.), 4.);
+        // no widths at all use the curve's width
+        assert_close(width_at(&open, &CurveWidths::PerPoint(vec![]), 15.), 1.);
+        assert_close(width_at(&open, &CurveWidths::Constant, 15.), 1.);
+    }
+
+    #[test]
+    fn per_point_widths_on_a_loop() {
+        let points = square();
+        let closed = path(&points, true);
+        let widths = CurveWidths::PerPoint(vec![2., 4., 6., 8.]);
+        assert_close(width_at(&closed, &widths, 25.), 7.);
+        // the last segment blends back to the first point's width
+        assert_close(width_at(&closed, &widths, 35.), 5.);
+        assert_close(width_at(&closed, &widths, -5.), 5.);
+        assert_close(width_at(&closed, &widths, 40.), 2.);
+        assert_close(width_at(&closed, &widths, 45.), 3.);
+    }
+
+    #[test]
+    fn widths_along_length() {
+        let points = corner();
+        let open = path(&points, false);
+        let linear = CurveWidths::AlongLength(LifetimeCurve::linear(2., 10.));
+        assert_close(width_at(&open, &linear, 5.), 4.);
+        assert_close(width_at(&open, &linear, 10.), 6.);
+        assert_close(width_at(&open, &linear, 20.), 10.);
+        assert_close(width_at(&open, &linear, 30.), 10.);
+
+        // keys between the curve's points split the lines they are on
+        let peak =
+            CurveWidths::AlongLength(LifetimeCurve::new(vec![(0., 2.), (0.25, 10.), (1., 2.)]));
+        assert_close(width_at(&open, &peak, 2.5), 6.);
+        assert_close(width_at(&open, &peak, 5.), 10.);
+        assert_close(width_at(&open, &peak, 10.), 10. - 8. / 3.);
+        assert_close(width_at(&open, &peak, 20.), 2.);
+        assert_eq!(open.width_splits(&peak), vec![(5., 10.)]);
+    }
+
+    #[test]
+    fn widths_along_length_on_a_loop() {
+        let points = square();
+        let closed = path(&points, true);
+        let linear = CurveWidths::AlongLength(LifetimeCurve::linear(2., 10.));
+        assert_close(width_at(&closed, &linear, 20.), 6.);
+        // loops blend back to their width at the first point
+        assert_close(width_at(&closed, &linear, 30.), 8.);
+        assert_close(width_at(&closed, &linear, 35.), 5.);
+        assert_close(width_at(&closed, &linear, 45.), 3.);
+    }
+
+    #[test]
+    fn per_point_values_on_smooth_curves() {
+        let points = square();
+        let values = [1., 2., 3., 4.];
+        for is_loop in [false, true] {
+            let path = CurvePath::new(&points, CurveStyle::CatmullRom, 0.1, is_loop, None);
+            let interpolated = path.per_point(&values);
+            assert_eq!(interpolated.len(), path.points.len());
+            for (value, parameter) in interpolated.iter().zip(path.parameters.iter()) {
+                if parameter.fract() == 0. {
+                    let point = *parameter as usize % points.len();
+                    assert_close(*value, values[point]);
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use std::ops::Range;
 use std::slice::{Iter, IterMut};
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -7,45 +8,213 @@ pub enum CurveStyle {
     PreserveAngles,
     /// angles not preserved, but no spikes
     DoubleJointed,
-    /// unimplemented
+    /// A smooth curve of cubic Bezier segments through every point. Each point's handles
+    /// lie along the line between its neighbours, scaled by the tension, from `0.` for
+    /// straight lines to `1.` for very round curves. Segments are always cubic, since
+    /// quadratic segments can't pass through every point without kinks.
     Bezier(f32),
+    /// A smooth centripetal Catmull-Rom spline through every point, which doesn't overshoot
+    /// when points are unevenly spaced
+    CatmullRom,
+}
+
+/// The shape drawn at either end of a curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    /// The curve ends flat, exactly at its first and last points
+    Butt,
+    /// The curve ends flat, extended past its first and last points by half its width
+    Square,
+    /// The curve ends with a semicircle around its first and last points
+    Round,
+}
+
+/// The shape drawn on the outside of each corner of a curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// The edges are extended until they meet in a point, unless the point is further than
+    /// the limit times the curve's width from the inside corner, in which case the corner is
+    /// beveled. SVG's default limit is `4.`
+    Miter(f32),
+    /// The corner is cut off flat
+    Bevel,
+    /// The corner is rounded, with half the curve's width as the radius
+    Round,
+}
+
+/// A repeating pattern of dashes and gaps along a curve, measured along its length
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashPattern {
+    pub(crate) lengths: Vec<f32>,
+    pub(crate) phase: f32,
+}
+
+impl DashPattern {
+    /// Creates a pattern from alternating dash and gap lengths, starting with a dash. An odd
+    /// number of lengths is repeated to make it even, as in SVG. Dashes with no length are
+    /// drawn as dots by `Round` and `Square` caps. Panics if `lengths` is empty or any length
+    /// is negative
+    pub fn new(lengths: &[f32]) -> Self {
+        if lengths.is_empty() {
+            panic!("Cannot create a dash pattern without any lengths");
+        }
+        if lengths.iter().any(|length| length.is_nan() || *length < 0.) {
+            panic!("Dash lengths must not be negative");
+        }
+        let mut lengths = lengths.to_vec();
+        if lengths.len() % 2 == 1 {
+            lengths.extend_from_within(..);
+        }
+        Self { lengths, phase: 0. }
+    }
+
+    /// Returns this pattern with its phase set, the same as `set_phase`
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.set_phase(phase);
+        self
+    }
+
+    /// Sets how far into the pattern the curve starts. Increasing the phase over time moves
+    /// the dashes towards the start of the curve. A phase that isn't finite is treated as `0.`
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = if phase.is_finite() { phase } else { 0. };
+    }
+
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Returns the alternating dash and gap lengths
+    pub fn lengths(&self) -> &[f32] {
+        &self.lengths
+    }
+
+    /// Returns the length of one repetition of the pattern
+    pub fn period(&self) -> f32 {
+        self.lengths.iter().sum()
+    }
+}
+
+/// How the vertices of a `Curve` are colored. Vertex colors are multiplied with the curve's
+/// texture.
+#[derive(Debug, Clone)]
+pub enum CurveColors {
+    /// Every vertex is white, so the texture is drawn unchanged
+    None,
+    /// One color per point, blended between points. Points without a color use the last
+    /// color
+    PerPoint(Vec<Color>),
+    /// A gradient along the curve, from an offset of `0.` at the first point to `1.` at the
+    /// last
+    AlongLength(Gradient),
+}
+
+/// How wide a `Curve` is along its length
+#[derive(Debug, Clone)]
+pub enum CurveWidths {
+    /// The curve is the same width everywhere
+    Constant,
+    /// One width per point, blended between points. Points without a width use the last
+    /// width
+    PerPoint(Vec<f32>),
+    /// A width along the curve, from a `t` of `0.` at the first point to `1.` at the last.
+    /// Loops blend back to their width at the first point
+    AlongLength(LifetimeCurve<f32>),
 }
 
 /// A `Sprite` wrapper for drawing curves and paths.
 ///
 /// You should probably use this if you want to draw curves, instead of
-/// making a sprite with `SpriteMesh::new_line()`, as this efficiently
+/// making a sprite from a `SpriteMesh`, as this efficiently
 /// modifies the mesh just as much as it needs to instead of building a new
 /// one each time.
 ///
-/// NOTE; The above is a lie. I am yet to implement this so-called "efficiency".
+/// Moving, adding or removing a few points only rebuilds the parts of the mesh around them,
+/// and only those parts are written to the GPU when the curve is drawn. Changing any other
+/// setting, using `iter_mut_points` or `points_mut`, drawing a dashed curve, changing the
+/// length of a curve with `CurveWidths::AlongLength`, or changing the points next to where a
+/// loop's ends meet rebuilds the whole mesh.
 #[derive(Debug)]
 pub struct Curve {
     pub(crate) sprite: Sprite,
     pub(crate) width: f32,
     pub(crate) points: Vec<Vector2>,
-    pub(crate) mapped_points: Vec<Vector2>,
+    /// The points smooth curves are drawn through
+    pub(crate) mapped: MappedPoints,
     pub(crate) style: CurveStyle,
+    /// The largest distance between a smooth curve and the lines it is drawn with
+    pub(crate) tolerance: f32,
+    pub(crate) cap: LineCap,
+    pub(crate) join: LineJoin,
+    pub(crate) dashes: Option<DashPattern>,
+    /// How the mesh was built from the drawn points
+    pub(crate) stroke: Stroke,
+    /// Whether the whole mesh needs to be rebuilt
     pub(crate) updated: bool,
+    /// The number of points at the start and end that haven't changed since the mesh was
+    /// built, or `None` if none have changed
+    pub(crate) unchanged: Option<(usize, usize)>,
+    /// The number of points the mesh was built from
+    pub(crate) built_point_count: usize,
+    /// The ranges of vertices and indices that have changed since they were written to
+    /// `buffers`
+    pub(crate) pending: Option<(Range<usize>, Range<usize>)>,
+    pub(crate) buffers: Option<MeshBuffers>,
+    pub(crate) freehand: Option<FreehandInput>,
     pub(crate) is_loop: bool,
+    pub(crate) colors: CurveColors,
+    pub(crate) widths: CurveWidths,
+}
+
+/// The points a curve is drawn through, mapped from its points according to its style
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MappedPoints {
+    pub(crate) points: Vec<Vector2>,
+    /// How far along the curve's points each of `points` is, where `1.5` is halfway between
+    /// the second and third points
+    pub(crate) parameters: Vec<f32>,
+    /// The index in `points` of the first point drawn for each segment between the curve's
+    /// points
+    pub(crate) segment_starts: Vec<usize>,
+}
+
+/// Input that has been added to a curve with `Curve::append_simplified`
+#[derive(Debug)]
+pub(crate) struct FreehandInput {
+    /// The input since the last point of the curve that won't change, starting with that
+    /// point
+    samples: Vec<Vector2>,
+    /// The number of points at the end of the curve that may still change
+    unsettled: usize,
+    /// The number of points the curve had after the input was added, so that other changes
+    /// to the curve are noticed
+    point_count: usize,
 }
 
 pub struct CurveConfiguration<'a> {
     /// A label used for debugging
     pub label: Option<&'a str>,
-    /// The width of this curve
+    /// The width of this curve, unless it is changed along its length with
+    /// `Curve::set_widths`
     pub width: f32,
     /// The points that are used to generate this curve (must be at least 2 in length)
     pub points: Vec<Vector2>,
     /// The way the curve should be drawn based on the provided points
     pub style: CurveStyle,
+    /// The shape drawn at either end of the curve
+    pub cap: LineCap,
+    /// The shape drawn at each corner of the curve
+    pub join: LineJoin,
+    /// The pattern of dashes the curve is drawn with, or `None` for a solid curve
+    pub dashes: Option<DashPattern>,
     /// The texture this curve should use
     pub default_texture: Texture,
     /// Same as the z_index attribute on `SpriteConfiguration`
     pub z_index: ZIndex,
     /// Same as the `opacity` attribute on `SpriteConfiguration`
     pub opacity: f32,
-    /// Determines whether this sprite will loop around and connect to itself
+    /// Determines whether this curve continues from its last point back to its first, with a
+    /// join where they meet instead of caps
     pub is_loop: bool,
     /// Same as the `texture_projection_method` attribute on `SpriteConfiguration`
     pub texture_projection_method: TextureProjectionMethod,
@@ -58,6 +227,9 @@ impl CurveConfiguration<'_> {
             width: 10.,
             points: vec![Vector2::ZERO, Vector2::ZERO],
             style: CurveStyle::PreserveAngles,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter(4.),
+            dashes: None,
             default_texture: Texture::default(oge)?,
             z_index: ZIndex::default(),
             opacity: 1.,
@@ -69,29 +241,62 @@ impl CurveConfiguration<'_> {
 
 
 impl Curve {
+    const DEFAULT_TOLERANCE: f32 = 0.25;
+    /// The most input kept by `append_simplified` while it stays close to a straight line
+    const MAX_FREEHAND_SAMPLES: usize = 256;
+
     // Creates a new curve from the given points
     pub fn new(config: CurveConfiguration) -> Result<Self> {
         if config.points.len() < 2 {
             panic!("Curve must have at least 2 points");
         }
-        let mapped_points = Self::map_points(&config.points, config.style);
+        let mapped = MappedPoints::new(
+            &config.points,
+            config.style,
+            Self::DEFAULT_TOLERANCE,
+            config.is_loop,
+        );
+        let (mesh, stroke) = Stroke::new(
+            &config.points,
+            (&vec![config.width; config.points.len()], &[]),
+            StrokeStyle {
+                closed: config.is_loop,
+                cap: config.cap,
+                join: config.join,
+                dashes: config.dashes.clone(),
+                detail: MeshDetail::Tolerance(Self::DEFAULT_TOLERANCE),
+            },
+        );
         Ok(Curve {
             sprite: Sprite::new(
                 SpriteConfiguration {
                     label: config.label,
-                    mesh: SpriteMesh::new_line(config.width, &config.points),
+                    mesh,
                     default_texture: config.default_texture,
                     z_index: config.z_index,
                     opacity: config.opacity,
                     texture_projection_method: config.texture_projection_method,
+                    normal_map: None,
                 },
             )?,
+            built_point_count: config.points.len(),
             points: config.points,
-            mapped_points,
+            mapped,
             width: config.width,
             style: config.style,
+            tolerance: Self::DEFAULT_TOLERANCE,
+            cap: config.cap,
+            join: config.join,
+            dashes: config.dashes,
+            stroke,
             updated: true,
+            unchanged: None,
+            pending: None,
+            buffers: None,
+            freehand: None,
             is_loop: config.is_loop,
+            colors: CurveColors::None,
+            widths: CurveWidths::Constant,
         })
     }
 
@@ -110,23 +315,88 @@ impl Curve {
         (index, distance)
     }
 
+    /// Records that the points after the first `start` and before the last `end` may have
+    /// changed
+    fn mark_changed(&mut self, start: usize, end: usize) {
+        self.unchanged = Some(match self.unchanged {
+            Some((unchanged_start, unchanged_end)) => {
+                (unchanged_start.min(start), unchanged_end.min(end))
+            }
+            None => (start, end),
+        });
+    }
+
     /// extend this line, by adding a point to the end
     pub fn push(&mut self, point: Vector2) {
-        self.updated = true;
         self.points.push(point);
+        self.mark_changed(self.points.len() - 1, 0);
+    }
+
+    /// Adds freehand input, such as from `Oge::get_real_cursor_positions`, to the end of this
+    /// curve, simplified with `Polyline::simplify`. The input since the last few points that
+    /// were added is simplified again each time, so the end of the curve may change as more
+    /// input is added. Adding or removing points in any other way starts again from the last
+    /// point
+    pub fn append_simplified(&mut self, input: &[Vector2], tolerance: f32) {
+        if input.is_empty() {
+            return;
+        }
+        let mut freehand = match self.freehand.take() {
+            Some(freehand)
+                if freehand.point_count == self.points.len()
+                    && freehand.samples.last() == self.points.last() =>
+            {
+                freehand
+            }
+            _ => FreehandInput {
+                samples: vec![*self.points.last().unwrap()],
+                unsettled: 0,
+                point_count: self.points.len(),
+            },
+        };
+        freehand.samples.extend_from_slice(input);
+        let indices = polyline::simplified_indices(&freehand.samples, tolerance);
+        let settled = self.points.len() - freehand.unsettled;
+        self.points.truncate(settled);
+        self.points
+            .extend(indices[1..].iter().map(|&i| freehand.samples[i]));
+        self.mark_changed(settled, 0);
+
+        // the last two points may still move as more input is added, but the input before
+        // the ones that won't is no longer needed
+        let last_settled = if indices.len() >= 4 {
+            Some(indices.len() - 3)
+        } else if freehand.samples.len() > Self::MAX_FREEHAND_SAMPLES {
+            Some(indices.len() - 1)
+        } else {
+            None
+        };
+        freehand.unsettled = match last_settled {
+            Some(last_settled) => {
+                freehand.samples.drain(..indices[last_settled]);
+                indices.len() - 1 - last_settled
+            }
+            None => indices.len() - 1,
+        };
+        freehand.point_count = self.points.len();
+        self.freehand = Some(freehand);
     }
 
     /// Inserts a point at the given index
     pub fn insert(&mut self, index: usize, point: Vector2) {
-        self.updated = true;
         self.points.insert(index, point);
+        self.mark_changed(index, self.points.len() - 1 - index);
     }
 
     /// Removes the point at the specified index and returns it. Panics if the line becomes
     /// less than two points long.
     pub fn remove(&mut self, index: usize) -> Vector2 {
-        self.updated = true;
-        self.points.remove(index)
+        if self.points.len() <= 2 {
+            panic!("Curve must have at least 2 points");
+        }
+        let point = self.points.remove(index);
+        self.mark_changed(index, self.points.len() - index);
+        point
     }
 
     /// get a reference to the point at the specified index
@@ -136,7 +406,7 @@ impl Curve {
 
     /// gets a mutable reference to the point at the specified index
     pub fn get_mut(&mut self, index: usize) -> &mut Vector2 {
-        self.updated = true;
+        self.mark_changed(index, self.points.len().saturating_sub(index + 1));
         &mut self.points[index]
     }
 
@@ -158,61 +428,578 @@ impl Curve {
 
     /// Returns a mutable reference to the points that make up this line
     pub fn points_mut(&mut self) -> &mut Vec<Vector2> {
+        self.updated = true;
         &mut self.points
     }
 
+    /// Sets whether this curve continues from its last point back to its first, with a join
+    /// where they meet instead of caps
+    pub fn set_loop(&mut self, is_loop: bool) {
+        self.updated = true;
+        self.is_loop = is_loop;
+    }
+
+    /// Returns whether this curve continues from its last point back to its first
+    pub fn is_loop(&self) -> bool {
+        self.is_loop
+    }
+
+    /// Sets how the vertices of this curve are colored
+    pub fn set_colors(&mut self, colors: CurveColors) {
+        self.updated = true;
+        self.colors = colors;
+    }
+
+    /// Returns how the vertices of this curve are colored
+    pub fn colors(&self) -> &CurveColors {
+        &self.colors
+    }
+
+    /// Sets how wide this curve is along its length. `Constant` uses the curve's width
+    pub fn set_widths(&mut self, widths: CurveWidths) {
+        self.updated = true;
+        self.widths = widths;
+    }
+
+    /// Returns how wide this curve is along its length
+    pub fn widths(&self) -> &CurveWidths {
+        &self.widths
+    }
+
+    /// Sets the largest distance, in world units, between a `Bezier` or `CatmullRom` curve,
+    /// or a round cap or join, and the straight lines it is drawn with. Smaller tolerances
+    /// draw smoother curves with more vertices. The default is `0.25`
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        self.updated = true;
+        self.tolerance = tolerance;
+    }
+
+    /// Sets the shape drawn at either end of this curve
+    pub fn set_cap(&mut self, cap: LineCap) {
+        self.updated = true;
+        self.cap = cap;
+    }
+
+    /// Sets the shape drawn at each corner of this curve
+    pub fn set_join(&mut self, join: LineJoin) {
+        self.updated = true;
+        self.join = join;
+    }
+
+    /// Sets the pattern of dashes this curve is drawn with, or `None` to draw it solid
+    pub fn set_dashes(&mut self, dashes: Option<DashPattern>) {
+        self.updated = true;
+        self.dashes = dashes;
+    }
+
+    /// Returns the pattern of dashes this curve is drawn with
+    pub fn dashes(&self) -> Option<&DashPattern> {
+        self.dashes.as_ref()
+    }
+
+    /// Sets the phase of this curve's dash pattern, if it has one. Changing the phase each
+    /// frame makes the dashes march along the curve
+    pub fn set_dash_phase(&mut self, phase: f32) {
+        if let Some(dashes) = &mut self.dashes {
+            self.updated = true;
+            dashes.set_phase(phase);
+        }
+    }
+
     /// Returns a reference to the internal sprite, for drawing.
     ///
     /// Can do a lot of calculations - be careful!
     pub fn get_sprite(&mut self) -> &Sprite {
-        if self.is_loop {
-            self.points.extend([self.points[0], self.points[1]]);
+        if self.points.len() < 2 {
+            panic!("Curve must have at least 2 points");
         }
         if self.updated {
-            self.updated = false;
-            self.sprite.mesh = SpriteMesh::new_line(self.width, {
-                match self.style {
-                    CurveStyle::PreserveAngles => &self.points,
-                    CurveStyle::DoubleJointed => {
-                        self.mapped_points = Self::map_points(&self.points, self.style);
-                        &self.mapped_points
-                    }
-                    CurveStyle::Bezier(_) => unimplemented!(),
-                }
-            });
-        }
-        if self.is_loop {
-            self.points.truncate(self.points.len() - 2);
+            self.rebuild();
+        } else if let Some(unchanged) = self.unchanged.take() {
+            self.update(unchanged);
         }
         &self.sprite
     }
 
-    pub(crate) fn map_points(points: &Vec<Vector2>, style: CurveStyle) -> Vec<Vector2> {
+    /// Builds the whole mesh again
+    fn rebuild(&mut self) {
+        self.updated = false;
+        self.unchanged = None;
+        self.built_point_count = self.points.len();
+        self.mapped = MappedPoints::new(&self.points, self.style, self.tolerance, self.is_loop);
+
+        let (widths, splits) = self.drawn_widths();
+        let (mesh, stroke) = Stroke::new(
+            self.drawn_points(),
+            (&widths, &splits),
+            StrokeStyle {
+                closed: self.is_loop,
+                cap: self.cap,
+                join: self.join,
+                dashes: self.dashes.clone(),
+                detail: MeshDetail::Tolerance(self.tolerance),
+            },
+        );
+        self.sprite.mesh.vertices = mesh.vertices;
+        self.sprite.mesh.indices = mesh.indices;
+        self.stroke = stroke;
+        let vertices = 0..self.sprite.mesh.vertices.len();
+        self.project_texture(vertices.clone());
+        self.color_mesh(vertices.clone());
+        self.pending = Some((vertices, 0..self.sprite.mesh.indices.len()));
+    }
+
+    /// Rebuilds the parts of the mesh around the points that have changed, given the number
+    /// of points at the start and end that haven't
+    fn update(&mut self, (unchanged_start, unchanged_end): (usize, usize)) {
+        let point_count = self.points.len();
+        let old_point_count = self.built_point_count;
+        self.built_point_count = point_count;
+        let unchanged_start = unchanged_start.min(point_count).min(old_point_count);
+        let unchanged_end = unchanged_end
+            .min(point_count - unchanged_start)
+            .min(old_point_count - unchanged_start);
+        if self.style != CurveStyle::PreserveAngles {
+            self.mapped.remap(
+                &self.points,
+                self.style,
+                self.tolerance,
+                self.is_loop,
+                (unchanged_start, unchanged_end),
+                old_point_count,
+            );
+        }
+
+        let (widths, splits) = self.drawn_widths();
+        let drawn_points = match self.style {
+            CurveStyle::PreserveAngles => &self.points,
+            _ => &self.mapped.points,
+        };
+        let (vertices, indices) =
+            self.stroke
+                .update(&mut self.sprite.mesh, drawn_points, (&widths, &splits));
+
+        // some textures and colors depend on the length of the curve before each vertex, or
+        // all of it
+        let vertex_count = self.sprite.mesh.vertices.len();
+        let mut attributes = vertices.clone();
+        match self.sprite.texture_projection_method {
+            TextureProjectionMethod::ScaleToFit | TextureProjectionMethod::StretchAlongLength => {
+                attributes = 0..vertex_count
+            }
+            TextureProjectionMethod::RepeatAlongLength => attributes.end = vertex_count,
+            TextureProjectionMethod::SingleColor => {}
+        }
+        match self.colors {
+            CurveColors::AlongLength(_) => attributes = 0..vertex_count,
+            CurveColors::PerPoint(_) if point_count != old_point_count => {
+                attributes.end = vertex_count
+            }
+            _ => {}
+        }
+        self.project_texture(attributes.clone());
+        self.color_mesh(attributes.clone());
+
+        let vertices = Self::union(vertices, attributes, vertex_count);
+        let index_count = self.sprite.mesh.indices.len();
+        self.pending = Some(match self.pending.take() {
+            Some((pending_vertices, pending_indices)) => (
+                Self::union(pending_vertices, vertices, vertex_count),
+                Self::union(pending_indices, indices, index_count),
+            ),
+            None => (vertices, indices),
+        });
+    }
+
+    /// Returns the smallest range containing both ranges, that ends by `length`
+    fn union(range: Range<usize>, other: Range<usize>, length: usize) -> Range<usize> {
+        let union = if range.is_empty() {
+            other
+        } else if other.is_empty() {
+            range
+        } else {
+            range.start.min(other.start)..range.end.max(other.end)
+        };
+        union.start.min(length)..union.end.min(length)
+    }
+
+    /// Returns the points the curve is drawn through. Loops are drawn back to the first one
+    fn drawn_points(&self) -> &Vec<Vector2> {
+        match self.style {
+            CurveStyle::PreserveAngles => &self.points,
+            _ => &self.mapped.points,
+        }
+    }
+
+    /// Returns the width of the curve at each of the points it is drawn through, and where it
+    /// is split between them
+    fn drawn_widths(&self) -> (Vec<f32>, Vec<(f32, f32)>) {
+        let point_count = self.drawn_points().len();
+        match self.widths {
+            CurveWidths::Constant => (vec![self.width; point_count], vec![]),
+            _ => {
+                let path = self.path();
+                let mut widths = path.widths(&self.widths, self.width);
+                widths.truncate(point_count);
+                (widths, path.width_splits(&self.widths))
+            }
+        }
+    }
+
+    /// Sets the texture coordinates of the `vertices` of the mesh, with U along the curve and
+    /// V across it for the projection methods that follow the curve's length. Other methods
+    /// set the texture coordinates of the whole mesh
+    fn project_texture(&mut self, vertices: Range<usize>) {
+        let distances = self.path().distances;
+        let length = *distances.last().unwrap();
+        let texture_length = match self.sprite.texture_projection_method {
+            TextureProjectionMethod::RepeatAlongLength => {
+                let (width, height) = self.sprite.default_texture.dimensions();
+                self.width * width as f32 / height.max(1) as f32
+            }
+            TextureProjectionMethod::StretchAlongLength => length,
+            _ => {
+                self.sprite.mesh.update_texture_coordinates(
+                    &self.sprite.default_texture,
+                    &self.sprite.texture_projection_method,
+                );
+                return;
+            }
+        };
+        let texture_length = if texture_length > 0. {
+            texture_length
+        } else {
+            1.
+        };
+
+        let last = distances.len() - 1;
+        for (vertex, stroke_vertex) in self.sprite.mesh.vertices[vertices.clone()]
+            .iter_mut()
+            .zip(self.stroke.stroke_vertices[vertices].iter())
+        {
+            let point = (stroke_vertex.parameter.floor() as usize).min(last);
+            let next = (point + 1).min(last);
+            let distance = distances[point]
+                + (distances[next] - distances[point]) * stroke_vertex.parameter.fract()
+                + stroke_vertex.extension;
+            vertex.texture_coordinates =
+                Vector2::new(distance / texture_length, (1. - stroke_vertex.side) / 2.);
+        }
+    }
+
+    /// Colors the `vertices` of the mesh, blending between the colors of the drawn points on
+    /// either side of each one
+    fn color_mesh(&mut self, vertices: Range<usize>) {
+        let colors: Vec<Color> = match &self.colors {
+            CurveColors::None => return,
+            CurveColors::PerPoint(colors) if colors.is_empty() => return,
+            CurveColors::PerPoint(colors) => self.path().per_point(colors),
+            CurveColors::AlongLength(gradient) => {
+                let distances = self.path().distances;
+                let distance = *distances.last().unwrap();
+                let length = if distance > 0. { distance } else { 1. };
+                distances
+                    .into_iter()
+                    .map(|distance| gradient.sample(distance / length))
+                    .collect()
+            }
+        };
+        let last = colors.len() - 1;
+        let stroke_vertices = &self.stroke.stroke_vertices[vertices.clone()];
+        for (vertex, &StrokeVertex { parameter, .. }) in self.sprite.mesh.vertices[vertices]
+            .iter_mut()
+            .zip(stroke_vertices.iter())
+        {
+            let point = (parameter.floor() as usize).min(last);
+            vertex.color = colors[point]
+                .lerp(&colors[(point + 1).min(last)], parameter.fract())
+                .as_f32_array();
+        }
+    }
+}
+
+impl MappedPoints {
+    /// Maps the points a curve is drawn through from its `points`. Loops have a segment from
+    /// the last point back to the first, and end with the first drawn point again.
+    /// `PreserveAngles` curves are drawn through `points` themselves, so nothing is mapped
+    /// for them
+    pub(crate) fn new(
+        points: &[Vector2],
+        style: CurveStyle,
+        tolerance: f32,
+        is_loop: bool,
+    ) -> Self {
+        if style == CurveStyle::PreserveAngles {
+            return Self::default();
+        }
+        let segment_count = if is_loop {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        let (mapped_points, mapped_parameters, counts) =
+            Self::map_segments(points, style, tolerance, 0..segment_count, is_loop);
+        Self {
+            points: [vec![Self::map_start(points, style, is_loop)], mapped_points].concat(),
+            parameters: [vec![0.], mapped_parameters].concat(),
+            segment_starts: Self::segment_starts(1, &counts),
+        }
+    }
+
+    /// Maps the segments around the points that have changed again, keeping the points
+    /// mapped from the rest, given the number of points at the start and end of `points` that
+    /// haven't changed and how many points there were before
+    pub(crate) fn remap(
+        &mut self,
+        points: &[Vector2],
+        style: CurveStyle,
+        tolerance: f32,
+        is_loop: bool,
+        (unchanged_start, unchanged_end): (usize, usize),
+        old_point_count: usize,
+    ) {
+        let point_count = points.len();
+        // the segments of a loop wrap around to its first points
+        if is_loop && (unchanged_start < 2 || unchanged_end < 2) {
+            *self = Self::new(points, style, tolerance, true);
+            return;
+        }
+        let segment_count = |point_count: usize| {
+            if is_loop {
+                point_count
+            } else {
+                point_count - 1
+            }
+        };
+        // each segment depends on the points from the one before it to the one two after it
+        let first = unchanged_start.saturating_sub(2);
+        let end = (point_count - unchanged_end + 1).min(segment_count(point_count));
+        let old_end = (old_point_count - unchanged_end + 1).min(segment_count(old_point_count));
+        let start_index = self.segment_starts[first];
+        let old_end_index = self
+            .segment_starts
+            .get(old_end)
+            .copied()
+            .unwrap_or(self.points.len());
+
+        let (mapped_points, mapped_parameters, counts) =
+            Self::map_segments(points, style, tolerance, first..end, is_loop);
+        let end_index = start_index + mapped_points.len();
+        let delta = end_index as isize - old_end_index as isize;
+        self.points.splice(start_index..old_end_index, mapped_points);
+        self.parameters
+            .splice(start_index..old_end_index, mapped_parameters);
+        self.segment_starts
+            .splice(first..old_end, Self::segment_starts(start_index, &counts));
+        // the points after the mapped ones are unchanged, but may have moved
+        for segment_start in self.segment_starts[end..].iter_mut() {
+            *segment_start = (*segment_start as isize + delta) as usize;
+        }
+        let shift = point_count as f32 - old_point_count as f32;
+        if shift != 0. {
+            for parameter in self.parameters[end_index..].iter_mut() {
+                *parameter += shift;
+            }
+        }
+        self.points[0] = Self::map_start(points, style, is_loop);
+    }
+
+    /// Returns the first point a curve is drawn through
+    fn map_start(points: &[Vector2], style: CurveStyle, is_loop: bool) -> Vector2 {
+        match style {
+            // loops start just after the corner at their first point
+            CurveStyle::DoubleJointed if is_loop => points[1]
+                .sub(&points[0])
+                .with_magnitude(0.01)
+                .add(&points[0]),
+            _ => points[0],
+        }
+    }
+
+    /// Returns the points a curve is drawn through along each of `segments`, where segment
+    /// `i` starts at `points[i]` and ends at the next point, which is the first one for the
+    /// last segment of a loop. The point each segment starts at is not included. Also
+    /// returns how far along `points` each of them is and how many there are for each
+    /// segment
+    fn map_segments(
+        points: &[Vector2],
+        style: CurveStyle,
+        tolerance: f32,
+        segments: Range<usize>,
+        is_loop: bool,
+    ) -> (Vec<Vector2>, Vec<f32>, Vec<usize>) {
+        let count = points.len();
+        let mut mapped_points = Vec::<Vector2>::with_capacity(segments.len() * 2);
+        let mut mapped_parameters = Vec::<f32>::with_capacity(segments.len() * 2);
+        let mut counts = Vec::with_capacity(segments.len());
         match style {
-            CurveStyle::PreserveAngles => vec![],
+            CurveStyle::PreserveAngles => {
+                for i in segments {
+                    mapped_points.push(points[(i + 1) % count]);
+                    mapped_parameters.push((i + 1) as f32);
+                    counts.push(1);
+                }
+            }
             CurveStyle::DoubleJointed => {
-                let mut mapped_points = Vec::<Vector2>::with_capacity(points.len() * 2);
-                mapped_points.push(points[0]);
-                for i in 1..points.len() - 1 {
-                    let prev_point = unsafe { points.get_unchecked(i - 1) };
-                    let point = unsafe { points.get_unchecked(i) };
-                    let next_point = unsafe { points.get_unchecked(i + 1) };
-                    mapped_points.extend([
-                        prev_point.sub(point).with_magnitude(0.01).add(point),
-                        next_point.sub(point).with_magnitude(0.01).add(point),
-                    ]);
+                // each segment ends just before the corner at its end, and the next one starts
+                // just after it
+                for i in segments {
+                    let point = &points[(i + 1) % count];
+                    let next_point = match i + 2 {
+                        next if next < count => Some(&points[next]),
+                        next if is_loop => Some(&points[next % count]),
+                        _ => None,
+                    };
+                    match next_point {
+                        Some(next_point) => {
+                            let prev_point = &points[i];
+                            mapped_points.extend([
+                                prev_point.sub(point).with_magnitude(0.01).add(point),
+                                next_point.sub(point).with_magnitude(0.01).add(point),
+                            ]);
+                            mapped_parameters.extend([(i + 1) as f32; 2]);
+                            counts.push(2);
+                        }
+                        None => {
+                            mapped_points.push(*point);
+                            mapped_parameters.push((i + 1) as f32);
+                            counts.push(1);
+                        }
+                    }
+                }
+            }
+            CurveStyle::Bezier(_) | CurveStyle::CatmullRom => {
+                let cubics = match style {
+                    CurveStyle::Bezier(tension) => {
+                        spline::cardinal_segments(points, tension, segments.clone(), is_loop)
+                    }
+                    _ => spline::catmull_rom_segments(points, segments.clone(), is_loop),
+                };
+                for (i, cubic) in segments.zip(cubics.iter()) {
+                    let count = mapped_points.len();
+                    spline::flatten_cubic(cubic, tolerance, &mut |point, t| {
+                        mapped_points.push(point);
+                        mapped_parameters.push(i as f32 + t);
+                    });
+                    counts.push(mapped_points.len() - count);
                 }
-                mapped_points.push(*points.last().unwrap());
-                mapped_points
             }
-            CurveStyle::Bezier(_) => unimplemented!(),
         }
+        (mapped_points, mapped_parameters, counts)
+    }
+
+    /// Returns where each segment's points start in `points`, given where the first
+    /// one starts and how many points each has
+    fn segment_starts(first: usize, counts: &[usize]) -> Vec<usize> {
+        let mut start = first;
+        counts
+            .iter()
+            .map(|count| {
+                start += count;
+                start - count
+            })
+            .collect()
     }
 }
 
 impl IntoRenderBundle for &mut Curve {
     fn get_render_bundle(self, oge: &Oge) -> RenderBundle {
-        self.get_sprite().get_render_bundle(oge)
+        self.get_sprite();
+        let mesh = &self.sprite.mesh;
+        match (&mut self.buffers, self.pending.take()) {
+            (Some(buffers), Some((vertices, indices))) => {
+                buffers.write(oge.render_state, mesh, vertices, indices)
+            }
+            (Some(_), None) => {}
+            (None, _) => {
+                let mut buffers = MeshBuffers::new(&oge.render_state.device_wrapper, mesh);
+                buffers.write(
+                    oge.render_state,
+                    mesh,
+                    0..mesh.vertices.len(),
+                    0..mesh.indices.len(),
+                );
+                self.buffers = Some(buffers);
+            }
+        }
+        self.buffers
+            .as_ref()
+            .unwrap()
+            .render_bundle(oge, &self.sprite)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const EPSILON: f32 = 1e-4;
+
+    fn random_point(rng: &mut StdRng) -> Vector2 {
+        Vector2::new(rng.gen_range(-50. ..50.), rng.gen_range(-50. ..50.))
+    }
+
+    fn assert_same_mapping(mapped: &MappedPoints, expected: &MappedPoints) {
+        assert_eq!(mapped.segment_starts, expected.segment_starts);
+        assert_eq!(mapped.points.len(), expected.points.len());
+        for (point, expected) in mapped.points.iter().zip(expected.points.iter()) {
+            assert!(point.distance_to(expected) < EPSILON, "{:?} != {:?}", point, expected);
+        }
+        for (parameter, expected) in mapped.parameters.iter().zip(expected.parameters.iter()) {
+            assert!((parameter - expected).abs() < EPSILON, "{} != {}", parameter, expected);
+        }
+    }
+
+    #[test]
+    fn remap_matches_new() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let tolerance = 0.25;
+        let styles = [
+            CurveStyle::Bezier(0.5),
+            CurveStyle::CatmullRom,
+            CurveStyle::DoubleJointed,
+        ];
+        for is_loop in [false, true] {
+            for &style in styles.iter() {
+                let mut points: Vec<Vector2> = (0..6).map(|_| random_point(&mut rng)).collect();
+                let mut mapped = MappedPoints::new(&points, style, tolerance, is_loop);
+                for _ in 0..200 {
+                    let old_point_count = points.len();
+                    let index = rng.gen_range(0..points.len());
+                    // the number of points at the start and end that haven't changed, as
+                    // `Curve` records them
+                    let unchanged = match rng.gen_range(0..4) {
+                        0 => {
+                            points[index] = random_point(&mut rng);
+                            (index, points.len() - 1 - index)
+                        }
+                        1 => {
+                            points.push(random_point(&mut rng));
+                            (points.len() - 1, 0)
+                        }
+                        2 => {
+                            points.insert(index, random_point(&mut rng));
+                            (index, points.len() - 1 - index)
+                        }
+                        _ if points.len() > 3 => {
+                            points.remove(index);
+                            (index, points.len() - index)
+                        }
+                        _ => continue,
+                    };
+                    mapped.remap(
+                        &points,
+                        style,
+                        tolerance,
+                        is_loop,
+                        unchanged,
+                        old_point_count,
+                    );
+                    let expected = MappedPoints::new(&points, style, tolerance, is_loop);
+                    assert_same_mapping(&mapped, &expected);
+                }
+            }
+        }
+    }
+}
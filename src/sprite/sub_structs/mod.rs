@@ -1 +1 @@
-usemod![pub curve_sprite];
+usemod![pub curve_sprite, pub curve_queries];
@@ -8,6 +8,13 @@ pub enum TextureProjectionMethod {
     /// Provides a faster creation than `ScaleToFit` but is functionally
     /// identical for `Color` texture sources.
     SingleColor = 1,
+    /// For `Curve`s. The texture runs along the curve, repeating every time the curve
+    /// covers the texture's length at the curve's width, and across the curve's width. Use a
+    /// texture with `AddressMode::Wrap` for this. Other meshes use `ScaleToFit`
+    RepeatAlongLength = 2,
+    /// For `Curve`s. The texture is stretched along the curve's whole length and across its
+    /// width. Other meshes use `ScaleToFit`
+    StretchAlongLength = 3,
 }
 
 #[derive(Clone, Debug)]
@@ -19,6 +26,18 @@ pub enum TextureSource {
     /// The entire texture is a uniform color (recommended if this is what you want).
     /// Use `TextureProjectionMethod::SingleColor` with this
     Color(Color),
+    /// A gradient that runs across the texture in `direction`, in radians, with `0` pointing up
+    /// and positive angles turning clockwise. Use `TextureProjectionMethod::ScaleToFit` with this
+    LinearGradient { gradient: Gradient, direction: f32 },
+    /// A gradient that runs outwards from `center`, reaching an offset of `1.` at `radius`.
+    /// `center` goes from `(0, 0)` at the bottom left of the texture to `(1, 1)` at the top
+    /// right, and `radius` is a fraction of the texture's width. Use
+    /// `TextureProjectionMethod::ScaleToFit` with this
+    RadialGradient {
+        gradient: Gradient,
+        center: Vector2,
+        radius: f32,
+    },
     /// The sprite has no texture. Use this if you intend to draw the sprite with
     /// `Sprite.with_texture(&Texture)`
     Null,
@@ -105,11 +124,12 @@ impl Texture {
     pub(crate) fn create_from_dynamic_image(
         render_state: &RenderState,
         dynamic_image: image::DynamicImage,
+        format: wgpu::TextureFormat,
     ) -> Result<(wgpu::Texture, wgpu::TextureView, (u32, u32))> {
         use image::GenericImageView;
 
         let dimensions = dynamic_image.dimensions();
-        let texture = render_state.create_image_texture(None, dynamic_image)?;
+        let texture = render_state.create_image_texture(None, dynamic_image, format)?;
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         Ok((texture, texture_view, dimensions))
@@ -118,15 +138,32 @@ impl Texture {
     const NULL_TEXTURE: &'static [u8] = &[0, 0, 0, 0];
 
     pub(crate) fn new(render_state: &RenderState, config: &TextureConfiguration) -> Result<Self> {
+        Self::new_with_format(render_state, config, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    /// Creates a texture whose texels are not in the sRGB color space. Normal maps store
+    /// directions rather than colors, so they have to be sampled linearly.
+    pub(crate) fn new_normal_map(
+        render_state: &RenderState,
+        config: &TextureConfiguration,
+    ) -> Result<Self> {
+        Self::new_with_format(render_state, config, wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    pub(crate) fn new_with_format(
+        render_state: &RenderState,
+        config: &TextureConfiguration,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
         let (texture, texture_view, dimensions) = match &config.source {
             TextureSource::Path(path_buf) => {
                 let dynamic_image = Texture::load_image(&path_buf)?;
-                Self::create_from_dynamic_image(render_state, dynamic_image)?
+                Self::create_from_dynamic_image(render_state, dynamic_image, format)?
             }
             TextureSource::Bytes(bytes) => {
                 let dynamic_image =
                     image::load_from_memory(bytes).or(Err(crate::TextureError::load_bytes()))?;
-                Self::create_from_dynamic_image(render_state, dynamic_image)?
+                Self::create_from_dynamic_image(render_state, dynamic_image, format)?
             }
             TextureSource::Color(color) => {
                 let dimensions = (1, 1);
@@ -134,6 +171,36 @@ impl Texture {
                     None,
                     &color.as_rgba8(),
                     dimensions,
+                    format,
+                )?;
+                let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (texture, texture_view, dimensions)
+            }
+            TextureSource::LinearGradient {
+                gradient,
+                direction,
+            } => {
+                let (image_buffer, dimensions) = gradient.linear_image_buffer(*direction);
+                let texture = render_state.create_image_texture_from_buffer(
+                    None,
+                    &image_buffer,
+                    dimensions,
+                    format,
+                )?;
+                let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (texture, texture_view, dimensions)
+            }
+            TextureSource::RadialGradient {
+                gradient,
+                center,
+                radius,
+            } => {
+                let (image_buffer, dimensions) = gradient.radial_image_buffer(*center, *radius);
+                let texture = render_state.create_image_texture_from_buffer(
+                    None,
+                    &image_buffer,
+                    dimensions,
+                    format,
                 )?;
                 let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
                 (texture, texture_view, dimensions)
@@ -144,6 +211,7 @@ impl Texture {
                     None,
                     Self::NULL_TEXTURE,
                     dimensions,
+                    format,
                 )?;
                 let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
                 (texture, texture_view, dimensions)
@@ -0,0 +1,5 @@
+usemod![
+    pub svg_document,
+    pub(crate) svg_parser,
+    pub(crate) svg_path,
+];
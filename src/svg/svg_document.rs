@@ -0,0 +1,199 @@
+use crate::*;
+use std::path::Path;
+
+/// A connected run of points in an `SvgShape`, with curves already flattened to lines
+#[derive(Debug, Clone)]
+pub struct SvgSubpath {
+    pub points: Vec<Vector2>,
+    /// Whether the subpath was closed with a `Z` command, or is a closed shape such as a
+    /// `<rect>`. The last point is not repeated at the start.
+    pub closed: bool,
+}
+
+/// A path or basic shape from an SVG file
+#[derive(Debug, Clone)]
+pub struct SvgShape {
+    pub id: Option<String>,
+    pub subpaths: Vec<SvgSubpath>,
+    /// The fill color, with `opacity` and `fill-opacity` applied, or `None` if the shape is
+    /// not filled
+    pub fill: Option<Color>,
+    /// The stroke color, with `opacity` and `stroke-opacity` applied, or `None` if the shape
+    /// is not stroked
+    pub stroke: Option<Color>,
+    pub stroke_width: f32,
+    pub stroke_cap: LineCap,
+    pub stroke_join: LineJoin,
+    /// The stroke's dash pattern, or `None` if it is solid
+    pub stroke_dashes: Option<DashPattern>,
+}
+
+impl SvgShape {
+    /// Returns a mesh filling this shape, or `None` if it has no area. Subpaths inside an
+    /// odd number of other subpaths are holes, as with the `evenodd` fill rule.
+    pub fn fill_mesh(&self) -> Option<SpriteMesh> {
+        let outlines: Vec<&Vec<Vector2>> = self
+            .subpaths
+            .iter()
+            .map(|subpath| &subpath.points)
+            .filter(|points| points.len() >= 3)
+            .collect();
+        let contains = |outline: &Vec<Vector2>, point: Vector2| {
+            let mut inside = false;
+            for i in 0..outline.len() {
+                let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+                if (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                {
+                    inside = !inside;
+                }
+            }
+            inside
+        };
+        // the subpaths each subpath is inside of
+        let containers: Vec<Vec<usize>> = outlines
+            .iter()
+            .enumerate()
+            .map(|(i, outline)| {
+                (0..outlines.len())
+                    .filter(|&j| j != i && contains(outlines[j], outline[0]))
+                    .collect()
+            })
+            .collect();
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for (i, outline) in outlines.iter().enumerate() {
+            if containers[i].len() % 2 == 1 {
+                continue;
+            }
+            // holes are the subpaths directly inside this one
+            let holes: Vec<Vec<Vector2>> = (0..outlines.len())
+                .filter(|&j| {
+                    containers[j].contains(&i) && containers[j].len() == containers[i].len() + 1
+                })
+                .map(|j| outlines[j].clone())
+                .collect();
+            let (points, triangles) = triangulation::triangulate(outline, &holes);
+            let offset = vertices.len() as u32;
+            vertices.extend(points.into_iter().map(VertexInput::new));
+            indices.extend(triangles.into_iter().map(|index| index + offset));
+        }
+        if indices.is_empty() {
+            return None;
+        }
+
+        Some(SpriteMesh {
+            vertices,
+            indices,
+            affine2: Affine2::default(),
+        })
+    }
+
+    /// Creates a sprite filling this shape with its fill color, or returns `None` if the shape
+    /// is not filled or has no area
+    pub fn create_fill_sprite(&self, oge: &Oge, z_index: ZIndex) -> Result<Option<Sprite>> {
+        let (fill, mesh) = match (self.fill, self.fill_mesh()) {
+            (Some(fill), Some(mesh)) => (fill, mesh),
+            _ => return Ok(None),
+        };
+        let sprite = oge.create_sprite(SpriteConfiguration {
+            label: self.id.as_deref(),
+            mesh,
+            default_texture: oge.create_texture(&TextureConfiguration::color(fill))?,
+            z_index,
+            opacity: 1.,
+            texture_projection_method: TextureProjectionMethod::SingleColor,
+            normal_map: None,
+        })?;
+        Ok(Some(sprite))
+    }
+
+    /// Creates a curve for each subpath of this shape, drawn with its stroke color and width.
+    /// Returns no curves if the shape is not stroked.
+    pub fn create_stroke_curves(&self, oge: &Oge, z_index: ZIndex) -> Result<Vec<Curve>> {
+        let stroke = match self.stroke {
+            Some(stroke) if self.stroke_width > 0. => stroke,
+            _ => return Ok(vec![]),
+        };
+        let mut curves = Vec::with_capacity(self.subpaths.len());
+        for subpath in self.subpaths.iter() {
+            curves.push(Curve::new(CurveConfiguration {
+                label: self.id.as_deref(),
+                width: self.stroke_width,
+                points: subpath.points.clone(),
+                style: CurveStyle::PreserveAngles,
+                cap: self.stroke_cap,
+                join: self.stroke_join,
+                dashes: self.stroke_dashes.clone(),
+                default_texture: oge.create_texture(&TextureConfiguration::color(stroke))?,
+                z_index,
+                opacity: 1.,
+                is_loop: subpath.closed,
+                texture_projection_method: TextureProjectionMethod::SingleColor,
+            })?);
+        }
+        Ok(curves)
+    }
+}
+
+/// The shapes in an SVG file.
+///
+/// Paths (`M`, `L`, `H`, `V`, `C`, `S`, `Q`, `T`, `A` and `Z` commands), `<rect>`,
+/// `<circle>`, `<ellipse>`, `<line>`, `<polyline>` and `<polygon>` are supported, along with
+/// groups, transforms and solid fill and stroke colors. Points are converted to the engine's
+/// coordinate system, with the y axis pointing up and the origin at the bottom left of the
+/// document.
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    pub width: f32,
+    pub height: f32,
+    /// Every shape in the document, in drawing order
+    pub shapes: Vec<SvgShape>,
+}
+
+impl SvgDocument {
+    /// Loads an SVG file. Curves are flattened into lines that are at most `tolerance` away
+    /// from the curve, which must be positive.
+    pub fn load<P: AsRef<Path>>(path: P, tolerance: f32) -> Result<Self> {
+        let path_buf = path.as_ref().to_path_buf();
+        let source = std::fs::read_to_string(&path_buf).or(Err(SvgError::open(&path_buf)))?;
+        svg_parser::parse_document(&source, tolerance)
+            .map_err(|reason| SvgError::parse(Some(&path_buf), &reason))
+    }
+
+    /// Parses the contents of an SVG file. Curves are flattened into lines that are at most
+    /// `tolerance` away from the curve, which must be positive.
+    pub fn parse(source: &str, tolerance: f32) -> Result<Self> {
+        svg_parser::parse_document(source, tolerance)
+            .map_err(|reason| SvgError::parse(None, &reason))
+    }
+
+    /// Returns the z index of the `index`th shape's fill. Each shape's stroke is one above its
+    /// fill.
+    fn fill_z_index(first_z_index: u32, index: usize) -> u32 {
+        first_z_index + index as u32 * 2
+    }
+
+    /// Creates a sprite for each filled shape. Shapes are layered in drawing order, starting
+    /// at `first_z_index`, with two z indices per shape so that strokes created with
+    /// `create_curves` are drawn above their fill.
+    pub fn create_sprites(&self, oge: &Oge, first_z_index: u32) -> Result<Vec<Sprite>> {
+        let mut sprites = vec![];
+        for (i, shape) in self.shapes.iter().enumerate() {
+            let z_index = ZIndex::Specific(Self::fill_z_index(first_z_index, i));
+            sprites.extend(shape.create_fill_sprite(oge, z_index)?);
+        }
+        Ok(sprites)
+    }
+
+    /// Creates curves for each stroked shape, layered to match `create_sprites`
+    pub fn create_curves(&self, oge: &Oge, first_z_index: u32) -> Result<Vec<Curve>> {
+        let mut curves = vec![];
+        for (i, shape) in self.shapes.iter().enumerate() {
+            let z_index = ZIndex::Specific(Self::fill_z_index(first_z_index, i) + 1);
+            curves.extend(shape.create_stroke_curves(oge, z_index)?);
+        }
+        Ok(curves)
+    }
+}
@@ -0,0 +1,618 @@
+use crate::*;
+use roxmltree::{Document, Node};
+
+/// A 2D affine transform in SVG's `matrix(a b c d e f)` form
+#[derive(Clone, Copy)]
+struct Transform([f32; 6]);
+
+impl Transform {
+    const IDENTITY: Self = Self([1., 0., 0., 1., 0., 0.]);
+
+    /// Returns the transform that applies `other` and then `self`
+    fn then(&self, other: &Self) -> Self {
+        let [a, b, c, d, e, f] = self.0;
+        let [oa, ob, oc, od, oe, of] = other.0;
+        Self([
+            a * oa + c * ob,
+            b * oa + d * ob,
+            a * oc + c * od,
+            b * oc + d * od,
+            a * oe + c * of + e,
+            b * oe + d * of + f,
+        ])
+    }
+
+    fn apply(&self, point: Vector2) -> Vector2 {
+        let [a, b, c, d, e, f] = self.0;
+        Vector2::new(a * point.x + c * point.y + e, b * point.x + d * point.y + f)
+    }
+
+    /// The factor lengths are scaled by, on average
+    fn scale(&self) -> f32 {
+        let [a, b, c, d, _, _] = self.0;
+        (a * d - b * c).abs().sqrt()
+    }
+}
+
+/// Presentation attributes, which are inherited by child elements
+#[derive(Clone)]
+struct Style {
+    fill: Option<[f32; 4]>,
+    stroke: Option<[f32; 4]>,
+    stroke_width: f32,
+    stroke_cap: LineCap,
+    /// The limit of a `LineJoin::Miter` is replaced with `stroke_miter_limit`
+    stroke_join: LineJoin,
+    stroke_miter_limit: f32,
+    stroke_dashes: Option<Vec<f32>>,
+    stroke_dash_offset: f32,
+    opacity: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    visible: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some([0., 0., 0., 1.]),
+            stroke: None,
+            stroke_width: 1.,
+            stroke_cap: LineCap::Butt,
+            stroke_join: LineJoin::Miter(4.),
+            stroke_miter_limit: 4.,
+            stroke_dashes: None,
+            stroke_dash_offset: 0.,
+            opacity: 1.,
+            fill_opacity: 1.,
+            stroke_opacity: 1.,
+            visible: true,
+        }
+    }
+}
+
+impl Style {
+    /// Returns this style with the presentation attributes and `style` declarations of `node`
+    /// applied. Values that can't be parsed are skipped, keeping the inherited value.
+    fn inherit(&self, node: &Node) -> Self {
+        let mut style = self.clone();
+        // opacity is not inherited, but multiplies with the parent's
+        style.opacity = 1.;
+        let declarations =
+            node.attribute("style")
+                .unwrap_or("")
+                .split(';')
+                .filter_map(|declaration| {
+                    let mut parts = declaration.splitn(2, ':');
+                    Some((parts.next()?.trim(), parts.next()?.trim()))
+                });
+        let attributes = node
+            .attributes()
+            .iter()
+            .map(|attribute| (attribute.name(), attribute.value().trim()));
+        // declarations in the style attribute take precedence over presentation attributes
+        for (name, value) in attributes.chain(declarations) {
+            if value == "inherit" {
+                continue;
+            }
+            if let Err(reason) = style.apply(name, value) {
+                log::warn!(
+                    "skipping {} on <{}>: {}",
+                    name,
+                    node.tag_name().name(),
+                    reason
+                );
+            }
+        }
+        style.opacity *= self.opacity;
+        style
+    }
+
+    /// Sets the presentation attribute `name` to `value`. Attributes that aren't presentation
+    /// attributes are ignored.
+    fn apply(&mut self, name: &str, value: &str) -> ParseResult<()> {
+        match name {
+            "fill" => self.fill = parse_paint(value)?,
+            "stroke" => self.stroke = parse_paint(value)?,
+            "stroke-width" => self.stroke_width = parse_length(value)?,
+            "stroke-linecap" => {
+                self.stroke_cap = match value {
+                    "square" => LineCap::Square,
+                    "round" => LineCap::Round,
+                    _ => LineCap::Butt,
+                }
+            }
+            "stroke-linejoin" => {
+                self.stroke_join = match value {
+                    "bevel" => LineJoin::Bevel,
+                    "round" => LineJoin::Round,
+                    _ => LineJoin::Miter(4.),
+                }
+            }
+            "stroke-miterlimit" => self.stroke_miter_limit = parse_number(value)?,
+            "stroke-dasharray" => self.stroke_dashes = parse_dash_array(value)?,
+            "stroke-dashoffset" => self.stroke_dash_offset = parse_length(value)?,
+            "opacity" => self.opacity = parse_number(value)?,
+            "fill-opacity" => self.fill_opacity = parse_number(value)?,
+            "stroke-opacity" => self.stroke_opacity = parse_number(value)?,
+            "display" | "visibility" => {
+                self.visible = !matches!(value, "none" | "hidden" | "collapse")
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+struct DocumentContext {
+    tolerance: f32,
+    /// Converts from the root element's user units to the engine's coordinate system
+    view_transform: Transform,
+    shapes: Vec<SvgShape>,
+}
+
+pub(crate) fn parse_document(source: &str, tolerance: f32) -> ParseResult<SvgDocument> {
+    if !(tolerance.is_finite() && tolerance > 0.) {
+        return Err(format!("the tolerance must be positive, not {}", tolerance));
+    }
+    let document = Document::parse(source).map_err(|error| error.to_string())?;
+    let root = document.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err("the root element must be <svg>".to_owned());
+    }
+
+    let (view_box, width, height) = match root.attribute("viewBox") {
+        Some(view_box) => {
+            let mut lexer = svg_path::Lexer::new(view_box);
+            let view_box = [
+                lexer.number()?,
+                lexer.number()?,
+                lexer.number()?,
+                lexer.number()?,
+            ];
+            let width = length_attribute(&root, "width", view_box[2])?;
+            let height = length_attribute(&root, "height", view_box[3])?;
+            (view_box, width, height)
+        }
+        None => {
+            let width = length_attribute(&root, "width", 0.)?;
+            let height = length_attribute(&root, "height", 0.)?;
+            ([0., 0., width, height], width, height)
+        }
+    };
+
+    // map the view box onto the document's size, flipping the y axis so that the origin is at
+    // the bottom left
+    let (scale_x, scale_y) = if view_box[2] > 0. && view_box[3] > 0. {
+        (width / view_box[2], height / view_box[3])
+    } else {
+        (1., 1.)
+    };
+    let view_transform = Transform([
+        scale_x,
+        0.,
+        0.,
+        -scale_y,
+        -view_box[0] * scale_x,
+        height + view_box[1] * scale_y,
+    ]);
+
+    let mut context = DocumentContext {
+        tolerance,
+        view_transform,
+        shapes: vec![],
+    };
+    parse_children(
+        &root,
+        Transform::IDENTITY,
+        Style::default().inherit(&root),
+        &mut context,
+    );
+
+    Ok(SvgDocument {
+        width,
+        height,
+        shapes: context.shapes,
+    })
+}
+
+fn parse_children(node: &Node, transform: Transform, style: Style, context: &mut DocumentContext) {
+    for child in node.children().filter(|child| child.is_element()) {
+        // an element that can't be parsed is skipped, rather than failing the whole document
+        if let Err(reason) = parse_element(&child, transform, style.clone(), context) {
+            log::warn!("skipping <{}>: {}", child.tag_name().name(), reason);
+        }
+    }
+}
+
+fn parse_element(
+    node: &Node,
+    transform: Transform,
+    style: Style,
+    context: &mut DocumentContext,
+) -> ParseResult<()> {
+    let tag_name = node.tag_name().name();
+    if matches!(
+        tag_name,
+        "defs" | "clipPath" | "mask" | "symbol" | "style" | "title" | "desc" | "metadata"
+    ) {
+        return Ok(());
+    }
+    let style = style.inherit(node);
+    if !style.visible {
+        return Ok(());
+    }
+    let transform = match node.attribute("transform") {
+        Some(value) => transform.then(&parse_transform(value)?),
+        None => transform,
+    };
+    if matches!(tag_name, "g" | "svg" | "a") {
+        parse_children(node, transform, style, context);
+        return Ok(());
+    }
+
+    // curves are flattened in the element's user units, so the tolerance is scaled to match
+    let scale = context.view_transform.then(&transform).scale();
+    let tolerance = if scale > 0. {
+        context.tolerance / scale
+    } else {
+        context.tolerance
+    };
+    let mut builder = svg_path::PathBuilder::new(tolerance);
+    let number = |name: &str| length_attribute(node, name, 0.);
+    match tag_name {
+        "path" => svg_path::parse_path_data(node.attribute("d").unwrap_or(""), &mut builder)?,
+        "rect" => {
+            let (x, y) = (number("x")?, number("y")?);
+            let (width, height) = (number("width")?, number("height")?);
+            // a missing radius is the same as the other one
+            let (rx, ry) = match (node.attribute("rx"), node.attribute("ry")) {
+                (None, None) => (0., 0.),
+                (Some(_), None) => (number("rx")?, number("rx")?),
+                (None, Some(_)) => (number("ry")?, number("ry")?),
+                (Some(_), Some(_)) => (number("rx")?, number("ry")?),
+            };
+            let (rx, ry) = (rx.min(width / 2.), ry.min(height / 2.));
+            let radii = Vector2::new(rx, ry);
+            builder.move_to(Vector2::new(x + rx, y));
+            builder.line_to(Vector2::new(x + width - rx, y));
+            builder.arc_to(radii, 0., false, true, Vector2::new(x + width, y + ry));
+            builder.line_to(Vector2::new(x + width, y + height - ry));
+            builder.arc_to(
+                radii,
+                0.,
+                false,
+                true,
+                Vector2::new(x + width - rx, y + height),
+            );
+            builder.line_to(Vector2::new(x + rx, y + height));
+            builder.arc_to(radii, 0., false, true, Vector2::new(x, y + height - ry));
+            builder.line_to(Vector2::new(x, y + ry));
+            builder.arc_to(radii, 0., false, true, Vector2::new(x + rx, y));
+            builder.close();
+        }
+        "circle" | "ellipse" => {
+            let center = Vector2::new(number("cx")?, number("cy")?);
+            let radii = if tag_name == "circle" {
+                Vector2::new(number("r")?, number("r")?)
+            } else {
+                Vector2::new(number("rx")?, number("ry")?)
+            };
+            let left = center.sub(&Vector2::new(radii.x, 0.));
+            let right = center.add(&Vector2::new(radii.x, 0.));
+            builder.move_to(right);
+            builder.arc_to(radii, 0., false, true, left);
+            builder.arc_to(radii, 0., false, true, right);
+            builder.close();
+        }
+        "line" => {
+            builder.move_to(Vector2::new(number("x1")?, number("y1")?));
+            builder.line_to(Vector2::new(number("x2")?, number("y2")?));
+        }
+        "polyline" | "polygon" => {
+            let points = svg_path::parse_points(node.attribute("points").unwrap_or(""))?;
+            if let Some((first, rest)) = points.split_first() {
+                builder.move_to(*first);
+                for point in rest {
+                    builder.line_to(*point);
+                }
+                if tag_name == "polygon" {
+                    builder.close();
+                }
+            }
+        }
+        // unsupported elements, such as text and images, are skipped
+        _ => return Ok(()),
+    }
+
+    let to_engine = context.view_transform.then(&transform);
+    let mut subpaths = builder.finish();
+    for subpath in subpaths.iter_mut() {
+        for point in subpath.points.iter_mut() {
+            *point = to_engine.apply(*point);
+        }
+    }
+    if subpaths.is_empty() {
+        return Ok(());
+    }
+
+    let color = |paint: Option<[f32; 4]>, opacity: f32| {
+        paint.map(|[r, g, b, a]| Color::new(r, g, b, a * opacity * style.opacity))
+    };
+    // lines have no area, so they are never filled
+    let fill = if tag_name == "line" {
+        None
+    } else {
+        color(style.fill, style.fill_opacity)
+    };
+    context.shapes.push(SvgShape {
+        id: node.attribute("id").map(str::to_owned),
+        subpaths,
+        fill,
+        stroke: color(style.stroke, style.stroke_opacity),
+        stroke_width: style.stroke_width * to_engine.scale(),
+        stroke_cap: style.stroke_cap,
+        stroke_join: match style.stroke_join {
+            LineJoin::Miter(_) => LineJoin::Miter(style.stroke_miter_limit),
+            join => join,
+        },
+        stroke_dashes: style.stroke_dashes.as_ref().map(|lengths| {
+            let scale = to_engine.scale();
+            let lengths: Vec<f32> = lengths.iter().map(|length| length * scale).collect();
+            DashPattern::new(&lengths).with_phase(style.stroke_dash_offset * scale)
+        }),
+    });
+    Ok(())
+}
+
+/// Parses a `transform` attribute, which is a list of transform functions applied from right
+/// to left
+fn parse_transform(value: &str) -> ParseResult<Transform> {
+    let mut transform = Transform::IDENTITY;
+    for function in value
+        .split(')')
+        .map(str::trim)
+        .filter(|function| !function.is_empty())
+    {
+        let mut parts = function.splitn(2, '(');
+        let name = parts.next().unwrap_or("").trim_start_matches(',').trim();
+        let mut lexer = svg_path::Lexer::new(parts.next().unwrap_or(""));
+        let mut arguments = vec![];
+        while lexer.at_number() {
+            arguments.push(lexer.number()?);
+        }
+        let argument =
+            |index: usize, default: f32| arguments.get(index).copied().unwrap_or(default);
+        let next = match (name, arguments.len()) {
+            ("matrix", 6) => Transform([
+                arguments[0],
+                arguments[1],
+                arguments[2],
+                arguments[3],
+                arguments[4],
+                arguments[5],
+            ]),
+            ("translate", 1..=2) => Transform([1., 0., 0., 1., argument(0, 0.), argument(1, 0.)]),
+            ("scale", 1..=2) => {
+                let x = argument(0, 1.);
+                Transform([x, 0., 0., argument(1, x), 0., 0.])
+            }
+            ("rotate", 1) | ("rotate", 3) => {
+                let (sin, cos) = argument(0, 0.).to_radians().sin_cos();
+                let (cx, cy) = (argument(1, 0.), argument(2, 0.));
+                // rotate about the origin, then move the origin to the center
+                let rotation = Transform([cos, sin, -sin, cos, 0., 0.]);
+                Transform([1., 0., 0., 1., cx, cy])
+                    .then(&rotation)
+                    .then(&Transform([1., 0., 0., 1., -cx, -cy]))
+            }
+            ("skewX", 1) => Transform([1., 0., argument(0, 0.).to_radians().tan(), 1., 0., 0.]),
+            ("skewY", 1) => Transform([1., argument(0, 0.).to_radians().tan(), 0., 1., 0., 0.]),
+            _ => return Err(format!("invalid transform {:?}", function)),
+        };
+        transform = transform.then(&next);
+    }
+    Ok(transform)
+}
+
+fn parse_number(value: &str) -> ParseResult<f32> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {:?}", value))
+}
+
+/// Parses a length in user units. Lengths may end in `px` or an absolute unit (`mm`, `cm`,
+/// `in`, `pt` or `pc`), which is converted at 96 user units per inch. Relative units such as
+/// `em` and `%` are not supported.
+fn parse_length(value: &str) -> ParseResult<f32> {
+    let units_per_inch = 96.;
+    let units = [
+        ("px", 1.),
+        ("in", units_per_inch),
+        ("cm", units_per_inch / 2.54),
+        ("mm", units_per_inch / 25.4),
+        ("pt", units_per_inch / 72.),
+        ("pc", units_per_inch / 6.),
+    ];
+    let (number, scale) = units
+        .iter()
+        .find_map(|(unit, scale)| value.strip_suffix(unit).map(|number| (number, *scale)))
+        .unwrap_or((value, 1.));
+    Ok(parse_number(number.trim_end())? * scale)
+}
+
+/// Parses a `stroke-dasharray` value. Returns `None` for `none`, and for lists with negative
+/// lengths, which are drawn solid.
+fn parse_dash_array(value: &str) -> ParseResult<Option<Vec<f32>>> {
+    if value == "none" {
+        return Ok(None);
+    }
+    let lengths = value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|length| !length.is_empty())
+        .map(parse_length)
+        .collect::<ParseResult<Vec<f32>>>()?;
+    if lengths.is_empty() || lengths.iter().any(|length| *length < 0.) {
+        return Ok(None);
+    }
+    Ok(Some(lengths))
+}
+
+fn length_attribute(node: &Node, name: &str, default: f32) -> ParseResult<f32> {
+    match node.attribute(name) {
+        Some(value) => parse_length(value.trim()),
+        None => Ok(default),
+    }
+}
+
+/// Parses a `fill` or `stroke` value as red, green, blue and alpha. Returns `None` for
+/// `none`, and black for paint servers such as gradients, which are not supported.
+fn parse_paint(value: &str) -> ParseResult<Option<[f32; 4]>> {
+    if value == "none" || value == "transparent" {
+        return Ok(None);
+    }
+    if value.starts_with("url(") || value == "currentColor" {
+        return Ok(Some([0., 0., 0., 1.]));
+    }
+    let invalid = || format!("invalid color {:?}", value);
+    let channel = |byte: u8| byte as f32 / 255.;
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        return match hex.len() {
+            3 => {
+                let digit = |shift: u32| channel(((digits >> shift) & 0xf) as u8 * 17);
+                Ok(Some([digit(8), digit(4), digit(0), 1.]))
+            }
+            6 => {
+                let byte = |shift: u32| channel((digits >> shift) as u8);
+                Ok(Some([byte(16), byte(8), byte(0), 1.]))
+            }
+            _ => Err(invalid()),
+        };
+    }
+    let function = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|value| value.strip_suffix(')'));
+    if let Some(arguments) = function {
+        let parts: Vec<&str> = arguments.split(',').map(str::trim).collect();
+        if parts.len() != 3 && parts.len() != 4 {
+            return Err(invalid());
+        }
+        let mut components = [1.; 4];
+        for (i, part) in parts.into_iter().enumerate() {
+            // colors are out of 255 and alpha is out of 1, unless given as a percentage
+            components[i] = match part.strip_suffix('%') {
+                Some(percentage) => parse_number(percentage)? / 100.,
+                None if i == 3 => parse_number(part)?,
+                None => parse_number(part)? / 255.,
+            }
+            .clamp(0., 1.);
+        }
+        return Ok(Some(components));
+    }
+    let [r, g, b] = match value {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "lime" => [0, 255, 0],
+        "green" => [0, 128, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "cyan" | "aqua" => [0, 255, 255],
+        "magenta" | "fuchsia" => [255, 0, 255],
+        "gray" | "grey" => [128, 128, 128],
+        "silver" => [192, 192, 192],
+        "maroon" => [128, 0, 0],
+        "olive" => [128, 128, 0],
+        "navy" => [0, 0, 128],
+        "purple" => [128, 0, 128],
+        "teal" => [0, 128, 128],
+        "orange" => [255, 165, 0],
+        _ => return Err(format!("unsupported color {:?}", value)),
+    };
+    Ok(Some([channel(r), channel(g), channel(b), 1.]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn lengths_with_units() {
+        assert_close(parse_length("12").unwrap(), 12.);
+        assert_close(parse_length("12px").unwrap(), 12.);
+        assert_close(parse_length("1in").unwrap(), 96.);
+        assert_close(parse_length("2.54cm").unwrap(), 96.);
+        assert_close(parse_length("25.4mm").unwrap(), 96.);
+        assert_close(parse_length("72pt").unwrap(), 96.);
+        assert_close(parse_length("6pc").unwrap(), 96.);
+        assert!(parse_length("2em").is_err());
+        assert!(parse_length("50%").is_err());
+        assert!(parse_length("1€").is_err());
+        assert!(parse_length("€px").is_err());
+    }
+
+    #[test]
+    fn paints() {
+        assert_eq!(parse_paint("none").unwrap(), None);
+        assert_eq!(parse_paint("#f00").unwrap(), Some([1., 0., 0., 1.]));
+        assert_eq!(parse_paint("#0000ff").unwrap(), Some([0., 0., 1., 1.]));
+        assert_eq!(
+            parse_paint("rgb(255, 0, 0)").unwrap(),
+            Some([1., 0., 0., 1.])
+        );
+        assert_eq!(
+            parse_paint("rgba(0, 255, 0, 0.5)").unwrap(),
+            Some([0., 1., 0., 0.5])
+        );
+        assert_eq!(
+            parse_paint("rgba(100%, 0%, 0%, 25%)").unwrap(),
+            Some([1., 0., 0., 0.25])
+        );
+        assert!(parse_paint("rgb(1, 2)").is_err());
+        assert!(parse_paint("hsl(0, 100%, 50%)").is_err());
+    }
+
+    #[test]
+    fn invalid_values_are_skipped() {
+        let document = parse_document(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                <g stroke="red" stroke-width="2">
+                    <rect width="4em" height="4" />
+                    <rect width="4" height="4" fill="hsl(0, 100%, 50%)" stroke="inherit"
+                        stroke-width="1cm" />
+                    <path d="M 0 0 L 1 1" stroke-width="3ex" />
+                    <path d="M 0 0 L 1 1" stroke-width="1€" />
+                </g>
+            </svg>"#,
+            0.1,
+        )
+        .unwrap();
+        // the rect with an unsupported width is skipped, and the unsupported fill and stroke
+        // width fall back to the inherited ones
+        assert_eq!(document.shapes.len(), 3);
+        assert!(document.shapes[0].fill.is_some());
+        assert!(document.shapes[0].stroke.is_some());
+        assert_close(document.shapes[0].stroke_width, 96. / 2.54);
+        assert_close(document.shapes[1].stroke_width, 2.);
+        assert_close(document.shapes[2].stroke_width, 2.);
+    }
+
+    #[test]
+    fn tolerance_must_be_positive() {
+        let source = r#"<svg xmlns="http://www.w3.org/2000/svg"><circle r="5" /></svg>"#;
+        for tolerance in [0., -1., f32::NAN, f32::INFINITY] {
+            assert!(parse_document(source, tolerance).is_err());
+        }
+        let document = parse_document(source, 1e-30).unwrap();
+        let points = document.shapes[0].subpaths[0].points.len();
+        assert!(points <= 2 * 4096);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::*;
+use std::f32::consts::TAU;
+
+/// Builds flattened subpaths from path commands, in SVG user units
+pub(crate) struct PathBuilder {
+    /// The maximum distance between a curve and the lines approximating it
+    tolerance: f32,
+    subpaths: Vec<SvgSubpath>,
+    current: Vec<Vector2>,
+}
+
+impl PathBuilder {
+    /// The most segments a single curve is flattened into, matching `MeshDetail::segments`
+    const MAX_SEGMENTS: usize = 4096;
+
+    pub(crate) fn new(tolerance: f32) -> Self {
+        Self {
+            tolerance,
+            subpaths: vec![],
+            current: vec![],
+        }
+    }
+
+    /// The current point, which is the origin if no commands have been added yet
+    fn position(&self) -> Vector2 {
+        self.current.last().copied().unwrap_or(Vector2::ZERO)
+    }
+
+    pub(crate) fn move_to(&mut self, point: Vector2) {
+        self.finish_subpath(false);
+        self.current.push(point);
+    }
+
+    pub(crate) fn line_to(&mut self, point: Vector2) {
+        if self.current.is_empty() {
+            self.current.push(Vector2::ZERO);
+        }
+        if self.position() != point {
+            self.current.push(point);
+        }
+    }
+
+    pub(crate) fn quadratic_to(&mut self, control: Vector2, end: Vector2) {
+        let start = self.position();
+        // the largest distance between the curve and its approximation is a quarter of the
+        // second difference, divided by the square of the number of segments
+        let second_difference = start.sub(&control.scale(2.)).add(&end).magnitude();
+        let segments = self.segment_count(second_difference / 4.);
+        for i in 1..=segments {
+            let t = i as f32 / segments as f32;
+            let u = 1. - t;
+            self.line_to(
+                start
+                    .scale(u * u)
+                    .add(&control.scale(2. * u * t))
+                    .add(&end.scale(t * t)),
+            );
+        }
+    }
+
+    pub(crate) fn cubic_to(&mut self, control_1: Vector2, control_2: Vector2, end: Vector2) {
+        let start = self.position();
+        let second_difference = start
+            .sub(&control_1.scale(2.))
+            .add(&control_2)
+            .magnitude()
+            .max(control_1.sub(&control_2.scale(2.)).add(&end).magnitude());
+        let segments = self.segment_count(second_difference * 0.75);
+        for i in 1..=segments {
+            let t = i as f32 / segments as f32;
+            let u = 1. - t;
+            self.line_to(
+                start
+                    .scale(u * u * u)
+                    .add(&control_1.scale(3. * u * u * t))
+                    .add(&control_2.scale(3. * u * t * t))
+                    .add(&end.scale(t * t * t)),
+            );
+        }
+    }
+
+    /// Adds an elliptical arc, following the endpoint parameterization of the SVG
+    /// specification. `rotation` is in degrees.
+    pub(crate) fn arc_to(
+        &mut self,
+        radii: Vector2,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        end: Vector2,
+    ) {
+        let start = self.position();
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+        if start == end {
+            return;
+        }
+        if rx == 0. || ry == 0. {
+            self.line_to(end);
+            return;
+        }
+
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let half_difference = start.sub(&end).scale(0.5);
+        let x1 = cos * half_difference.x + sin * half_difference.y;
+        let y1 = -sin * half_difference.x + cos * half_difference.y;
+
+        // scale up radii that are too small to reach the end point
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1. {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+        let midpoint = start.midpoint(&end);
+        let center = Vector2::new(
+            cos * cx1 - sin * cy1 + midpoint.x,
+            sin * cx1 + cos * cy1 + midpoint.y,
+        );
+
+        let angle = |x: f32, y: f32| y.atan2(x);
+        let start_angle = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let mut sweep_angle = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - start_angle;
+        if sweep && sweep_angle < 0. {
+            sweep_angle += TAU;
+        } else if !sweep && sweep_angle > 0. {
+            sweep_angle -= TAU;
+        }
+
+        // the sagitta of each segment must be within the tolerance
+        let radius = rx.max(ry);
+        let segment_angle = if self.tolerance < radius {
+            2. * (1. - self.tolerance / radius).acos()
+        } else {
+            TAU
+        };
+        let segments =
+            ((sweep_angle.abs() / segment_angle).ceil() as usize).clamp(1, Self::MAX_SEGMENTS);
+        for i in 1..segments {
+            let theta = start_angle + sweep_angle * i as f32 / segments as f32;
+            let (x, y) = (rx * theta.cos(), ry * theta.sin());
+            self.line_to(Vector2::new(
+                cos * x - sin * y + center.x,
+                sin * x + cos * y + center.y,
+            ));
+        }
+        self.line_to(end);
+    }
+
+    pub(crate) fn close(&mut self) {
+        let start = self.current.first().copied();
+        self.finish_subpath(true);
+        // commands after a close continue from the start of the closed subpath
+        self.current.extend(start);
+    }
+
+    /// Returns every subpath that has been built
+    pub(crate) fn finish(mut self) -> Vec<SvgSubpath> {
+        self.finish_subpath(false);
+        self.subpaths
+    }
+
+    fn finish_subpath(&mut self, closed: bool) {
+        let mut points = std::mem::take(&mut self.current);
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() > 1 {
+            self.subpaths.push(SvgSubpath { points, closed });
+        }
+    }
+
+    /// Returns the number of line segments needed to approximate a curve, where `error` is
+    /// how far the curve is from a single segment
+    fn segment_count(&self, error: f32) -> usize {
+        ((error / self.tolerance).sqrt().ceil() as usize).clamp(1, Self::MAX_SEGMENTS)
+    }
+}
+
+/// Reads the numbers and flags in path data and `points` attributes
+pub(crate) struct Lexer<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self {
+            source: source.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(byte) = self.source.get(self.position) {
+            if byte.is_ascii_whitespace() || *byte == b',' {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&mut self) -> bool {
+        self.skip_separators();
+        self.position >= self.source.len()
+    }
+
+    /// Returns the next byte if it is a path command, without consuming it
+    fn peek_command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.source
+            .get(self.position)
+            .copied()
+            .filter(|byte| byte.is_ascii_alphabetic() && !matches!(byte, b'e' | b'E'))
+    }
+
+    /// Returns `true` if the next token is a number
+    pub(crate) fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        matches!(
+            self.source.get(self.position),
+            Some(b'0'..=b'9' | b'-' | b'+' | b'.')
+        )
+    }
+
+    pub(crate) fn number(&mut self) -> ParseResult<f32> {
+        self.skip_separators();
+        let start = self.position;
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+        if matches!(self.source.get(self.position), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+        while let Some(&byte) = self.source.get(self.position) {
+            match byte {
+                b'0'..=b'9' => {}
+                // a second dot starts a new number, as in "0.5.5"
+                b'.' if !seen_dot && !seen_exponent => seen_dot = true,
+                b'e' | b'E' if !seen_exponent => {
+                    seen_exponent = true;
+                    if matches!(self.source.get(self.position + 1), Some(b'-' | b'+')) {
+                        self.position += 1;
+                    }
+                }
+                _ => break,
+            }
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.source[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| format!("expected a number at offset {}", start))
+    }
+
+    /// Reads an arc flag, which may be written without a separator before the next number
+    fn flag(&mut self) -> ParseResult<bool> {
+        self.skip_separators();
+        let flag = match self.source.get(self.position) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(format!("expected an arc flag at offset {}", self.position)),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+
+    pub(crate) fn point(&mut self) -> ParseResult<Vector2> {
+        Ok(Vector2::new(self.number()?, self.number()?))
+    }
+}
+
+/// Parses the `d` attribute of a `<path>`
+pub(crate) fn parse_path_data(data: &str, builder: &mut PathBuilder) -> ParseResult<()> {
+    let mut lexer = Lexer::new(data);
+    let mut command = match lexer.peek_command() {
+        Some(command) => command,
+        None if lexer.is_empty() => return Ok(()),
+        None => return Err("path data must start with a command".to_owned()),
+    };
+    // the last control point of a curve, which `S` and `T` reflect
+    let mut last_cubic_control: Option<Vector2> = None;
+    let mut last_quadratic_control: Option<Vector2> = None;
+
+    loop {
+        if let Some(next) = lexer.peek_command() {
+            command = next;
+            lexer.position += 1;
+        } else if lexer.is_empty() {
+            return Ok(());
+        } else if matches!(command, b'Z' | b'z') {
+            return Err("expected a command after Z".to_owned());
+        }
+
+        let current = builder.position();
+        let relative = command.is_ascii_lowercase();
+        let offset = if relative { current } else { Vector2::ZERO };
+        let mut cubic_control = None;
+        let mut quadratic_control = None;
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                builder.move_to(lexer.point()?.add(&offset));
+                // further coordinate pairs are implicit line commands
+                command = if relative { b'l' } else { b'L' };
+            }
+            b'L' => builder.line_to(lexer.point()?.add(&offset)),
+            b'H' => {
+                let x = lexer.number()? + offset.x;
+                builder.line_to(Vector2::new(x, current.y));
+            }
+            b'V' => {
+                let y = lexer.number()? + offset.y;
+                builder.line_to(Vector2::new(current.x, y));
+            }
+            b'C' => {
+                let control_1 = lexer.point()?.add(&offset);
+                let control_2 = lexer.point()?.add(&offset);
+                let end = lexer.point()?.add(&offset);
+                builder.cubic_to(control_1, control_2, end);
+                cubic_control = Some(control_2);
+            }
+            b'S' => {
+                let control_1 = reflect(last_cubic_control, current);
+                let control_2 = lexer.point()?.add(&offset);
+                let end = lexer.point()?.add(&offset);
+                builder.cubic_to(control_1, control_2, end);
+                cubic_control = Some(control_2);
+            }
+            b'Q' => {
+                let control = lexer.point()?.add(&offset);
+                let end = lexer.point()?.add(&offset);
+                builder.quadratic_to(control, end);
+                quadratic_control = Some(control);
+            }
+            b'T' => {
+                let control = reflect(last_quadratic_control, current);
+                let end = lexer.point()?.add(&offset);
+                builder.quadratic_to(control, end);
+                quadratic_control = Some(control);
+            }
+            b'A' => {
+                let radii = lexer.point()?;
+                let rotation = lexer.number()?;
+                let large_arc = lexer.flag()?;
+                let sweep = lexer.flag()?;
+                let end = lexer.point()?.add(&offset);
+                builder.arc_to(radii, rotation, large_arc, sweep, end);
+            }
+            b'Z' => builder.close(),
+            _ => return Err(format!("unknown path command {:?}", command as char)),
+        }
+        last_cubic_control = cubic_control;
+        last_quadratic_control = quadratic_control;
+    }
+}
+
+/// Reflects the previous control point about `current`, or returns `current` if the previous
+/// command was not the same kind of curve
+fn reflect(control: Option<Vector2>, current: Vector2) -> Vector2 {
+    match control {
+        Some(control) => current.scale(2.).sub(&control),
+        None => current,
+    }
+}
+
+/// Parses the `points` attribute of a `<polyline>` or `<polygon>`
+pub(crate) fn parse_points(points: &str) -> ParseResult<Vec<Vector2>> {
+    let mut lexer = Lexer::new(points);
+    let mut parsed = vec![];
+    while lexer.at_number() {
+        parsed.push(lexer.point()?);
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,157 @@
+use crate::*;
+
+/// A square section of a `Tilemap` that is drawn with a single mesh.
+#[derive(Debug)]
+pub(crate) struct Chunk {
+    pub(crate) tiles: [[Option<TileId>; Chunk::SIZE]; Chunk::SIZE],
+    /// `None` if the tiles have changed since the mesh was last built
+    pub(crate) mesh: Option<SpriteMesh>,
+}
+
+impl Chunk {
+    /// The width and height of a chunk, in tiles
+    pub(crate) const SIZE: usize = 16;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            tiles: [[None; Chunk::SIZE]; Chunk::SIZE],
+            mesh: None,
+        }
+    }
+
+    /// Splits a tile position into the position of its chunk and its position within
+    /// that chunk
+    pub(crate) fn locate(x: i32, y: i32) -> ((i32, i32), (usize, usize)) {
+        let size = Chunk::SIZE as i32;
+        (
+            (x.div_euclid(size), y.div_euclid(size)),
+            (x.rem_euclid(size) as usize, y.rem_euclid(size) as usize),
+        )
+    }
+
+    /// Sets the tile at the given position relative to the chunk's bottom left corner
+    pub(crate) fn set(&mut self, x: usize, y: usize, tile: Option<TileId>) {
+        if self.tiles[y][x] != tile {
+            self.tiles[y][x] = tile;
+            self.mesh = None;
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tiles.iter().flatten().all(Option::is_none)
+    }
+
+    /// Returns this chunk's mesh, rebuilding it if any tiles have changed. `origin` is the
+    /// position of the chunk's bottom left corner.
+    pub(crate) fn mesh(
+        &mut self,
+        tileset: &Tileset,
+        tile_size: &Vector2,
+        origin: Vector2,
+    ) -> &SpriteMesh {
+        let tiles = &self.tiles;
+        self.mesh.get_or_insert_with(|| {
+            let mut vertices = Vec::<VertexInput>::new();
+            let mut indices = Vec::<u32>::new();
+
+            for (y, row) in tiles.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
+                    let tile = match tile {
+                        Some(tile) => *tile,
+                        None => continue,
+                    };
+                    let bottom_left = Vector2::new(x as f32 * tile_size.x, y as f32 * tile_size.y);
+                    let top_right = bottom_left.add(tile_size);
+                    let (uv_top_left, uv_bottom_right) = tileset.texture_coordinates(tile);
+
+                    let i = vertices.len() as u32;
+                    vertices.extend([
+                        VertexInput::with_texture_coordinates(
+                            bottom_left,
+                            Vector2::new(uv_top_left.x, uv_bottom_right.y),
+                        ),
+                        VertexInput::with_texture_coordinates(
+                            Vector2::new(top_right.x, bottom_left.y),
+                            uv_bottom_right,
+                        ),
+                        VertexInput::with_texture_coordinates(
+                            top_right,
+                            Vector2::new(uv_bottom_right.x, uv_top_left.y),
+                        ),
+                        VertexInput::with_texture_coordinates(
+                            Vector2::new(bottom_left.x, top_right.y),
+                            uv_top_left,
+                        ),
+                    ]);
+                    indices.extend([i, i + 1, i + 2, i, i + 2, i + 3]);
+                }
+            }
+
+            SpriteMesh {
+                vertices,
+                indices,
+                affine2: Affine2 {
+                    matrix2: Matrix2::default(),
+                    translation: origin,
+                },
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate() {
+        assert_eq!(Chunk::locate(0, 0), ((0, 0), (0, 0)));
+        assert_eq!(Chunk::locate(5, 9), ((0, 0), (5, 9)));
+        assert_eq!(Chunk::locate(15, 15), ((0, 0), (15, 15)));
+        assert_eq!(Chunk::locate(16, 31), ((1, 1), (0, 15)));
+        assert_eq!(Chunk::locate(32, 47), ((2, 2), (0, 15)));
+    }
+
+    #[test]
+    fn locate_negative_positions() {
+        assert_eq!(Chunk::locate(-1, -1), ((-1, -1), (15, 15)));
+        assert_eq!(Chunk::locate(-16, -15), ((-1, -1), (0, 1)));
+        assert_eq!(Chunk::locate(-17, -32), ((-2, -2), (15, 0)));
+        assert_eq!(Chunk::locate(-33, 16), ((-3, 1), (15, 0)));
+    }
+
+    #[test]
+    fn locate_covers_every_tile_once() {
+        let size = Chunk::SIZE as i32;
+        for x in -2 * size..2 * size {
+            let ((chunk_x, _), (tile_x, _)) = Chunk::locate(x, 0);
+            assert_eq!(chunk_x * size + tile_x as i32, x);
+            assert!(tile_x < Chunk::SIZE);
+        }
+    }
+
+    #[test]
+    fn set() {
+        let mut chunk = Chunk::new();
+        assert!(chunk.is_empty());
+
+        chunk.set(15, 0, Some(3));
+        assert_eq!(chunk.tiles[0][15], Some(3));
+        assert!(!chunk.is_empty());
+
+        chunk.set(15, 0, None);
+        assert!(chunk.is_empty());
+    }
+
+    #[test]
+    fn set_invalidates_the_mesh_only_when_a_tile_changes() {
+        let mut chunk = Chunk::new();
+        chunk.set(2, 3, Some(1));
+        chunk.mesh = Some(SpriteMesh::new_rectangle(1., 1.));
+
+        chunk.set(2, 3, Some(1));
+        assert!(chunk.mesh.is_some());
+        chunk.set(2, 3, Some(2));
+        assert!(chunk.mesh.is_none());
+    }
+}
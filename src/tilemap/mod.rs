@@ -0,0 +1,6 @@
+usemod![
+    pub tileset,
+    pub tilemap_struct,
+    pub(crate) chunk,
+    pub tiled,
+];
@@ -0,0 +1,5 @@
+usemod![
+    pub tiled_map,
+    pub(crate) tmx,
+    pub(crate) tmj,
+];
@@ -0,0 +1,521 @@
+use crate::*;
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// The custom properties attached to a Tiled map, layer, tileset or object
+pub type TiledProperties = HashMap<String, TiledProperty>;
+
+/// The value of a custom property
+#[derive(Debug, Clone)]
+pub enum TiledProperty {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Color(Color),
+    /// A path, relative to the current working directory
+    File(PathBuf),
+    /// The id of another object in the map
+    Object(u32),
+    /// A custom class, made up of further properties
+    Class(TiledProperties),
+}
+
+/// A tileset used by a `TiledMap`. Only tilesets made from a single image are supported.
+#[derive(Debug, Clone)]
+pub struct TiledTileset {
+    /// The global tile id of the first tile in this tileset
+    pub first_gid: u32,
+    pub name: String,
+    /// The path to the tileset image, relative to the current working directory
+    pub image: PathBuf,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub properties: TiledProperties,
+}
+
+impl TiledTileset {
+    /// Returns `true` if the global tile id `gid` refers to a tile in this tileset
+    pub fn contains(&self, gid: u32) -> bool {
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    /// Returns the number of rows of tiles in the tileset image
+    pub fn rows(&self) -> u32 {
+        (self.tile_count + self.columns - 1) / self.columns
+    }
+
+    /// Loads the tileset image into an engine `Tileset`
+    pub fn create_tileset(&self, oge: &Oge) -> Result<Tileset> {
+        if self.spacing != 0 || self.margin != 0 {
+            return Err(TiledError::unsupported("tilesets with spacing or margins"));
+        }
+        Ok(Tileset::new(TilesetConfiguration {
+            texture: oge.create_texture(&TextureConfiguration {
+                source: TextureSource::Path(self.image.clone()),
+                filter_mode: FilterMode::Point,
+                ..Default::default()
+            })?,
+            columns: self.columns,
+            rows: self.rows(),
+        }))
+    }
+}
+
+/// The shape of a `TiledObject`. All coordinates are relative to the object's position.
+#[derive(Debug, Clone)]
+pub enum TiledShape {
+    /// A rectangle extending right and down from the object's position
+    Rectangle { width: f32, height: f32 },
+    /// An ellipse inscribed in a rectangle extending right and down from the object's
+    /// position
+    Ellipse { width: f32, height: f32 },
+    /// A closed polygon
+    Polygon(Vec<Vector2>),
+    /// An open path
+    Polyline(Vec<Vector2>),
+    /// A single point at the object's position
+    Point,
+    /// A tile, extending right and up from the object's position
+    Tile { gid: u32, width: f32, height: f32 },
+}
+
+/// An object from an object layer
+#[derive(Debug, Clone)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// The object's class (called "type" in older versions of Tiled)
+    pub class: String,
+    pub position: Vector2,
+    /// The counter-clockwise rotation of this object about its position, in radians
+    pub rotation: f32,
+    pub shape: TiledShape,
+    pub visible: bool,
+    pub properties: TiledProperties,
+}
+
+impl TiledObject {
+    /// The number of points used to approximate an ellipse outline
+    const ELLIPSE_DETAIL: u16 = 32;
+
+    /// Returns the outline of this object as a list of points, with the object's position
+    /// and rotation applied. Points and polylines are returned as they are.
+    pub fn outline(&self) -> Vec<Vector2> {
+        let points = match &self.shape {
+            TiledShape::Rectangle { width, height } => vec![
+                Vector2::new(0., -height),
+                Vector2::new(*width, -height),
+                Vector2::new(*width, 0.),
+                Vector2::ZERO,
+            ],
+            TiledShape::Tile { width, height, .. } => vec![
+                Vector2::ZERO,
+                Vector2::new(*width, 0.),
+                Vector2::new(*width, *height),
+                Vector2::new(0., *height),
+            ],
+            TiledShape::Ellipse { width, height } => {
+                let center = Vector2::new(width * 0.5, -height * 0.5);
+                (0..Self::ELLIPSE_DETAIL)
+                    .map(|i| {
+                        let angle =
+                            2. * std::f32::consts::PI * i as f32 / Self::ELLIPSE_DETAIL as f32;
+                        Vector2::new(angle.cos() * width * 0.5, angle.sin() * height * 0.5)
+                            .add(&center)
+                    })
+                    .collect()
+            }
+            TiledShape::Polygon(points) | TiledShape::Polyline(points) => points.clone(),
+            TiledShape::Point => vec![Vector2::ZERO],
+        };
+
+        let rotation = Matrix2::rotation(-self.rotation);
+        points
+            .into_iter()
+            .map(|point| point.mul(&rotation).add(&self.position))
+            .collect()
+    }
+
+    /// Returns a filled mesh for this object, or `None` if the object has no area.
+    pub fn mesh(&self) -> Option<SpriteMesh> {
+        match self.shape {
+            TiledShape::Point | TiledShape::Polyline(_) => return None,
+            _ => {}
+        }
+        let outline = self.outline();
+        if outline.len() < 3 {
+            return None;
+        }
+        Some(SpriteMesh::new_polygon(&outline))
+    }
+}
+
+/// The contents of a `TiledLayer`
+#[derive(Debug, Clone)]
+pub enum TiledLayerContent {
+    /// Every non-empty tile in the layer, as `(x, y, gid)`. The flip flags Tiled stores in
+    /// the upper bits of each gid are removed.
+    Tiles(Vec<(i32, i32, u32)>),
+    Objects(Vec<TiledObject>),
+}
+
+/// A tile or object layer. Layers nested in groups are flattened, with the group's offset,
+/// opacity and visibility applied to them.
+#[derive(Debug, Clone)]
+pub struct TiledLayer {
+    pub name: String,
+    /// Layers are ordered from `ZIndex::Specific(0)` upwards, in the order Tiled draws them
+    pub z_index: ZIndex,
+    pub opacity: f32,
+    pub visible: bool,
+    pub offset: Vector2,
+    pub properties: TiledProperties,
+    pub content: TiledLayerContent,
+}
+
+/// A map made with the [Tiled](https://www.mapeditor.org) editor.
+///
+/// All positions are converted to the engine's coordinate system, where one unit is one
+/// pixel, y points up and the bottom left corner of the map is the origin.
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    /// The width of the map, in tiles
+    pub width: u32,
+    /// The height of the map, in tiles
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: TiledProperties,
+}
+
+impl TiledMap {
+    pub(crate) const FLIP_FLAGS: u32 = 0xf000_0000;
+
+    /// Loads a map from a `.tmx` (XML) or `.tmj`/`.json` (JSON) file. External tilesets are
+    /// loaded relative to the map.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path_buf = path.as_ref().to_path_buf();
+        let source =
+            std::fs::read_to_string(&path_buf).or(Err(TiledError::open(&path_buf)))?;
+        let base_dir = path_buf.parent().unwrap_or(Path::new("")).to_path_buf();
+
+        let map = match path_buf.extension().and_then(|extension| extension.to_str()) {
+            Some("tmx") | Some("xml") => tmx::parse_map(&source, &base_dir),
+            Some("tmj") | Some("json") => tmj::parse_map(&source, &base_dir),
+            _ => return Err(TiledError::unsupported("maps that are not .tmx, .tmj or .json")),
+        };
+        map.map_err(|reason| TiledError::parse(&path_buf, &reason))
+    }
+
+    /// Parses a map from the contents of a `.tmx` file. `base_dir` is used to resolve
+    /// external tilesets and images.
+    pub fn from_tmx(source: &str, base_dir: &Path) -> Result<Self> {
+        tmx::parse_map(source, base_dir)
+            .map_err(|reason| TiledError::parse(&base_dir.to_path_buf(), &reason))
+    }
+
+    /// Parses a map from the contents of a `.tmj` file. `base_dir` is used to resolve
+    /// external tilesets and images.
+    pub fn from_tmj(source: &str, base_dir: &Path) -> Result<Self> {
+        tmj::parse_map(source, base_dir)
+            .map_err(|reason| TiledError::parse(&base_dir.to_path_buf(), &reason))
+    }
+
+    /// Returns the tileset that contains the tile with the global id `gid`
+    pub fn tileset_for_gid(&self, gid: u32) -> Option<&TiledTileset> {
+        self.tilesets.iter().find(|tileset| tileset.contains(gid))
+    }
+
+    /// Returns the visible object layers, in drawing order
+    pub fn object_layers(&self) -> impl Iterator<Item = (&TiledLayer, &Vec<TiledObject>)> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.visible)
+            .filter_map(|layer| match &layer.content {
+                TiledLayerContent::Objects(objects) => Some((layer, objects)),
+                _ => None,
+            })
+    }
+
+    /// Creates a `Tilemap` for each visible tile layer, in drawing order. A layer that uses
+    /// tiles from several tilesets is split into one `Tilemap` per tileset.
+    ///
+    /// Each tileset image is loaded once, the first time a layer uses it, and shared by
+    /// every `Tilemap` that draws from it.
+    pub fn create_tilemaps(&self, oge: &Oge) -> Result<Vec<Tilemap>> {
+        let mut tilemaps = Vec::new();
+        let mut loaded_tilesets: HashMap<usize, Tileset> = HashMap::new();
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            let tiles = match &layer.content {
+                TiledLayerContent::Tiles(tiles) => tiles,
+                _ => continue,
+            };
+            for (index, tileset) in self.tilesets.iter().enumerate() {
+                let mut tilemap: Option<Tilemap> = None;
+                for &(x, y, gid) in tiles.iter().filter(|(_, _, gid)| tileset.contains(*gid)) {
+                    if tilemap.is_none() {
+                        let loaded = match loaded_tilesets.get(&index) {
+                            Some(loaded) => loaded.clone(),
+                            None => {
+                                let loaded = tileset.create_tileset(oge)?;
+                                loaded_tilesets.insert(index, loaded.clone());
+                                loaded
+                            }
+                        };
+                        tilemap = Some(Tilemap::new(TilemapConfiguration {
+                            label: Some(&layer.name),
+                            tileset: loaded,
+                            tile_size: Vector2::new(
+                                self.tile_width as f32,
+                                self.tile_height as f32,
+                            ),
+                            origin: layer.offset,
+                            z_index: layer.z_index,
+                            opacity: layer.opacity,
+                        }));
+                    }
+                    if let Some(tilemap) = tilemap.as_mut() {
+                        tilemap.set_tile(x, y, Some(gid - tileset.first_gid));
+                    }
+                }
+                tilemaps.extend(tilemap);
+            }
+        }
+        Ok(tilemaps)
+    }
+}
+
+// Helpers shared by the `.tmx` and `.tmj` parsers
+
+pub(crate) type ParseResult<T> = std::result::Result<T, String>;
+
+/// State inherited from the group layers a layer is nested in
+#[derive(Clone, Copy)]
+pub(crate) struct GroupState {
+    pub(crate) offset: Vector2,
+    pub(crate) opacity: f32,
+    pub(crate) visible: bool,
+}
+
+impl GroupState {
+    /// The state of layers at the top level of the map
+    pub(crate) const ROOT: Self = Self {
+        offset: Vector2::ZERO,
+        opacity: 1.,
+        visible: true,
+    };
+
+    /// Returns the state of a layer nested in this group, given the layer's own attributes
+    pub(crate) fn nest(&self, offset: Vector2, opacity: f32, visible: bool) -> Self {
+        Self {
+            offset: self.offset.add(&offset),
+            opacity: self.opacity * opacity,
+            visible: self.visible && visible,
+        }
+    }
+}
+
+/// Map-wide values needed while parsing layers
+pub(crate) struct MapContext<'a> {
+    pub(crate) base_dir: &'a Path,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixel_height: f32,
+    pub(crate) next_z_index: u32,
+}
+
+impl<'a> MapContext<'a> {
+    pub(crate) fn new(base_dir: &'a Path, width: u32, height: u32, tile_height: u32) -> Self {
+        Self {
+            base_dir,
+            width,
+            height,
+            pixel_height: height as f32 * tile_height as f32,
+            next_z_index: 0,
+        }
+    }
+}
+
+/// Returns the number of columns in a tileset, computing it from the image width when the
+/// tileset does not record it
+pub(crate) fn tileset_columns(
+    columns: u32,
+    tile_width: u32,
+    image_width: impl FnOnce() -> ParseResult<u32>,
+) -> ParseResult<u32> {
+    let columns = match columns {
+        0 if tile_width == 0 => return Err("tileset has no tile width".to_owned()),
+        0 => image_width()? / tile_width,
+        columns => columns,
+    };
+    if columns == 0 {
+        return Err("tileset has no columns".to_owned());
+    }
+    Ok(columns)
+}
+
+/// Converts a position in Tiled's coordinate system (y down, origin at the top left) to
+/// the engine's coordinate system
+pub(crate) fn flip_position(x: f32, y: f32, map_pixel_height: f32) -> Vector2 {
+    Vector2::new(x, map_pixel_height - y)
+}
+
+/// Parses a Tiled color, either `#RRGGBB` or `#AARRGGBB`
+pub(crate) fn parse_color(color: &str) -> ParseResult<Color> {
+    let hex = color.trim_start_matches('#');
+    if !hex.is_ascii() {
+        return Err(format!("invalid color {}", color));
+    }
+    let component = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid color {}", color))
+    };
+    match hex.len() {
+        6 => Ok(Color::from_rgba8(component(0)?, component(2)?, component(4)?, 255)),
+        8 => Ok(Color::from_rgba8(
+            component(2)?,
+            component(4)?,
+            component(6)?,
+            component(0)?,
+        )),
+        _ => Err(format!("invalid color {}", color)),
+    }
+}
+
+/// Parses a property value given as a string, according to its Tiled type
+pub(crate) fn parse_property(
+    property_type: &str,
+    value: &str,
+    base_dir: &Path,
+) -> ParseResult<TiledProperty> {
+    let invalid = || format!("invalid {} property {:?}", property_type, value);
+    Ok(match property_type {
+        "int" => TiledProperty::Int(value.parse().map_err(|_| invalid())?),
+        "float" => TiledProperty::Float(value.parse().map_err(|_| invalid())?),
+        "bool" => TiledProperty::Bool(value.parse().map_err(|_| invalid())?),
+        "color" if value.is_empty() => TiledProperty::Color(Color::default()),
+        "color" => TiledProperty::Color(parse_color(value)?),
+        "file" => TiledProperty::File(base_dir.join(value)),
+        "object" => TiledProperty::Object(value.parse().map_err(|_| invalid())?),
+        _ => TiledProperty::String(value.to_owned()),
+    })
+}
+
+/// Decodes the tile data of a layer or chunk into a list of gids
+pub(crate) fn decode_tile_data(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> ParseResult<Vec<u32>> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(|gid| gid.trim())
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| gid.parse().map_err(|_| format!("invalid tile {:?}", gid)))
+            .collect(),
+        Some("base64") => {
+            let bytes = base64::decode(data.trim()).map_err(|_| "invalid base64 tile data")?;
+            let bytes = match compression {
+                None | Some("") => bytes,
+                Some("zlib") => {
+                    let mut decompressed = Vec::new();
+                    flate2::read::ZlibDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .map_err(|_| "invalid zlib tile data")?;
+                    decompressed
+                }
+                Some("gzip") => {
+                    let mut decompressed = Vec::new();
+                    flate2::read::GzDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .map_err(|_| "invalid gzip tile data")?;
+                    decompressed
+                }
+                Some(compression) => {
+                    return Err(format!("unsupported tile compression {}", compression))
+                }
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        Some(encoding) => Err(format!("unsupported tile encoding {}", encoding)),
+        None => Err("missing tile encoding".to_owned()),
+    }
+}
+
+/// Converts a row of gids starting at `(x, y)` in Tiled's tile coordinates into a list of
+/// non-empty tiles in engine tile coordinates
+pub(crate) fn push_tiles(
+    tiles: &mut Vec<(i32, i32, u32)>,
+    gids: &[u32],
+    (x, y): (i32, i32),
+    width: u32,
+    map_height: u32,
+) {
+    for (i, gid) in gids.iter().enumerate() {
+        let gid = gid & !TiledMap::FLIP_FLAGS;
+        if gid == 0 {
+            continue;
+        }
+        let (column, row) = (x + (i as u32 % width) as i32, y + (i as u32 / width) as i32);
+        tiles.push((column, map_height as i32 - 1 - row, gid));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#ff8000").unwrap().as_rgba8(), [255, 128, 0, 255]);
+        assert_eq!(parse_color("80ff0000").unwrap().as_rgba8(), [255, 0, 0, 128]);
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#gg0000").is_err());
+        // Six bytes, but not six hex digits
+        assert!(parse_color("#1€2a").is_err());
+    }
+
+    #[test]
+    fn zero_tile_width_is_an_error() {
+        assert!(tileset_columns(0, 0, || Ok(64)).is_err());
+        assert!(tileset_columns(0, 16, || Ok(8)).is_err());
+        assert_eq!(tileset_columns(0, 16, || Ok(64)), Ok(4));
+        assert_eq!(tileset_columns(3, 0, || Ok(64)), Ok(3));
+    }
+
+    #[test]
+    fn groups_combine_with_their_layers() {
+        let group = GroupState::ROOT.nest(Vector2::new(4., -8.), 0.5, true);
+        let layer = group.nest(Vector2::new(1., 0.), 0.5, true);
+        assert_eq!(layer.offset, Vector2::new(5., -8.));
+        assert_eq!(layer.opacity, 0.25);
+        assert!(layer.visible);
+        let hidden = group.nest(Vector2::ZERO, 1., false);
+        assert!(!hidden.visible);
+        assert!(!hidden.nest(Vector2::ZERO, 1., true).visible);
+    }
+
+    #[test]
+    fn tiles_are_flipped_to_y_up() {
+        let mut tiles = Vec::new();
+        push_tiles(&mut tiles, &[1, 0, 0, 4 | TiledMap::FLIP_FLAGS], (0, 0), 2, 2);
+        assert_eq!(tiles, vec![(0, 1, 1), (1, 0, 4)]);
+
+        let mut tiles = Vec::new();
+        push_tiles(&mut tiles, &[7, 8], (-2, -1), 1, 2);
+        assert_eq!(tiles, vec![(-2, 2, 7), (-2, 1, 8)]);
+    }
+}
@@ -0,0 +1,529 @@
+use crate::*;
+use serde_json::Value;
+use std::path::Path;
+
+fn field<'a>(value: &'a Value, name: &str) -> ParseResult<&'a Value> {
+    value
+        .get(name)
+        .ok_or(format!("missing the field {:?}", name))
+}
+
+fn u32_field(value: &Value, name: &str) -> ParseResult<u32> {
+    field(value, name)?
+        .as_u64()
+        .map(|n| n as u32)
+        .ok_or(format!("{:?} is not an unsigned integer", name))
+}
+
+fn u32_field_or(value: &Value, name: &str, default: u32) -> ParseResult<u32> {
+    match value.get(name) {
+        Some(_) => u32_field(value, name),
+        None => Ok(default),
+    }
+}
+
+fn i32_field(value: &Value, name: &str) -> ParseResult<i32> {
+    field(value, name)?
+        .as_i64()
+        .map(|n| n as i32)
+        .ok_or(format!("{:?} is not an integer", name))
+}
+
+fn f32_field_or(value: &Value, name: &str, default: f32) -> ParseResult<f32> {
+    match value.get(name) {
+        Some(number) => number
+            .as_f64()
+            .map(|n| n as f32)
+            .ok_or(format!("{:?} is not a number", name)),
+        None => Ok(default),
+    }
+}
+
+fn bool_field_or(value: &Value, name: &str, default: bool) -> ParseResult<bool> {
+    match value.get(name) {
+        Some(boolean) => boolean
+            .as_bool()
+            .ok_or(format!("{:?} is not a boolean", name)),
+        None => Ok(default),
+    }
+}
+
+fn str_field_or<'a>(value: &'a Value, name: &str, default: &'a str) -> &'a str {
+    value.get(name).and_then(Value::as_str).unwrap_or(default)
+}
+
+fn array_field<'a>(value: &'a Value, name: &str) -> ParseResult<&'a Vec<Value>> {
+    match value.get(name) {
+        Some(array) => array
+            .as_array()
+            .ok_or(format!("{:?} is not an array", name)),
+        None => Ok(&EMPTY),
+    }
+}
+
+static EMPTY: Vec<Value> = Vec::new();
+
+fn parse_property_value(
+    property_type: &str,
+    value: &Value,
+    base_dir: &Path,
+) -> ParseResult<TiledProperty> {
+    match (property_type, value) {
+        ("class", Value::Object(members)) => {
+            let mut properties = TiledProperties::new();
+            for (name, member) in members.iter() {
+                properties.insert(name.clone(), parse_untyped_value(member, base_dir)?);
+            }
+            Ok(TiledProperty::Class(properties))
+        }
+        (property_type, Value::String(value)) => parse_property(property_type, value, base_dir),
+        (property_type, value) => parse_property(property_type, &value.to_string(), base_dir),
+    }
+}
+
+/// Class members do not record their type, so it is guessed from the JSON value
+fn parse_untyped_value(value: &Value, base_dir: &Path) -> ParseResult<TiledProperty> {
+    Ok(match value {
+        Value::Bool(boolean) => TiledProperty::Bool(*boolean),
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => TiledProperty::Int(int),
+            None => TiledProperty::Float(number.as_f64().unwrap_or(0.)),
+        },
+        Value::Object(_) => parse_property_value("class", value, base_dir)?,
+        Value::String(string) => TiledProperty::String(string.clone()),
+        _ => TiledProperty::String(value.to_string()),
+    })
+}
+
+fn parse_properties(value: &Value, base_dir: &Path) -> ParseResult<TiledProperties> {
+    let mut properties = TiledProperties::new();
+    for property in array_field(value, "properties")? {
+        let name = field(property, "name")?
+            .as_str()
+            .ok_or("property name is not a string")?;
+        let property_type = str_field_or(property, "type", "string");
+        let value = parse_property_value(property_type, field(property, "value")?, base_dir)?;
+        properties.insert(name.to_owned(), value);
+    }
+    Ok(properties)
+}
+
+fn parse_tileset(value: &Value, first_gid: u32, base_dir: &Path) -> ParseResult<TiledTileset> {
+    let image = value
+        .get("image")
+        .and_then(Value::as_str)
+        .ok_or("only tilesets made from a single image are supported")?;
+    let tile_width = u32_field(value, "tilewidth")?;
+    let columns = tileset_columns(u32_field_or(value, "columns", 0)?, tile_width, || {
+        u32_field(value, "imagewidth")
+    })?;
+
+    Ok(TiledTileset {
+        first_gid,
+        name: str_field_or(value, "name", "").to_owned(),
+        image: base_dir.join(image),
+        tile_width,
+        tile_height: u32_field(value, "tileheight")?,
+        columns,
+        tile_count: u32_field(value, "tilecount")?,
+        spacing: u32_field_or(value, "spacing", 0)?,
+        margin: u32_field_or(value, "margin", 0)?,
+        properties: parse_properties(value, base_dir)?,
+    })
+}
+
+/// Parses the contents of an external `.tsj` tileset
+pub(crate) fn parse_external_tileset(
+    source: &str,
+    first_gid: u32,
+    base_dir: &Path,
+) -> ParseResult<TiledTileset> {
+    let value: Value = serde_json::from_str(source).map_err(|error| error.to_string())?;
+    parse_tileset(&value, first_gid, base_dir)
+}
+
+/// Parses an entry of a map's `tilesets` array, loading it from an external file if it has
+/// a `source` field
+fn parse_map_tileset(value: &Value, base_dir: &Path) -> ParseResult<TiledTileset> {
+    let first_gid = u32_field(value, "firstgid")?;
+    match value.get("source").and_then(Value::as_str) {
+        Some(source) => {
+            let path = base_dir.join(source);
+            let tileset_dir = path.parent().unwrap_or(base_dir);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|_| format!("could not open tileset {:?}", path))?;
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("tsx") | Some("xml") => {
+                    tmx::parse_external_tileset(&source, first_gid, tileset_dir)
+                }
+                _ => parse_external_tileset(&source, first_gid, tileset_dir),
+            }
+        }
+        None => parse_tileset(value, first_gid, base_dir),
+    }
+}
+
+fn decode_layer_data(value: &Value, data: &Value) -> ParseResult<Vec<u32>> {
+    match data {
+        Value::Array(gids) => gids
+            .iter()
+            .map(|gid| {
+                gid.as_u64()
+                    .map(|gid| gid as u32)
+                    .ok_or("invalid tile".to_owned())
+            })
+            .collect(),
+        Value::String(data) => decode_tile_data(
+            data,
+            Some(str_field_or(value, "encoding", "base64")),
+            value.get("compression").and_then(Value::as_str),
+        ),
+        _ => Err("invalid tile data".to_owned()),
+    }
+}
+
+fn parse_tiles(value: &Value, context: &MapContext) -> ParseResult<Vec<(i32, i32, u32)>> {
+    let mut tiles = Vec::new();
+    if let Some(data) = value.get("data") {
+        let gids = decode_layer_data(value, data)?;
+        push_tiles(&mut tiles, &gids, (0, 0), context.width, context.height);
+    }
+    for chunk in array_field(value, "chunks")? {
+        let gids = decode_layer_data(value, field(chunk, "data")?)?;
+        push_tiles(
+            &mut tiles,
+            &gids,
+            (i32_field(chunk, "x")?, i32_field(chunk, "y")?),
+            u32_field(chunk, "width")?,
+            context.height,
+        );
+    }
+    Ok(tiles)
+}
+
+fn parse_points(points: &[Value]) -> ParseResult<Vec<Vector2>> {
+    points
+        .iter()
+        .map(|point| {
+            Ok(Vector2::new(
+                f32_field_or(point, "x", 0.)?,
+                -f32_field_or(point, "y", 0.)?,
+            ))
+        })
+        .collect()
+}
+
+fn parse_object(value: &Value, offset: &Vector2, context: &MapContext) -> ParseResult<TiledObject> {
+    let width = f32_field_or(value, "width", 0.)?;
+    let height = f32_field_or(value, "height", 0.)?;
+
+    let shape = if let Some(gid) = value.get("gid") {
+        let gid = gid.as_u64().ok_or("invalid object gid")? as u32;
+        TiledShape::Tile {
+            gid: gid & !TiledMap::FLIP_FLAGS,
+            width,
+            height,
+        }
+    } else if bool_field_or(value, "ellipse", false)? {
+        TiledShape::Ellipse { width, height }
+    } else if bool_field_or(value, "point", false)? {
+        TiledShape::Point
+    } else if value.get("polygon").is_some() {
+        TiledShape::Polygon(parse_points(array_field(value, "polygon")?)?)
+    } else if value.get("polyline").is_some() {
+        TiledShape::Polyline(parse_points(array_field(value, "polyline")?)?)
+    } else {
+        TiledShape::Rectangle { width, height }
+    };
+
+    Ok(TiledObject {
+        id: u32_field_or(value, "id", 0)?,
+        name: str_field_or(value, "name", "").to_owned(),
+        class: value
+            .get("class")
+            .or(value.get("type"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_owned(),
+        position: flip_position(
+            f32_field_or(value, "x", 0.)?,
+            f32_field_or(value, "y", 0.)?,
+            context.pixel_height,
+        )
+        .add(offset),
+        rotation: -f32_field_or(value, "rotation", 0.)?.to_radians(),
+        shape,
+        visible: bool_field_or(value, "visible", true)?,
+        properties: parse_properties(value, context.base_dir)?,
+    })
+}
+
+fn parse_layers(
+    values: &[Value],
+    group: GroupState,
+    context: &mut MapContext,
+    layers: &mut Vec<TiledLayer>,
+) -> ParseResult<()> {
+    for value in values {
+        let state = group.nest(
+            Vector2::new(
+                f32_field_or(value, "offsetx", 0.)?,
+                -f32_field_or(value, "offsety", 0.)?,
+            ),
+            f32_field_or(value, "opacity", 1.)?,
+            bool_field_or(value, "visible", true)?,
+        );
+
+        let content = match str_field_or(value, "type", "") {
+            "tilelayer" => TiledLayerContent::Tiles(parse_tiles(value, context)?),
+            "objectgroup" => TiledLayerContent::Objects(
+                array_field(value, "objects")?
+                    .iter()
+                    .map(|object| parse_object(object, &state.offset, context))
+                    .collect::<ParseResult<_>>()?,
+            ),
+            "group" => {
+                parse_layers(array_field(value, "layers")?, state, context, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+
+        layers.push(TiledLayer {
+            name: str_field_or(value, "name", "").to_owned(),
+            z_index: ZIndex::Specific(context.next_z_index),
+            opacity: state.opacity,
+            visible: state.visible,
+            offset: state.offset,
+            properties: parse_properties(value, context.base_dir)?,
+            content,
+        });
+        context.next_z_index += 1;
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_map(source: &str, base_dir: &Path) -> ParseResult<TiledMap> {
+    let map: Value = serde_json::from_str(source).map_err(|error| error.to_string())?;
+    if str_field_or(&map, "orientation", "orthogonal") != "orthogonal" {
+        return Err("only orthogonal maps are supported".to_owned());
+    }
+
+    let width = u32_field(&map, "width")?;
+    let height = u32_field(&map, "height")?;
+    let tile_width = u32_field(&map, "tilewidth")?;
+    let tile_height = u32_field(&map, "tileheight")?;
+
+    let tilesets = array_field(&map, "tilesets")?
+        .iter()
+        .map(|tileset| parse_map_tileset(tileset, base_dir))
+        .collect::<ParseResult<_>>()?;
+
+    let mut context = MapContext::new(base_dir, width, height, tile_height);
+    let mut layers = Vec::new();
+    parse_layers(
+        array_field(&map, "layers")?,
+        GroupState::ROOT,
+        &mut context,
+        &mut layers,
+    )?;
+
+    Ok(TiledMap {
+        width,
+        height,
+        tile_width,
+        tile_height,
+        tilesets,
+        layers,
+        properties: parse_properties(&map, base_dir)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r##"{
+        "orientation": "orthogonal",
+        "width": 2,
+        "height": 2,
+        "tilewidth": 16,
+        "tileheight": 16,
+        "properties": [
+            { "name": "title", "type": "string", "value": "test" },
+            { "name": "gravity", "type": "float", "value": 9.5 },
+            { "name": "tint", "type": "color", "value": "#80ff0000" },
+            { "name": "spawn", "type": "class", "value": { "x": 3, "named": "start" } }
+        ],
+        "tilesets": [{
+            "firstgid": 1,
+            "name": "tiles",
+            "image": "tiles.png",
+            "imagewidth": 32,
+            "tilewidth": 16,
+            "tileheight": 16,
+            "tilecount": 4
+        }],
+        "layers": [
+            { "type": "tilelayer", "name": "ground", "data": [1, 0, 0, 4] },
+            {
+                "type": "group",
+                "offsetx": 4,
+                "offsety": 8,
+                "opacity": 0.5,
+                "layers": [
+                    {
+                        "type": "objectgroup",
+                        "name": "objects",
+                        "offsetx": 1,
+                        "opacity": 0.5,
+                        "objects": [
+                            {
+                                "id": 1, "name": "box", "type": "crate",
+                                "x": 0, "y": 16, "width": 8, "height": 4, "rotation": 90,
+                                "properties": [{ "name": "solid", "type": "bool", "value": true }]
+                            },
+                            {
+                                "id": 2, "x": 2, "y": 2,
+                                "polygon": [
+                                    { "x": 0, "y": 0 }, { "x": 4, "y": 0 }, { "x": 0, "y": 4 }
+                                ]
+                            },
+                            { "id": 3, "x": 0, "y": 0, "point": true }
+                        ]
+                    },
+                    {
+                        "type": "group",
+                        "visible": false,
+                        "layers": [{
+                            "type": "tilelayer",
+                            "name": "hidden",
+                            "chunks": [{ "x": 0, "y": 0, "width": 1, "height": 1, "data": [2] }]
+                        }]
+                    }
+                ]
+            }
+        ]
+    }"##;
+
+    fn parse(source: &str) -> ParseResult<TiledMap> {
+        parse_map(source, Path::new(""))
+    }
+
+    fn tiles(layer: &TiledLayer) -> &[(i32, i32, u32)] {
+        match &layer.content {
+            TiledLayerContent::Tiles(tiles) => tiles,
+            _ => panic!("not a tile layer"),
+        }
+    }
+
+    #[test]
+    fn map() {
+        let map = parse(MAP).unwrap();
+        assert_eq!((map.width, map.height), (2, 2));
+        assert_eq!((map.tile_width, map.tile_height), (16, 16));
+        assert!(
+            matches!(map.properties["title"], TiledProperty::String(ref title) if title == "test")
+        );
+        assert!(
+            matches!(map.properties["gravity"], TiledProperty::Float(gravity) if gravity == 9.5)
+        );
+        match map.properties["tint"] {
+            TiledProperty::Color(tint) => assert_eq!(tint.as_rgba8(), [255, 0, 0, 128]),
+            ref property => panic!("expected a color, not {:?}", property),
+        }
+        match &map.properties["spawn"] {
+            TiledProperty::Class(members) => {
+                assert!(matches!(members["x"], TiledProperty::Int(3)));
+                assert!(
+                    matches!(members["named"], TiledProperty::String(ref name) if name == "start")
+                );
+            }
+            property => panic!("expected a class, not {:?}", property),
+        }
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.columns, 2);
+        assert_eq!(tileset.rows(), 2);
+        assert_eq!(tileset.image, Path::new("tiles.png"));
+    }
+
+    #[test]
+    fn layers() {
+        let map = parse(MAP).unwrap();
+        assert_eq!(map.layers.len(), 3);
+
+        let ground = &map.layers[0];
+        assert_eq!(ground.name, "ground");
+        assert_eq!(ground.z_index, ZIndex::Specific(0));
+        assert_eq!(tiles(ground), &[(0, 1, 1), (1, 0, 4)]);
+
+        let objects = &map.layers[1];
+        assert_eq!(objects.z_index, ZIndex::Specific(1));
+        assert_eq!(objects.offset, Vector2::new(5., -8.));
+        assert_eq!(objects.opacity, 0.25);
+        assert!(objects.visible);
+
+        let hidden = &map.layers[2];
+        assert_eq!(hidden.z_index, ZIndex::Specific(2));
+        assert!(!hidden.visible);
+        assert_eq!(tiles(hidden), &[(0, 1, 2)]);
+    }
+
+    #[test]
+    fn objects() {
+        let map = parse(MAP).unwrap();
+        let objects = match &map.layers[1].content {
+            TiledLayerContent::Objects(objects) => objects,
+            _ => panic!("not an object layer"),
+        };
+
+        let crate_box = &objects[0];
+        assert_eq!((crate_box.id, crate_box.name.as_str()), (1, "box"));
+        assert_eq!(crate_box.class, "crate");
+        assert_eq!(crate_box.position, Vector2::new(5., 8.));
+        assert_eq!(crate_box.rotation, -90f32.to_radians());
+        match crate_box.shape {
+            TiledShape::Rectangle { width, height } => assert_eq!((width, height), (8., 4.)),
+            ref shape => panic!("expected a rectangle, not {:?}", shape),
+        }
+        assert!(matches!(
+            crate_box.properties["solid"],
+            TiledProperty::Bool(true)
+        ));
+
+        match &objects[1].shape {
+            TiledShape::Polygon(points) => assert_eq!(
+                points,
+                &[Vector2::ZERO, Vector2::new(4., 0.), Vector2::new(0., -4.)]
+            ),
+            shape => panic!("expected a polygon, not {:?}", shape),
+        }
+        assert!(matches!(objects[2].shape, TiledShape::Point));
+    }
+
+    #[test]
+    fn malformed_maps_are_errors() {
+        let zero_tile_width = MAP.replace(
+            r#""tilewidth": 16,
+            "tileheight""#,
+            r#""tilewidth": 0,
+            "tileheight""#,
+        );
+        assert_ne!(zero_tile_width, MAP);
+        assert_eq!(
+            parse(&zero_tile_width).unwrap_err(),
+            "tileset has no tile width"
+        );
+
+        let bad_color = MAP.replace("#80ff0000", "#1€2a");
+        assert!(parse(&bad_color).is_err());
+
+        let bad_tile = MAP.replace("[1, 0, 0, 4]", "[1, -1, 0, 4]");
+        assert!(parse(&bad_tile).is_err());
+
+        assert!(parse(&MAP.replace(r#""orthogonal""#, r#""isometric""#)).is_err());
+        assert!(parse("{").is_err());
+        assert!(parse("{}").is_err());
+    }
+}
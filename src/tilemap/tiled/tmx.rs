@@ -0,0 +1,431 @@
+use crate::*;
+use roxmltree::{Document, Node};
+use std::{path::Path, str::FromStr};
+
+fn attribute<'a>(node: &Node<'a, '_>, name: &str) -> ParseResult<&'a str> {
+    node.attribute(name).ok_or(format!(
+        "<{}> is missing the attribute {:?}",
+        node.tag_name().name(),
+        name
+    ))
+}
+
+fn parse_attribute<T: FromStr>(node: &Node, name: &str) -> ParseResult<T> {
+    attribute(node, name)?
+        .parse()
+        .map_err(|_| format!("invalid value for {:?}", name))
+}
+
+fn parse_attribute_or<T: FromStr>(node: &Node, name: &str, default: T) -> ParseResult<T> {
+    match node.attribute(name) {
+        Some(_) => parse_attribute(node, name),
+        None => Ok(default),
+    }
+}
+
+fn children<'a, 'input>(
+    node: &Node<'a, 'input>,
+    tag_name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == tag_name)
+}
+
+fn parse_properties(node: &Node, base_dir: &Path) -> ParseResult<TiledProperties> {
+    let mut properties = TiledProperties::new();
+    for properties_node in children(node, "properties") {
+        for property in children(&properties_node, "property") {
+            let name = attribute(&property, "name")?.to_owned();
+            let property_type = property.attribute("type").unwrap_or("string");
+            let value = if property_type == "class" {
+                TiledProperty::Class(parse_properties(&property, base_dir)?)
+            } else {
+                let value = property
+                    .attribute("value")
+                    .or(property.text())
+                    .unwrap_or("");
+                parse_property(property_type, value, base_dir)?
+            };
+            properties.insert(name, value);
+        }
+    }
+    Ok(properties)
+}
+
+fn parse_tileset(node: &Node, first_gid: u32, base_dir: &Path) -> ParseResult<TiledTileset> {
+    let image = children(node, "image")
+        .next()
+        .ok_or("only tilesets made from a single image are supported")?;
+    let tile_width = parse_attribute(node, "tilewidth")?;
+    let tile_count = parse_attribute(node, "tilecount")?;
+    let columns = tileset_columns(parse_attribute_or(node, "columns", 0)?, tile_width, || {
+        parse_attribute(&image, "width")
+    })?;
+
+    Ok(TiledTileset {
+        first_gid,
+        name: node.attribute("name").unwrap_or("").to_owned(),
+        image: base_dir.join(attribute(&image, "source")?),
+        tile_width,
+        tile_height: parse_attribute(node, "tileheight")?,
+        columns,
+        tile_count,
+        spacing: parse_attribute_or(node, "spacing", 0)?,
+        margin: parse_attribute_or(node, "margin", 0)?,
+        properties: parse_properties(node, base_dir)?,
+    })
+}
+
+/// Parses the contents of an external `.tsx` tileset
+pub(crate) fn parse_external_tileset(
+    source: &str,
+    first_gid: u32,
+    base_dir: &Path,
+) -> ParseResult<TiledTileset> {
+    let document = Document::parse(source).map_err(|error| error.to_string())?;
+    parse_tileset(&document.root_element(), first_gid, base_dir)
+}
+
+/// Parses a `<tileset>` element, loading it from an external `.tsx` file if it has a
+/// `source` attribute
+fn parse_map_tileset(node: &Node, base_dir: &Path) -> ParseResult<TiledTileset> {
+    let first_gid = parse_attribute(node, "firstgid")?;
+    match node.attribute("source") {
+        Some(source) => {
+            let path = base_dir.join(source);
+            let tileset_dir = path.parent().unwrap_or(base_dir);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|_| format!("could not open tileset {:?}", path))?;
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("tsj") | Some("json") => {
+                    tmj::parse_external_tileset(&source, first_gid, tileset_dir)
+                }
+                _ => parse_external_tileset(&source, first_gid, tileset_dir),
+            }
+        }
+        None => parse_tileset(node, first_gid, base_dir),
+    }
+}
+
+fn parse_tiles(node: &Node, context: &MapContext) -> ParseResult<Vec<(i32, i32, u32)>> {
+    let mut tiles = Vec::new();
+    let data = match children(node, "data").next() {
+        Some(data) => data,
+        None => return Ok(tiles),
+    };
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+
+    let chunks: Vec<Node> = children(&data, "chunk").collect();
+    if !chunks.is_empty() {
+        for chunk in chunks {
+            let gids = decode_tile_data(chunk.text().unwrap_or(""), encoding, compression)?;
+            push_tiles(
+                &mut tiles,
+                &gids,
+                (parse_attribute(&chunk, "x")?, parse_attribute(&chunk, "y")?),
+                parse_attribute(&chunk, "width")?,
+                context.height,
+            );
+        }
+    } else if encoding.is_none() {
+        let gids = children(&data, "tile")
+            .map(|tile| parse_attribute_or(&tile, "gid", 0))
+            .collect::<ParseResult<Vec<u32>>>()?;
+        push_tiles(&mut tiles, &gids, (0, 0), context.width, context.height);
+    } else {
+        let gids = decode_tile_data(data.text().unwrap_or(""), encoding, compression)?;
+        push_tiles(&mut tiles, &gids, (0, 0), context.width, context.height);
+    }
+    Ok(tiles)
+}
+
+fn parse_points(points: &str) -> ParseResult<Vec<Vector2>> {
+    points
+        .split_whitespace()
+        .map(|point| {
+            let mut coordinates = point.split(',').map(str::parse::<f32>);
+            match (coordinates.next(), coordinates.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => Ok(Vector2::new(x, -y)),
+                _ => Err(format!("invalid point {:?}", point)),
+            }
+        })
+        .collect()
+}
+
+fn parse_object(node: &Node, offset: &Vector2, context: &MapContext) -> ParseResult<TiledObject> {
+    let width = parse_attribute_or(node, "width", 0.)?;
+    let height = parse_attribute_or(node, "height", 0.)?;
+
+    let shape = if let Some(gid) = node.attribute("gid") {
+        let gid: u32 = gid.parse().map_err(|_| "invalid object gid")?;
+        TiledShape::Tile {
+            gid: gid & !TiledMap::FLIP_FLAGS,
+            width,
+            height,
+        }
+    } else if children(node, "ellipse").next().is_some() {
+        TiledShape::Ellipse { width, height }
+    } else if children(node, "point").next().is_some() {
+        TiledShape::Point
+    } else if let Some(polygon) = children(node, "polygon").next() {
+        TiledShape::Polygon(parse_points(attribute(&polygon, "points")?)?)
+    } else if let Some(polyline) = children(node, "polyline").next() {
+        TiledShape::Polyline(parse_points(attribute(&polyline, "points")?)?)
+    } else {
+        TiledShape::Rectangle { width, height }
+    };
+
+    Ok(TiledObject {
+        id: parse_attribute_or(node, "id", 0)?,
+        name: node.attribute("name").unwrap_or("").to_owned(),
+        class: node
+            .attribute("class")
+            .or(node.attribute("type"))
+            .unwrap_or("")
+            .to_owned(),
+        position: flip_position(
+            parse_attribute_or(node, "x", 0.)?,
+            parse_attribute_or(node, "y", 0.)?,
+            context.pixel_height,
+        )
+        .add(offset),
+        rotation: -parse_attribute_or::<f32>(node, "rotation", 0.)?.to_radians(),
+        shape,
+        visible: parse_attribute_or(node, "visible", 1)? != 0,
+        properties: parse_properties(node, context.base_dir)?,
+    })
+}
+
+fn parse_layers(
+    node: &Node,
+    group: GroupState,
+    context: &mut MapContext,
+    layers: &mut Vec<TiledLayer>,
+) -> ParseResult<()> {
+    for child in node.children().filter(Node::is_element) {
+        let state = group.nest(
+            Vector2::new(
+                parse_attribute_or(&child, "offsetx", 0.)?,
+                -parse_attribute_or::<f32>(&child, "offsety", 0.)?,
+            ),
+            parse_attribute_or(&child, "opacity", 1.)?,
+            parse_attribute_or(&child, "visible", 1)? != 0,
+        );
+
+        let content = match child.tag_name().name() {
+            "layer" => TiledLayerContent::Tiles(parse_tiles(&child, context)?),
+            "objectgroup" => TiledLayerContent::Objects(
+                children(&child, "object")
+                    .map(|object| parse_object(&object, &state.offset, context))
+                    .collect::<ParseResult<_>>()?,
+            ),
+            "group" => {
+                parse_layers(&child, state, context, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+
+        layers.push(TiledLayer {
+            name: child.attribute("name").unwrap_or("").to_owned(),
+            z_index: ZIndex::Specific(context.next_z_index),
+            opacity: state.opacity,
+            visible: state.visible,
+            offset: state.offset,
+            properties: parse_properties(&child, context.base_dir)?,
+            content,
+        });
+        context.next_z_index += 1;
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_map(source: &str, base_dir: &Path) -> ParseResult<TiledMap> {
+    let document = Document::parse(source).map_err(|error| error.to_string())?;
+    let map = document.root_element();
+    if map.tag_name().name() != "map" {
+        return Err("the root element is not <map>".to_owned());
+    }
+    if map.attribute("orientation").unwrap_or("orthogonal") != "orthogonal" {
+        return Err("only orthogonal maps are supported".to_owned());
+    }
+
+    let width = parse_attribute(&map, "width")?;
+    let height = parse_attribute(&map, "height")?;
+    let tile_width = parse_attribute(&map, "tilewidth")?;
+    let tile_height = parse_attribute(&map, "tileheight")?;
+
+    let tilesets = children(&map, "tileset")
+        .map(|tileset| parse_map_tileset(&tileset, base_dir))
+        .collect::<ParseResult<_>>()?;
+
+    let mut context = MapContext::new(base_dir, width, height, tile_height);
+    let mut layers = Vec::new();
+    parse_layers(&map, GroupState::ROOT, &mut context, &mut layers)?;
+
+    Ok(TiledMap {
+        width,
+        height,
+        tile_width,
+        tile_height,
+        tilesets,
+        layers,
+        properties: parse_properties(&map, base_dir)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r##"
+        <map orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+          <properties>
+            <property name="title" value="test"/>
+            <property name="gravity" type="float" value="9.5"/>
+            <property name="tint" type="color" value="#80ff0000"/>
+          </properties>
+          <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="4">
+            <image source="tiles.png" width="32" height="32"/>
+          </tileset>
+          <layer name="ground" width="2" height="2">
+            <data encoding="csv">1,0,0,4</data>
+          </layer>
+          <group name="group" offsetx="4" offsety="8" opacity="0.5">
+            <objectgroup name="objects" offsetx="1" opacity="0.5">
+              <object id="1" name="box" type="crate" x="0" y="16" width="8" height="4"
+                      rotation="90">
+                <properties>
+                  <property name="solid" type="bool" value="true"/>
+                </properties>
+              </object>
+              <object id="2" x="2" y="2">
+                <polygon points="0,0 4,0 0,4"/>
+              </object>
+              <object id="3" x="0" y="0"><point/></object>
+            </objectgroup>
+            <group visible="0">
+              <layer name="hidden" width="2" height="2">
+                <data><tile gid="2"/></data>
+              </layer>
+            </group>
+          </group>
+        </map>
+    "##;
+
+    fn parse(source: &str) -> ParseResult<TiledMap> {
+        parse_map(source, Path::new(""))
+    }
+
+    fn tiles(layer: &TiledLayer) -> &[(i32, i32, u32)] {
+        match &layer.content {
+            TiledLayerContent::Tiles(tiles) => tiles,
+            _ => panic!("not a tile layer"),
+        }
+    }
+
+    #[test]
+    fn map() {
+        let map = parse(MAP).unwrap();
+        assert_eq!((map.width, map.height), (2, 2));
+        assert_eq!((map.tile_width, map.tile_height), (16, 16));
+        assert!(
+            matches!(map.properties["title"], TiledProperty::String(ref title) if title == "test")
+        );
+        assert!(
+            matches!(map.properties["gravity"], TiledProperty::Float(gravity) if gravity == 9.5)
+        );
+        match map.properties["tint"] {
+            TiledProperty::Color(tint) => assert_eq!(tint.as_rgba8(), [255, 0, 0, 128]),
+            ref property => panic!("expected a color, not {:?}", property),
+        }
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.columns, 2);
+        assert_eq!(tileset.rows(), 2);
+        assert_eq!(tileset.image, Path::new("tiles.png"));
+        assert!(tileset.contains(4) && !tileset.contains(5));
+    }
+
+    #[test]
+    fn layers() {
+        let map = parse(MAP).unwrap();
+        assert_eq!(map.layers.len(), 3);
+
+        let ground = &map.layers[0];
+        assert_eq!(ground.name, "ground");
+        assert_eq!(ground.z_index, ZIndex::Specific(0));
+        assert_eq!(tiles(ground), &[(0, 1, 1), (1, 0, 4)]);
+
+        let objects = &map.layers[1];
+        assert_eq!(objects.z_index, ZIndex::Specific(1));
+        assert_eq!(objects.offset, Vector2::new(5., -8.));
+        assert_eq!(objects.opacity, 0.25);
+        assert!(objects.visible);
+
+        let hidden = &map.layers[2];
+        assert_eq!(hidden.z_index, ZIndex::Specific(2));
+        assert!(!hidden.visible);
+        assert_eq!(tiles(hidden), &[(0, 1, 2)]);
+    }
+
+    #[test]
+    fn objects() {
+        let map = parse(MAP).unwrap();
+        let objects = match &map.layers[1].content {
+            TiledLayerContent::Objects(objects) => objects,
+            _ => panic!("not an object layer"),
+        };
+
+        let crate_box = &objects[0];
+        assert_eq!((crate_box.id, crate_box.name.as_str()), (1, "box"));
+        assert_eq!(crate_box.class, "crate");
+        assert_eq!(crate_box.position, Vector2::new(5., 8.));
+        assert_eq!(crate_box.rotation, -90f32.to_radians());
+        match crate_box.shape {
+            TiledShape::Rectangle { width, height } => assert_eq!((width, height), (8., 4.)),
+            ref shape => panic!("expected a rectangle, not {:?}", shape),
+        }
+        assert!(matches!(
+            crate_box.properties["solid"],
+            TiledProperty::Bool(true)
+        ));
+
+        match &objects[1].shape {
+            TiledShape::Polygon(points) => assert_eq!(
+                points,
+                &[Vector2::ZERO, Vector2::new(4., 0.), Vector2::new(0., -4.)]
+            ),
+            shape => panic!("expected a polygon, not {:?}", shape),
+        }
+        assert!(matches!(objects[2].shape, TiledShape::Point));
+        assert!(objects[2].mesh().is_none());
+    }
+
+    #[test]
+    fn malformed_maps_are_errors() {
+        let zero_tile_width = MAP.replacen(
+            r#"tilewidth="16" tileheight="16" tilecount"#,
+            r#"tilewidth="0" tileheight="16" tilecount"#,
+            1,
+        );
+        assert_eq!(
+            parse(&zero_tile_width).unwrap_err(),
+            "tileset has no tile width"
+        );
+
+        let bad_color = MAP.replace("#80ff0000", "#1€2a");
+        assert!(parse(&bad_color).is_err());
+
+        let bad_tile = MAP.replace("1,0,0,4", "1,x,0,4");
+        assert!(parse(&bad_tile).is_err());
+
+        assert!(
+            parse(&MAP.replace(r#"orientation="orthogonal""#, r#"orientation="isometric""#))
+                .is_err()
+        );
+        assert!(parse("<map").is_err());
+        assert!(parse("<tileset/>").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::*;
+use std::collections::HashMap;
+
+/// Describes how a `Tilemap` should be configured
+pub struct TilemapConfiguration<'a> {
+    /// A label used for debugging
+    pub label: Option<&'a str>,
+    /// The tileset that tile IDs refer to
+    pub tileset: Tileset,
+    /// The width and height of a single tile, in the coordinate system of the window
+    pub tile_size: Vector2,
+    /// The position of the bottom left corner of the tile at `(0, 0)`
+    pub origin: Vector2,
+    /// Same as the `z_index` attribute on `SpriteConfiguration`
+    pub z_index: ZIndex,
+    /// Same as the `opacity` attribute on `SpriteConfiguration`
+    pub opacity: f32,
+}
+
+/// The size and position of the tiles in a `Tilemap`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TileGrid {
+    pub(crate) tile_size: Vector2,
+    /// The position of the bottom left corner of the tile at `(0, 0)`
+    pub(crate) origin: Vector2,
+}
+
+impl TileGrid {
+    pub(crate) fn world_to_tile(&self, position: &Vector2) -> (i32, i32) {
+        let relative_position = position.sub(&self.origin);
+        (
+            (relative_position.x / self.tile_size.x).floor() as i32,
+            (relative_position.y / self.tile_size.y).floor() as i32,
+        )
+    }
+
+    pub(crate) fn tile_to_world(&self, x: i32, y: i32) -> Vector2 {
+        Vector2::new(x as f32 * self.tile_size.x, y as f32 * self.tile_size.y).add(&self.origin)
+    }
+
+    pub(crate) fn tile_center(&self, x: i32, y: i32) -> Vector2 {
+        self.tile_to_world(x, y).add(&self.tile_size.scale(0.5))
+    }
+
+    pub(crate) fn tile_bounds(&self, x: i32, y: i32) -> Bounds {
+        let bottom_left = self.tile_to_world(x, y);
+        Bounds {
+            bottom_left,
+            top_right: bottom_left.add(&self.tile_size),
+        }
+    }
+
+    /// Returns the region covered by the chunk at `(chunk_x, chunk_y)`
+    pub(crate) fn chunk_bounds(&self, chunk_x: i32, chunk_y: i32) -> Bounds {
+        let size = Chunk::SIZE as i32;
+        let bottom_left = self.tile_to_world(chunk_x * size, chunk_y * size);
+        Bounds {
+            bottom_left,
+            top_right: bottom_left.add(&self.tile_size.scale(Chunk::SIZE as f32)),
+        }
+    }
+}
+
+/// An unbounded grid of tiles, drawn from a `Tileset`.
+///
+/// Tiles are grouped into square chunks that are each drawn with a single mesh. A chunk's
+/// mesh is only rebuilt when one of its tiles changes, and chunks outside of the window
+/// bounds are not drawn at all.
+#[derive(Debug)]
+pub struct Tilemap {
+    pub(crate) label: Option<Box<str>>,
+    pub(crate) tileset: Tileset,
+    pub(crate) grid: TileGrid,
+    pub(crate) z_index: ZIndex,
+    pub(crate) opacity: f32,
+    pub(crate) chunks: HashMap<(i32, i32), Chunk>,
+}
+
+impl Tilemap {
+    /// Creates a new, empty tilemap
+    pub fn new(config: TilemapConfiguration) -> Self {
+        Self {
+            label: config.label.map(|label| label.to_owned().into_boxed_str()),
+            tileset: config.tileset,
+            grid: TileGrid {
+                tile_size: config.tile_size,
+                origin: config.origin,
+            },
+            z_index: config.z_index,
+            opacity: config.opacity,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Sets the tile at `(x, y)`. `None` removes the tile.
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Option<TileId>) {
+        if let Some(tile) = tile {
+            if tile >= self.tileset.tile_count() {
+                panic!(
+                    "Tile {} does not exist in a tileset with {} tiles",
+                    tile,
+                    self.tileset.tile_count()
+                );
+            }
+        }
+
+        let (chunk_position, (tile_x, tile_y)) = Chunk::locate(x, y);
+        match self.chunks.get_mut(&chunk_position) {
+            Some(chunk) => {
+                chunk.set(tile_x, tile_y, tile);
+                if chunk.is_empty() {
+                    self.chunks.remove(&chunk_position);
+                }
+            }
+            None if tile.is_some() => {
+                let mut chunk = Chunk::new();
+                chunk.set(tile_x, tile_y, tile);
+                self.chunks.insert(chunk_position, chunk);
+            }
+            None => {}
+        }
+    }
+
+    /// Returns the tile at `(x, y)`, or `None` if there is no tile there
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<TileId> {
+        let (chunk_position, (tile_x, tile_y)) = Chunk::locate(x, y);
+        self.chunks
+            .get(&chunk_position)
+            .and_then(|chunk| chunk.tiles[tile_y][tile_x])
+    }
+
+    /// Removes every tile from this tilemap
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Returns the position of the tile that contains `position`
+    pub fn world_to_tile(&self, position: &Vector2) -> (i32, i32) {
+        self.grid.world_to_tile(position)
+    }
+
+    /// Returns the position of the bottom left corner of the tile at `(x, y)`
+    pub fn tile_to_world(&self, x: i32, y: i32) -> Vector2 {
+        self.grid.tile_to_world(x, y)
+    }
+
+    /// Returns the position of the center of the tile at `(x, y)`
+    pub fn tile_center(&self, x: i32, y: i32) -> Vector2 {
+        self.grid.tile_center(x, y)
+    }
+
+    /// Returns the region covered by the tile at `(x, y)`
+    pub fn tile_bounds(&self, x: i32, y: i32) -> Bounds {
+        self.grid.tile_bounds(x, y)
+    }
+
+    /// Returns the width and height of a single tile
+    pub fn tile_size(&self) -> Vector2 {
+        self.grid.tile_size
+    }
+
+    /// Returns a reference to the tileset used by this tilemap
+    pub fn tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    /// Returns a `RenderBundle` for each chunk that is visible in the window, rebuilding
+    /// any chunk meshes that have changed. Draw these with `Oge::draw`.
+    pub fn render_bundles(&mut self, oge: &Oge) -> Vec<RenderBundle> {
+        let window_bounds = oge.window_bounds();
+
+        let mut render_bundles = Vec::new();
+        for (&(chunk_x, chunk_y), chunk) in self.chunks.iter_mut() {
+            let chunk_bounds = self.grid.chunk_bounds(chunk_x, chunk_y);
+            if !chunk_bounds.intersects(&window_bounds) {
+                continue;
+            }
+
+            let mesh = chunk.mesh(&self.tileset, &self.grid.tile_size, chunk_bounds.bottom_left);
+            render_bundles.push(Sprite::create_render_bundle(
+                oge,
+                self.label.as_deref(),
+                mesh,
+                &self.tileset.texture,
+                None,
+                self.opacity,
+                self.z_index,
+            ));
+        }
+        render_bundles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn grid() -> TileGrid {
+        TileGrid {
+            tile_size: Vector2::new(2., 0.5),
+            origin: Vector2::new(-1., 3.),
+        }
+    }
+
+    #[test]
+    fn world_to_tile() {
+        let grid = grid();
+        assert_eq!(grid.world_to_tile(&Vector2::new(-1., 3.)), (0, 0));
+        assert_eq!(grid.world_to_tile(&Vector2::new(0.9, 3.4)), (0, 0));
+        assert_eq!(grid.world_to_tile(&Vector2::new(1., 3.5)), (1, 1));
+        assert_eq!(grid.world_to_tile(&Vector2::new(6., 8.25)), (3, 10));
+    }
+
+    #[test]
+    fn world_to_tile_rounds_negative_positions_down() {
+        let grid = grid();
+        assert_eq!(grid.world_to_tile(&Vector2::new(-1.1, 2.9)), (-1, -1));
+        assert_eq!(grid.world_to_tile(&Vector2::new(-3., 2.5)), (-1, -1));
+        assert_eq!(grid.world_to_tile(&Vector2::new(-3.1, 2.4)), (-2, -2));
+        assert_eq!(grid.world_to_tile(&Vector2::new(-10., 0.)), (-5, -6));
+    }
+
+    #[test]
+    fn tile_to_world_round_trips() {
+        let grid = grid();
+        for &(x, y) in &[(0, 0), (3, -7), (-16, 15), (-1, 16), (17, -17)] {
+            let bottom_left = grid.tile_to_world(x, y);
+            assert_eq!(grid.world_to_tile(&grid.tile_center(x, y)), (x, y));
+            assert_eq!(grid.world_to_tile(&bottom_left), (x, y));
+
+            let bounds = grid.tile_bounds(x, y);
+            assert!(bounds.bottom_left.approx_eq(&bottom_left, EPSILON));
+            assert!(bounds.top_right.approx_eq(&grid.tile_to_world(x + 1, y + 1), EPSILON));
+            assert!(bounds.center().approx_eq(&grid.tile_center(x, y), EPSILON));
+        }
+    }
+
+    #[test]
+    fn chunk_bounds() {
+        let grid = grid();
+        let bounds = grid.chunk_bounds(0, 0);
+        assert!(bounds.bottom_left.approx_eq(&grid.origin, EPSILON));
+        assert!(bounds.top_right.approx_eq(&Vector2::new(31., 11.), EPSILON));
+
+        let bounds = grid.chunk_bounds(-1, 2);
+        assert!(bounds.bottom_left.approx_eq(&Vector2::new(-33., 19.), EPSILON));
+        assert!(bounds.top_right.approx_eq(&Vector2::new(-1., 27.), EPSILON));
+
+        // every tile lies within the bounds of the chunk it is stored in
+        for &(x, y) in &[(0, 0), (15, 15), (16, -1), (-16, -17), (-17, 31)] {
+            let ((chunk_x, chunk_y), _) = Chunk::locate(x, y);
+            let bounds = grid.chunk_bounds(chunk_x, chunk_y);
+            assert!(bounds.contains(&grid.tile_center(x, y)));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::*;
+use std::rc::Rc;
+
+/// Identifies a tile in a `Tileset`. Tiles are numbered left to right, then top to bottom,
+/// starting from `0`
+pub type TileId = u32;
+
+/// Describes how a `Tileset` should be configured
+pub struct TilesetConfiguration {
+    /// The texture containing every tile, laid out in a grid
+    pub texture: Texture,
+    /// The number of tiles in each row of `texture`
+    pub columns: u32,
+    /// The number of tiles in each column of `texture`
+    pub rows: u32,
+}
+
+/// A texture split into a grid of equally sized tiles. Clones share the same texture.
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    pub(crate) texture: Rc<Texture>,
+    pub(crate) columns: u32,
+    pub(crate) rows: u32,
+}
+
+impl Tileset {
+    /// Creates a new tileset. Panics if `columns` or `rows` is `0`
+    pub fn new(config: TilesetConfiguration) -> Self {
+        if config.columns == 0 || config.rows == 0 {
+            panic!("A tileset must have at least one row and one column");
+        }
+        Self {
+            texture: Rc::new(config.texture),
+            columns: config.columns,
+            rows: config.rows,
+        }
+    }
+
+    /// Returns the number of tiles in this tileset
+    pub fn tile_count(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// Returns a reference to the texture containing every tile
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the texture coordinates of the top left and bottom right corners of `tile`
+    pub(crate) fn texture_coordinates(&self, tile: TileId) -> (Vector2, Vector2) {
+        let (column, row) = (tile % self.columns, tile / self.columns);
+        let (tile_width, tile_height) = (1. / self.columns as f32, 1. / self.rows as f32);
+        let top_left = Vector2::new(column as f32 * tile_width, row as f32 * tile_height);
+        (
+            top_left,
+            top_left.add(&Vector2::new(tile_width, tile_height)),
+        )
+    }
+}
@@ -0,0 +1,38 @@
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] color: vec4<f32>;
+};
+
+// Only the parts of the buffer that debug shapes use are declared, the layout matches
+// `shader.wgsl`
+[[block]] struct UniformBuffer {
+    [[size(48)]] affine2: mat3x2<f32>;
+    [[size(48)]] model_affine2: mat3x2<f32>;
+    opacity: f32;
+};
+
+// Bind Group
+
+[[group(0), binding(2)]]
+var<uniform> uniform_buffer: UniformBuffer;
+
+// Entry Points
+
+[[stage(vertex)]]
+fn main(vertex_input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = vertex_input.color;
+    let position = vec3<f32>(vertex_input.position.x, vertex_input.position.y, 1.0);
+    out.clip_position = vec4<f32>(uniform_buffer.affine2 * position, 1.0, 1.0);
+    return out;
+}
+
+[[stage(fragment)]]
+fn main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return in.color * uniform_buffer.opacity;
+}
@@ -0,0 +1,60 @@
+struct VertexInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] texture_coordinates: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
+};
+
+struct InstanceInput {
+    [[location(3)]] center: vec2<f32>;
+    // x is the size, y is the rotation in radians
+    [[location(4)]] size_rotation: vec2<f32>;
+    [[location(5)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] texture_coordinates: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+// Only the parts of the buffer that particles use are declared, the layout matches
+// `shader.wgsl`
+[[block]] struct UniformBuffer {
+    [[size(48)]] affine2: mat3x2<f32>;
+    [[size(48)]] model_affine2: mat3x2<f32>;
+    opacity: f32;
+};
+
+// Bind Group
+
+[[group(0), binding(0)]]
+var texture: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var sampler_type: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> uniform_buffer: UniformBuffer;
+
+// Entry Points
+
+[[stage(vertex)]]
+fn main(vertex_input: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.texture_coordinates = vertex_input.texture_coordinates;
+    out.color = vertex_input.color * instance.color;
+
+    let scaled = vertex_input.position * instance.size_rotation.x;
+    let c = cos(instance.size_rotation.y);
+    let s = sin(instance.size_rotation.y);
+    let rotated = vec2<f32>(scaled.x * c - scaled.y * s, scaled.x * s + scaled.y * c);
+    let position = vec3<f32>(rotated + instance.center, 1.0);
+    out.clip_position = vec4<f32>(uniform_buffer.affine2 * position, 1.0, 1.0);
+    return out;
+}
+
+[[stage(fragment)]]
+fn main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let texture_sample = textureSample(texture, sampler_type, in.texture_coordinates);
+    return texture_sample * in.color * uniform_buffer.opacity;
+}
@@ -1,16 +1,34 @@
 struct VertexInput {
     [[location(0)]] position: vec2<f32>;
     [[location(1)]] texture_coordinates: vec2<f32>;
+    [[location(2)]] color: vec4<f32>;
 };
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] texture_coordinates: vec2<f32>;
+    [[location(1)]] world_position: vec2<f32>;
+    // tangent and bitangent, taken from the sprite's `Affine2`
+    [[location(2)]] tangent: vec2<f32>;
+    [[location(3)]] bitangent: vec2<f32>;
+    [[location(4)]] color: vec4<f32>;
+};
+
+struct Light {
+    position: vec2<f32>;
+    height: f32;
+    radius: f32;
+    color: vec4<f32>;
 };
 
 [[block]] struct UniformBuffer {
     [[size(48)]] affine2: mat3x2<f32>;
+    [[size(48)]] model_affine2: mat3x2<f32>;
     opacity: f32;
+    light_count: u32;
+    normal_mapped: u32;
+    ambient_light: vec4<f32>;
+    lights: [[stride(32)]] array<Light, 8>;
 };
 
 // Bind Group
@@ -24,19 +42,55 @@ var sampler_type: sampler;
 [[group(0), binding(2)]]
 var<uniform> uniform_buffer: UniformBuffer;
 
+[[group(0), binding(3)]]
+var normal_map: texture_2d<f32>;
+
+[[group(0), binding(4)]]
+var normal_map_sampler: sampler;
+
 // Entry Points
 
 [[stage(vertex)]]
 fn main(vertex_input: VertexInput) -> VertexOutput {
     var out: VertexOutput;
     out.texture_coordinates = vertex_input.texture_coordinates;
+    out.color = vertex_input.color;
     let position = vec3<f32>(vertex_input.position.x, vertex_input.position.y, 1.0);
     out.clip_position = vec4<f32>(uniform_buffer.affine2 * position, 1.0, 1.0);
+    out.world_position = uniform_buffer.model_affine2 * position;
+    out.tangent = normalize(uniform_buffer.model_affine2[0]);
+    out.bitangent = normalize(uniform_buffer.model_affine2[1]);
     return out;
 }
 
 [[stage(fragment)]]
 fn main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-    let texture_sample = textureSample(texture, sampler_type, in.texture_coordinates);
-    return texture_sample * uniform_buffer.opacity;
-}
\ No newline at end of file
+    let texture_sample = textureSample(texture, sampler_type, in.texture_coordinates) * in.color;
+    let normal_sample = textureSample(normal_map, normal_map_sampler, in.texture_coordinates);
+    if (uniform_buffer.normal_mapped == 0u) {
+        return texture_sample * uniform_buffer.opacity;
+    }
+
+    let tangent_normal = normal_sample.xyz * 2.0 - vec3<f32>(1.0, 1.0, 1.0);
+    let normal = normalize(vec3<f32>(
+        in.tangent * tangent_normal.x + in.bitangent * tangent_normal.y,
+        tangent_normal.z,
+    ));
+
+    var light_color: vec3<f32> = uniform_buffer.ambient_light.xyz;
+    var i: u32 = 0u;
+    loop {
+        if (i >= uniform_buffer.light_count) {
+            break;
+        }
+        let light = uniform_buffer.lights[i];
+        let to_light = vec3<f32>(light.position - in.world_position, light.height);
+        let attenuation = clamp(1.0 - length(to_light) / light.radius, 0.0, 1.0);
+        let diffuse = max(dot(normal, normalize(to_light)), 0.0);
+        light_color = light_color + light.color.xyz * diffuse * attenuation * attenuation;
+        i = i + 1u;
+    }
+
+    let lit = vec4<f32>(texture_sample.xyz * light_color, texture_sample.w);
+    return lit * uniform_buffer.opacity;
+}